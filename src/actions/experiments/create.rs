@@ -78,7 +78,8 @@ impl Action for CreateExperiment {
             )?;
 
             for krate in &crates {
-                let skipped = !self.ignore_blacklist && ctx.config.should_skip(krate);
+                let skipped =
+                    !self.ignore_blacklist && ctx.config.should_skip_in_mode(krate, self.mode);
                 transaction.execute(
                     "INSERT INTO experiment_crates (experiment, crate, skipped, status) VALUES (?1, ?2, ?3, ?4);",
                     &[&self.name, &krate.id(), &skipped, &Status::Queued.to_string()],
@@ -210,9 +211,7 @@ mod tests {
             "build-pass".into(),
             CrateConfig {
                 skip: true,
-                skip_tests: false,
-                quiet: false,
-                broken: false,
+                ..Default::default()
             },
         );
         let ctx = ActionsCtx::new(&db, &config);
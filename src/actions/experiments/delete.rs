@@ -2,6 +2,7 @@ use crate::actions::{experiments::ExperimentError, Action, ActionsCtx};
 use crate::db::QueryUtils;
 use crate::experiments::Experiment;
 use crate::prelude::*;
+use crate::results::{open_log_store, DatabaseDB, DeleteResults};
 
 pub struct DeleteExperiment {
     pub name: String,
@@ -9,9 +10,15 @@ pub struct DeleteExperiment {
 
 impl Action for DeleteExperiment {
     fn apply(self, ctx: &ActionsCtx) -> Fallible<()> {
-        if !Experiment::exists(&ctx.db, &self.name)? {
-            return Err(ExperimentError::NotFound(self.name).into());
-        }
+        let ex = match Experiment::get(&ctx.db, &self.name)? {
+            Some(ex) => ex,
+            None => return Err(ExperimentError::NotFound(self.name).into()),
+        };
+
+        // The logs are shared between experiments and might live outside of the database, so the
+        // results are deleted through the log store to remove the logs not used anymore
+        let logs = open_log_store(ctx.config, ctx.db)?;
+        DatabaseDB::with_log_store(ctx.db, logs).delete_all_results(&ex)?;
 
         // This will also delete all the data related to this experiment, thanks to the foreign
         // keys in the SQLite database
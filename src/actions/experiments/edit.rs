@@ -64,6 +64,17 @@ impl Action for EditExperiment {
                 }
             }
 
+            // Try to update the mode
+            // The list of skipped crates will be recalculated afterwards
+            if let Some(mode) = self.mode {
+                let changes = t.execute(
+                    "UPDATE experiments SET mode = ?1 WHERE name = ?2;",
+                    &[&mode.to_str(), &self.name],
+                )?;
+                assert_eq!(changes, 1);
+                ex.mode = mode;
+            }
+
             // Try to update the ignore_blacklist field
             // The list of skipped crates will be recalculated afterwards
             if let Some(ignore_blacklist) = self.ignore_blacklist {
@@ -76,14 +87,15 @@ impl Action for EditExperiment {
             }
 
             // Try to update the list of crates
-            // This is also done if ignore_blacklist is changed to recalculate the skipped crates
+            // This is also done if ignore_blacklist or the mode are changed to recalculate the
+            // skipped crates
             let new_crates = if let Some(crates) = self.crates {
                 Some(crate::crates::lists::get_crates(
                     &crates,
                     &ctx.db,
                     &ctx.config,
                 )?)
-            } else if self.ignore_blacklist.is_some() {
+            } else if self.ignore_blacklist.is_some() || self.mode.is_some() {
                 Some(ex.get_crates(&ctx.db)?)
             } else {
                 None
@@ -102,23 +114,14 @@ impl Action for EditExperiment {
                         &[
                             &self.name,
                             &krate.id(),
-                            &(!ex.ignore_blacklist && ctx.config.should_skip(krate)),
+                            &(!ex.ignore_blacklist
+                                && ctx.config.should_skip_in_mode(krate, ex.mode)),
                             &Status::Queued.to_string(),
                         ],
                     )?;
                 }
             }
 
-            // Try to update the mode
-            if let Some(mode) = self.mode {
-                let changes = t.execute(
-                    "UPDATE experiments SET mode = ?1 WHERE name = ?2;",
-                    &[&mode.to_str(), &self.name],
-                )?;
-                assert_eq!(changes, 1);
-                ex.mode = mode;
-            }
-
             // Try to update the cap_lints
             if let Some(cap_lints) = self.cap_lints {
                 let changes = t.execute(
@@ -278,9 +281,7 @@ mod tests {
             "build-pass".into(),
             CrateConfig {
                 skip: true,
-                skip_tests: false,
-                quiet: false,
-                broken: false,
+                ..Default::default()
             },
         );
         let ctx = ActionsCtx::new(&db, &config);
@@ -312,6 +313,45 @@ mod tests {
         assert!(is_skipped(&db, "foo", "build-pass"));
     }
 
+    #[test]
+    fn test_skip_modes() {
+        fn is_skipped(db: &Database, krate: &Crate) -> bool {
+            db.get_row(
+                "SELECT skipped FROM experiment_crates WHERE experiment = ?1 AND crate = ?2;",
+                &[&"foo", &krate.id().as_str()],
+                |row| row.get("skipped"),
+            )
+            .unwrap()
+            .unwrap()
+        }
+
+        let db = Database::temp().unwrap();
+        let mut config = Config::default();
+        config.local_crates.insert(
+            "build-pass".into(),
+            CrateConfig {
+                skip_modes: vec![Mode::Clippy],
+                ..Default::default()
+            },
+        );
+        let ctx = ActionsCtx::new(&db, &config);
+        let krate = Crate::Local("build-pass".into());
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        CreateExperiment::dummy("foo").apply(&ctx).unwrap();
+        assert!(!is_skipped(&db, &krate));
+
+        // Changing the mode recalculates the skipped crates
+        EditExperiment {
+            mode: Some(Mode::Clippy),
+            ..EditExperiment::dummy("foo")
+        }
+        .apply(&ctx)
+        .unwrap();
+        assert!(is_skipped(&db, &krate));
+    }
+
     #[test]
     fn test_duplicate_toolchains() {
         let db = Database::temp().unwrap();
@@ -2,7 +2,7 @@ use crate::agent::Capabilities;
 use crate::crates::Crate;
 use crate::experiments::Experiment;
 use crate::prelude::*;
-use crate::results::TestResult;
+use crate::results::{Diagnostic, Environment, TaskTiming, TestResult};
 use crate::server::api_types::{AgentConfig, ApiResponse, CraterToken};
 use crate::toolchain::Toolchain;
 use crate::utils;
@@ -152,6 +152,9 @@ impl AgentApi {
         toolchain: &Toolchain,
         log: &[u8],
         result: &TestResult,
+        diagnostics: &[Diagnostic],
+        classifier: Option<&str>,
+        timing: &TaskTiming,
         version: Option<(&Crate, &Crate)>,
     ) -> Fallible<()> {
         self.retry(|this| {
@@ -165,6 +168,9 @@ impl AgentApi {
                             "toolchain": toolchain,
                             "result": result,
                             "log": base64::encode(log),
+                            "diagnostics": diagnostics,
+                            "classifier": classifier,
+                            "timing": timing,
                         },
                     ],
                     "version": version
@@ -175,6 +181,20 @@ impl AgentApi {
         })
     }
 
+    pub fn record_environment(&self, ex: &Experiment, environment: &Environment) -> Fallible<()> {
+        self.retry(|this| {
+            let _: bool = this
+                .build_request(Method::POST, "record-environment")
+                .json(&json!({
+                    "experiment-name": ex.name,
+                    "environment": environment,
+                }))
+                .send()?
+                .to_api_response()?;
+            Ok(())
+        })
+    }
+
     pub fn heartbeat(&self) -> Fallible<()> {
         self.retry(|this| {
             let _: bool = this
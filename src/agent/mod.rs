@@ -111,6 +111,7 @@ fn run_experiment(
     workspace: &Workspace,
     db: &ResultsUploader,
     threads_count: usize,
+    docker_image: &str,
     past_experiment: &mut Option<String>,
 ) -> Result<(), (Option<Experiment>, Error)> {
     let (ex, crates) = agent.experiment().map_err(|e| (None, e))?;
@@ -133,28 +134,45 @@ fn run_experiment(
         }
     }
 
-    crate::runner::run_ex(&ex, workspace, &crates, db, threads_count, &agent.config)
-        .map_err(|err| (Some(ex), err))?;
+    crate::runner::run_ex(
+        &ex,
+        workspace,
+        &crates,
+        db,
+        threads_count,
+        &agent.config,
+        docker_image,
+    )
+    .map_err(|err| (Some(ex), err))?;
     Ok(())
 }
 
+/// Runs the experiments assigned by the server. The workspace is created once the configuration
+/// is received from the server, as the default timeouts of its commands depend on it.
 pub fn run(
     url: &str,
     token: &str,
     threads_count: usize,
     caps: &Capabilities,
-    workspace: &Workspace,
+    workspace: impl FnOnce(&Config) -> Fallible<Workspace>,
+    docker_image: &str,
 ) -> Fallible<()> {
     let agent = Agent::new(url, token, caps)?;
+    let workspace = workspace(&agent.config)?;
     let db = results::ResultsUploader::new(&agent.api);
 
     run_heartbeat(url, token);
 
     let mut past_experiment = None;
     loop {
-        if let Err((ex, err)) =
-            run_experiment(&agent, workspace, &db, threads_count, &mut past_experiment)
-        {
+        if let Err((ex, err)) = run_experiment(
+            &agent,
+            &workspace,
+            &db,
+            threads_count,
+            docker_image,
+            &mut past_experiment,
+        ) {
             utils::report_failure(&err);
             if let Some(ex) = ex {
                 if let Err(e) = agent
@@ -3,9 +3,11 @@ use crate::config::Config;
 use crate::crates::Crate;
 use crate::experiments::Experiment;
 use crate::prelude::*;
-use crate::results::{EncodingType, TestResult, WriteResults};
+use crate::results::{
+    capture_timed, Diagnostic, EncodingType, Environment, TestResult, WriteResults,
+};
 use crate::toolchain::Toolchain;
-use rustwide::logging::{self, LogStorage};
+use rustwide::logging::LogStorage;
 use std::collections::{hash_map::Entry::Occupied, HashMap};
 use std::sync::{Arc, Mutex};
 
@@ -13,6 +15,7 @@ use std::sync::{Arc, Mutex};
 pub struct ResultsUploader<'a> {
     api: &'a AgentApi,
     versions: Arc<Mutex<HashMap<Crate, (Crate, bool)>>>,
+    diagnostics: Arc<Mutex<HashMap<(Crate, Toolchain), Vec<Diagnostic>>>>,
 }
 
 impl<'a> ResultsUploader<'a> {
@@ -20,6 +23,7 @@ impl<'a> ResultsUploader<'a> {
         ResultsUploader {
             api,
             versions: Arc::new(Mutex::new(HashMap::new())),
+            diagnostics: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 }
@@ -43,6 +47,26 @@ impl<'a> WriteResults for ResultsUploader<'a> {
         Ok(())
     }
 
+    fn record_environment(&self, ex: &Experiment, env: &Environment) -> Fallible<()> {
+        info!("sending the environment to the crater server...");
+        self.api.record_environment(ex, env)
+    }
+
+    fn record_diagnostics(
+        &self,
+        _ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        diagnostics: Vec<Diagnostic>,
+    ) -> Fallible<()> {
+        // Diagnostics are sent to the server along with the result
+        self.diagnostics
+            .lock()
+            .unwrap()
+            .insert((krate.clone(), toolchain.clone()), diagnostics);
+        Ok(())
+    }
+
     fn record_result<F>(
         &self,
         ex: &Experiment,
@@ -57,8 +81,17 @@ impl<'a> WriteResults for ResultsUploader<'a> {
         F: FnOnce() -> Fallible<TestResult>,
     {
         let storage = existing_logs.unwrap_or_else(|| LogStorage::from(config));
-        let result = logging::capture(&storage, f)?;
+        let res = capture_timed(&storage, f);
+        // The diagnostics are removed even if the task failed, to avoid leaking them
+        let diagnostics = self
+            .diagnostics
+            .lock()
+            .unwrap()
+            .remove(&(krate.clone(), toolchain.clone()))
+            .unwrap_or_default();
+        let (result, timing) = res?;
         let output = storage.to_string();
+        let classifier = config.classify(&output).map(|c| c.label.as_str());
 
         let mut updated = None;
         let mut new_version = None;
@@ -87,6 +120,9 @@ impl<'a> WriteResults for ResultsUploader<'a> {
             toolchain,
             output.as_bytes(),
             &result,
+            &diagnostics,
+            classifier,
+            &timing,
             new_version.map(|new| (krate, new)),
         )?;
 
@@ -64,11 +64,15 @@ load_files! {
 
         "ui/queue.html",
         "ui/experiment.html",
+        "ui/triage.html",
+        "ui/crate-history.html",
 
         "ui/404.html",
         "ui/500.html",
 
         "report/layout.html",
+        "report/diagnostics.html",
+        "report/diff.html",
         "report/downloads.html",
         "report/results.html",
     ],
@@ -11,12 +11,12 @@
 
 use crater::actions::{self, Action, ActionsCtx};
 use crater::agent::{self, Capabilities};
-use crater::config::Config;
+use crater::config::{Config, SandboxConfig};
 use crater::crates::Crate;
 use crater::db::Database;
 use crater::experiments::{Assignee, CapLints, DeferredCrateSelect, Experiment, Mode, Status};
-use crater::report;
-use crater::results::{DatabaseDB, DeleteResults};
+use crater::report::{self, ReportFormat};
+use crater::results::{open_log_store, DatabaseDB, DeleteResults};
 use crater::runner;
 use crater::server;
 use crater::toolchain::Toolchain;
@@ -29,6 +29,12 @@ use std::str::FromStr;
 use std::time::Duration;
 use structopt::clap::AppSettings;
 
+// The docker image used when --docker-env is not passed, the same one rustwide defaults to
+#[cfg(not(windows))]
+const DEFAULT_DOCKER_ENV: &str = "rustops/crates-build-env";
+#[cfg(windows)]
+const DEFAULT_DOCKER_ENV: &str = "rustops/crates-build-env-windows";
+
 // An experiment name
 #[derive(Debug, Clone)]
 pub struct Ex(String);
@@ -75,6 +81,15 @@ fn default_capabilities_for_target() -> Capabilities {
     Capabilities::new(caps)
 }
 
+/// The report formats to generate, defaulting to HTML and Markdown when none was selected.
+fn report_formats(selected: &[ReportFormat]) -> Vec<ReportFormat> {
+    if selected.is_empty() {
+        ReportFormat::defaults()
+    } else {
+        selected.to_vec()
+    }
+}
+
 #[derive(structopt_derive::StructOpt)]
 #[allow(clippy::large_enum_variant)]
 #[structopt(
@@ -246,6 +261,16 @@ pub enum Crater {
         force: bool,
         #[structopt(name = "output-templates", long = "output-templates")]
         output_templates: bool,
+        #[structopt(
+            name = "format",
+            long = "format",
+            help = "Formats to generate the report in (html and markdown by default).",
+            raw(
+                possible_values = "ReportFormat::possible_values()",
+                use_delimiter = "true"
+            )
+        )]
+        formats: Vec<ReportFormat>,
     },
 
     #[structopt(name = "publish-report", about = "publish the experiment report to S3")]
@@ -263,6 +288,58 @@ pub enum Crater {
         force: bool,
         #[structopt(name = "output-templates", long = "output-templates")]
         output_templates: bool,
+        #[structopt(
+            name = "format",
+            long = "format",
+            help = "Formats to generate the report in (html and markdown by default).",
+            raw(
+                possible_values = "ReportFormat::possible_values()",
+                use_delimiter = "true"
+            )
+        )]
+        formats: Vec<ReportFormat>,
+    },
+
+    #[structopt(
+        name = "diff-reports",
+        about = "show the crates whose result changed between two reports"
+    )]
+    DiffReports {
+        #[structopt(
+            name = "old",
+            help = "The old experiment, or the path to the results.json of its report."
+        )]
+        old: report::ReportSource,
+        #[structopt(
+            name = "new",
+            help = "The new experiment, or the path to the results.json of its report."
+        )]
+        new: report::ReportSource,
+        #[structopt(name = "destination")]
+        dest: Dest,
+        #[structopt(name = "output-templates", long = "output-templates")]
+        output_templates: bool,
+    },
+
+    #[structopt(
+        name = "broken-crates",
+        about = "list the crates that never built or passed their tests in the recent experiments"
+    )]
+    BrokenCrates {
+        #[structopt(
+            name = "experiments",
+            long = "experiments",
+            default_value = "10",
+            help = "How many of the last completed experiments to look at."
+        )]
+        experiments: usize,
+        #[structopt(
+            name = "min-experiments",
+            long = "min-experiments",
+            default_value = "2",
+            help = "How many experiments a crate must have failed in to be listed."
+        )]
+        min_experiments: usize,
     },
 
     #[structopt(name = "server")]
@@ -435,8 +512,10 @@ impl Crater {
                 actions::DeleteExperiment { name: ex.0.clone() }.apply(&ctx)?;
             }
             Crater::DeleteAllResults { ref ex } => {
+                let config = Config::load()?;
                 let db = Database::open()?;
-                let result_db = DatabaseDB::new(&db);
+                let logs = open_log_store(&config, &db)?;
+                let result_db = DatabaseDB::with_log_store(&db, logs);
 
                 if let Some(mut experiment) = Experiment::get(&db, &ex.0)? {
                     result_db.delete_all_results(&experiment)?;
@@ -450,8 +529,10 @@ impl Crater {
                 ref tc,
                 ref krate,
             } => {
+                let config = Config::load()?;
                 let db = Database::open()?;
-                let result_db = DatabaseDB::new(&db);
+                let logs = open_log_store(&config, &db)?;
+                let result_db = DatabaseDB::with_log_store(&db, logs);
 
                 if let Some(mut experiment) = Experiment::get(&db, &ex.0)? {
                     if let Some(tc) = tc {
@@ -491,10 +572,12 @@ impl Crater {
                         other => bail!("can't run an experiment with status {}", other.to_str()),
                     }
 
-                    let result_db = DatabaseDB::new(&db);
+                    let logs = open_log_store(&config, &db)?;
+                    let result_db = DatabaseDB::with_log_store(&db, logs);
 
-                    let workspace = self
-                        .workspace(docker_env.as_ref().map(|s| s.as_str()), fast_workspace_init)?;
+                    let docker_env = docker_env.as_deref().unwrap_or(DEFAULT_DOCKER_ENV);
+                    let workspace =
+                        self.workspace(docker_env, fast_workspace_init, &config.sandbox)?;
                     workspace.purge_all_build_dirs()?;
                     let res = runner::run_ex(
                         &experiment,
@@ -503,6 +586,7 @@ impl Crater {
                         &result_db,
                         threads,
                         &config,
+                        docker_env,
                     );
                     workspace.purge_all_build_dirs()?;
                     res?;
@@ -517,6 +601,7 @@ impl Crater {
                 ref dest,
                 force,
                 output_templates,
+                ref formats,
             } => {
                 let config = Config::load()?;
                 let db = Database::open()?;
@@ -534,13 +619,15 @@ impl Crater {
                         ),
                     }
 
-                    let result_db = DatabaseDB::new(&db);
+                    let logs = open_log_store(&config, &db)?;
+                    let result_db = DatabaseDB::with_log_store(&db, logs);
                     let res = report::gen(
                         &result_db,
                         &experiment,
                         &experiment.get_crates(&db)?,
                         &report::FileWriter::create(dest.0.clone())?,
                         &config,
+                        &report_formats(formats),
                         output_templates,
                     );
 
@@ -559,6 +646,7 @@ impl Crater {
                 ref s3_prefix,
                 force,
                 output_templates,
+                ref formats,
             } => {
                 let config = Config::load()?;
                 let db = Database::open()?;
@@ -576,7 +664,8 @@ impl Crater {
                         ),
                     }
 
-                    let result_db = DatabaseDB::new(&db);
+                    let logs = open_log_store(&config, &db)?;
+                    let result_db = DatabaseDB::with_log_store(&db, logs);
                     let client = report::get_client_for_bucket(&s3_prefix.bucket)?;
 
                     let res = report::gen(
@@ -585,6 +674,7 @@ impl Crater {
                         &experiment.get_crates(&db)?,
                         &report::S3Writer::create(client, s3_prefix.clone())?,
                         &config,
+                        &report_formats(formats),
                         output_templates,
                     );
 
@@ -598,6 +688,35 @@ impl Crater {
                     bail!("missing experiment: {}", ex.0);
                 }
             }
+            Crater::DiffReports {
+                ref old,
+                ref new,
+                ref dest,
+                output_templates,
+            } => {
+                let config = Config::load()?;
+                let db = Database::open()?;
+
+                report::gen_diff(
+                    old,
+                    new,
+                    &db,
+                    &config,
+                    &report::FileWriter::create(dest.0.clone())?,
+                    output_templates,
+                )?;
+            }
+            Crater::BrokenCrates {
+                experiments,
+                min_experiments,
+            } => {
+                let config = Config::load()?;
+                let db = Database::open()?;
+
+                let broken =
+                    report::find_broken_crates(&db, &config, experiments, min_experiments)?;
+                print!("{}", report::render_skip_list(&broken)?);
+            }
             Crater::Server { bind } => {
                 let config = Config::load()?;
                 server::run(
@@ -621,13 +740,14 @@ impl Crater {
                 };
                 caps.extend(capabilities.clone().into_iter());
 
+                let docker_env = docker_env.as_deref().unwrap_or(DEFAULT_DOCKER_ENV);
                 agent::run(
                     url,
                     token,
                     threads,
                     &caps,
-                    &self
-                        .workspace(docker_env.as_ref().map(|s| s.as_str()), fast_workspace_init)?,
+                    |config| self.workspace(docker_env, fast_workspace_init, &config.sandbox),
+                    docker_env,
                 )?;
             }
             Crater::DumpTasksGraph { ref dest, ref ex } => {
@@ -650,20 +770,26 @@ impl Crater {
         Ok(())
     }
 
-    fn workspace(&self, docker_env: Option<&str>, fast_init: bool) -> Result<Workspace, Error> {
-        let mut builder = WorkspaceBuilder::new(&crater::dirs::WORK_DIR, &crater::USER_AGENT)
-            .fast_init(fast_init)
-            .fetch_registry_index_during_builds(false)
-            .command_timeout(Some(Duration::from_secs(15 * 60)))
-            .command_no_output_timeout(Some(Duration::from_secs(5 * 60)))
-            .running_inside_docker(std::env::var("CRATER_INSIDE_DOCKER").is_ok());
-        if let Some(env) = docker_env {
-            builder = builder.sandbox_image(if env.contains('/') {
-                SandboxImage::remote(env)?
-            } else {
-                SandboxImage::local(env)?
-            });
-        }
-        Ok(builder.init()?)
+    fn workspace(
+        &self,
+        docker_env: &str,
+        fast_init: bool,
+        sandbox: &SandboxConfig,
+    ) -> Result<Workspace, Error> {
+        let sandbox_image = if docker_env.contains('/') {
+            SandboxImage::remote(docker_env)?
+        } else {
+            SandboxImage::local(docker_env)?
+        };
+        Ok(
+            WorkspaceBuilder::new(&crater::dirs::WORK_DIR, &crater::USER_AGENT)
+                .fast_init(fast_init)
+                .fetch_registry_index_during_builds(false)
+                .command_timeout(Some(Duration::from_secs(sandbox.total_timeout)))
+                .command_no_output_timeout(Some(Duration::from_secs(sandbox.no_output_timeout)))
+                .running_inside_docker(std::env::var("CRATER_INSIDE_DOCKER").is_ok())
+                .sandbox_image(sandbox_image)
+                .init()?,
+        )
     }
 }
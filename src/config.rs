@@ -1,15 +1,23 @@
 use crate::crates::Crate;
+use crate::experiments::Mode;
 use crate::prelude::*;
+use crate::utils::glob;
 use crate::utils::size::Size;
+use chrono::{NaiveDate, Utc};
 use log::LevelFilter;
 use regex::Regex;
 use rustwide::logging::LogStorage;
-use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
 use std::ffi::OsStr;
 use std::fs::File;
 use std::io::Read;
-use std::path::PathBuf;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use url::Url;
 
 fn default_config_file() -> PathBuf {
     env::var_os("CRATER_CONFIG")
@@ -21,6 +29,7 @@ fn default_config_file() -> PathBuf {
 #[fail(display = "the configuration file has errors")]
 pub struct BadConfig;
 
+#[cfg_attr(test, derive(Default))]
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct CrateConfig {
@@ -28,16 +37,181 @@ pub struct CrateConfig {
     pub skip: bool,
     #[serde(default = "default_false")]
     pub skip_tests: bool,
+    /// The modes of the experiments the crate is skipped in.
+    #[serde(default)]
+    pub skip_modes: Vec<Mode>,
     #[serde(default = "default_false")]
     pub quiet: bool,
     #[serde(default = "default_false")]
     pub broken: bool,
+    #[serde(default = "default_false")]
+    pub slow: bool,
+    /// Overrides the memory limit of the sandbox.
+    #[serde(default)]
+    pub memory_limit: Option<Size>,
+    /// Overrides the number of seconds a command can run without output before being killed.
+    #[serde(default)]
+    pub no_output_timeout: Option<u64>,
+    /// Overrides the number of seconds a command can run before being killed.
+    #[serde(default)]
+    pub total_timeout: Option<u64>,
+    /// Why the crate is configured this way.
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// When the entry was added, as a `YYYY-MM-DD` string.
+    #[serde(default)]
+    pub added: Option<NaiveDate>,
+    /// When the entry should be checked again, as a `YYYY-MM-DD` string.
+    #[serde(default)]
+    pub expires: Option<NaiveDate>,
+}
+
+/// The entries of a crate section, keyed by name or by pattern. The patterns are split and sorted
+/// once when the section is loaded, as every crate of an experiment is matched against them.
+#[derive(Clone, Default)]
+pub struct CrateRules {
+    entries: HashMap<String, CrateConfig>,
+    /// The pattern keys, from the most specific one. Patterns as specific as each other are sorted
+    /// alphabetically.
+    patterns: Vec<glob::Pattern>,
+}
+
+impl CrateRules {
+    fn new(entries: HashMap<String, CrateConfig>) -> Self {
+        let mut rules = CrateRules {
+            entries,
+            patterns: Vec::new(),
+        };
+        rules.update_patterns();
+        rules
+    }
+
+    pub fn insert(&mut self, name: String, config: CrateConfig) -> Option<CrateConfig> {
+        let previous = self.entries.insert(name, config);
+        self.update_patterns();
+        previous
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut CrateConfig> {
+        self.entries.get_mut(name)
+    }
+
+    fn update_patterns(&mut self) {
+        let mut patterns = self
+            .entries
+            .keys()
+            .filter(|key| glob::is_pattern(key))
+            .map(|key| glob::Pattern::new(key))
+            .collect::<Vec<_>>();
+        patterns.sort_by(|a, b| {
+            (Reverse(a.specificity()), a.as_str()).cmp(&(Reverse(b.specificity()), b.as_str()))
+        });
+        self.patterns = patterns;
+    }
+
+    /// Returns the entry applying to the name: an exact match is always preferred over patterns,
+    /// otherwise the most specific pattern matching the name is used.
+    fn find(&self, name: &str) -> Option<(&str, &CrateConfig)> {
+        if let Some((key, config)) = self.entries.get_key_value(name) {
+            return Some((key.as_str(), config));
+        }
+        self.matching_patterns(name).next()
+    }
+
+    /// Returns the patterns matching the name, from the most specific one.
+    fn matching_patterns<'a: 'b, 'b>(
+        &'a self,
+        name: &'b str,
+    ) -> impl Iterator<Item = (&'a str, &'a CrateConfig)> + 'b {
+        self.patterns
+            .iter()
+            .filter(move |pattern| pattern.matches(name))
+            .map(move |pattern| (pattern.as_str(), &self.entries[pattern.as_str()]))
+    }
+}
+
+impl Deref for CrateRules {
+    type Target = HashMap<String, CrateConfig>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.entries
+    }
+}
+
+impl Serialize for CrateRules {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.entries.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CrateRules {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        HashMap::deserialize(deserializer).map(CrateRules::new)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ClassifierConfig {
+    pub label: String,
+    #[serde(default = "default_false")]
+    pub spurious: bool,
 }
 
 fn default_false() -> bool {
     false
 }
 
+/// The regex of a classifier, compiled once when the configuration is loaded.
+#[derive(Clone, Debug)]
+pub struct ClassifierRegex(Regex);
+
+impl ClassifierRegex {
+    pub fn new(regex: &str) -> Fallible<Self> {
+        Ok(ClassifierRegex(Regex::new(regex)?))
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    pub fn is_match(&self, log: &str) -> bool {
+        self.0.is_match(log)
+    }
+}
+
+impl PartialEq for ClassifierRegex {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for ClassifierRegex {}
+
+impl PartialOrd for ClassifierRegex {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ClassifierRegex {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Serialize for ClassifierRegex {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serde_regex::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ClassifierRegex {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        serde_regex::deserialize(deserializer).map(ClassifierRegex)
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct ServerConfig {
@@ -76,6 +250,51 @@ pub struct SandboxConfig {
     pub memory_limit: Size,
     pub build_log_max_size: Size,
     pub build_log_max_lines: usize,
+    /// The number of seconds a command can run without output before being killed.
+    #[serde(default = "default_no_output_timeout")]
+    pub no_output_timeout: u64,
+    /// The number of seconds a command can run before being killed.
+    #[serde(default = "default_total_timeout")]
+    pub total_timeout: u64,
+}
+
+fn default_no_output_timeout() -> u64 {
+    5 * 60
+}
+
+fn default_total_timeout() -> u64 {
+    15 * 60
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", tag = "backend")]
+pub enum LogStorageConfig {
+    Database,
+    Filesystem { path: PathBuf },
+    S3 { prefix: String },
+}
+
+impl Default for LogStorageConfig {
+    fn default() -> Self {
+        LogStorageConfig::Database
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", tag = "backend")]
+pub enum ReportStorageConfig {
+    S3,
+    Filesystem {
+        path: PathBuf,
+        #[serde(rename = "public-url")]
+        public_url: String,
+    },
+}
+
+impl Default for ReportStorageConfig {
+    fn default() -> Self {
+        ReportStorageConfig::S3
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -88,11 +307,23 @@ pub struct ChunkConfig {
 #[serde(rename_all = "kebab-case")]
 pub struct Config {
     pub demo_crates: DemoCrates,
-    pub crates: HashMap<String, CrateConfig>,
-    pub github_repos: HashMap<String, CrateConfig>,
-    pub local_crates: HashMap<String, CrateConfig>,
+    pub crates: CrateRules,
+    pub github_repos: CrateRules,
+    pub local_crates: CrateRules,
+    /// Crates fetched from a git repository, keyed by the URL of the repository.
+    #[serde(default)]
+    pub git_repos: CrateRules,
+    /// Crates built from a path on the machine running crater, keyed by the path.
+    #[serde(default)]
+    pub path_crates: CrateRules,
     pub server: ServerConfig,
     pub sandbox: SandboxConfig,
+    #[serde(default)]
+    pub classifiers: BTreeMap<ClassifierRegex, ClassifierConfig>,
+    #[serde(default)]
+    pub log_storage: LogStorageConfig,
+    #[serde(default)]
+    pub report_storage: ReportStorageConfig,
 }
 
 impl Config {
@@ -109,19 +340,38 @@ impl Config {
         Ok(buffer)
     }
 
+    fn crate_rule(&self, c: &Crate) -> Option<(&str, &CrateConfig)> {
+        let section = match *c {
+            Crate::Registry(_) => &self.crates,
+            Crate::GitHub(_) => &self.github_repos,
+            Crate::Local(_) => &self.local_crates,
+            Crate::Git(_) => &self.git_repos,
+            Crate::Path(_) => &self.path_crates,
+        };
+        section.find(&c.unversioned_name())
+    }
+
     fn crate_config(&self, c: &Crate) -> Option<&CrateConfig> {
-        match *c {
-            Crate::Registry(ref details) => self.crates.get(&details.name),
-            Crate::GitHub(ref repo) => self.github_repos.get(&repo.slug()),
-            Crate::Local(ref name) => self.local_crates.get(name),
-            Crate::Git(_) | Crate::Path(_) => unimplemented!("unsupported crate"),
-        }
+        self.crate_rule(c).map(|(_, config)| config)
+    }
+
+    /// Returns the key of the entry applying to the crate, which might be a pattern.
+    pub fn crate_rule_name(&self, c: &Crate) -> Option<&str> {
+        self.crate_rule(c).map(|(name, _)| name)
     }
 
     pub fn should_skip(&self, c: &Crate) -> bool {
         self.crate_config(c).map(|c| c.skip).unwrap_or(false)
     }
 
+    /// Whether the crate shouldn't be run in experiments with the mode, either because it's
+    /// skipped entirely or only in that mode.
+    pub fn should_skip_in_mode(&self, c: &Crate, mode: Mode) -> bool {
+        self.crate_config(c)
+            .map(|c| c.skip || c.skip_modes.contains(&mode))
+            .unwrap_or(false)
+    }
+
     pub fn should_skip_tests(&self, c: &Crate) -> bool {
         self.crate_config(c).map(|c| c.skip_tests).unwrap_or(false)
     }
@@ -134,6 +384,77 @@ impl Config {
         self.crate_config(c).map(|c| c.broken).unwrap_or(false)
     }
 
+    pub fn is_slow(&self, c: &Crate) -> bool {
+        self.crate_config(c).map(|c| c.slow).unwrap_or(false)
+    }
+
+    pub fn memory_limit(&self, c: &Crate) -> Size {
+        self.crate_config(c)
+            .and_then(|c| c.memory_limit)
+            .unwrap_or(self.sandbox.memory_limit)
+    }
+
+    /// Returns how long commands can run without output before being killed. The runner doesn't
+    /// apply this timeout to quiet crates.
+    pub fn no_output_timeout(&self, c: &Crate) -> Duration {
+        let seconds = self
+            .crate_config(c)
+            .and_then(|c| c.no_output_timeout)
+            .unwrap_or(self.sandbox.no_output_timeout);
+        Duration::from_secs(seconds)
+    }
+
+    /// Returns how long commands can run before being killed. Crates marked as slow are given
+    /// twice the default timeout, unless they have their own.
+    pub fn total_timeout(&self, c: &Crate) -> Duration {
+        let config = self.crate_config(c);
+        let seconds = match config.and_then(|c| c.total_timeout) {
+            Some(seconds) => seconds,
+            None if config.map(|c| c.slow).unwrap_or(false) => self.sandbox.total_timeout * 2,
+            None => self.sandbox.total_timeout,
+        };
+        Duration::from_secs(seconds)
+    }
+
+    /// Returns the reason the crate is configured the way it is, if any.
+    pub fn crate_reason(&self, c: &Crate) -> Option<&str> {
+        self.crate_config(c).and_then(|c| c.reason.as_deref())
+    }
+
+    /// Returns the entries of the crate sections that expired before `today`, as
+    /// `(name, expiry date)`.
+    pub fn expired_entries(&self, today: NaiveDate) -> Vec<(&str, NaiveDate)> {
+        let mut expired = self
+            .crates
+            .iter()
+            .chain(self.github_repos.iter())
+            .chain(self.local_crates.iter())
+            .chain(self.git_repos.iter())
+            .chain(self.path_crates.iter())
+            .filter_map(|(name, config)| match config.expires {
+                Some(expires) if expires < today => Some((name.as_str(), expires)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        expired.sort();
+        expired
+    }
+
+    /// Returns the first classifier whose regex matches the log, trying them in the alphabetical
+    /// order of their regexes.
+    pub fn classify(&self, log: &str) -> Option<&ClassifierConfig> {
+        self.classifiers
+            .iter()
+            .find(|(regex, _)| regex.is_match(log))
+            .map(|(_, classifier)| classifier)
+    }
+
+    pub fn is_spurious_label(&self, label: &str) -> bool {
+        self.classifiers
+            .values()
+            .any(|classifier| classifier.label == label && classifier.spurious)
+    }
+
     pub fn demo_crates(&self) -> &DemoCrates {
         &self.demo_crates
     }
@@ -155,11 +476,21 @@ impl Config {
 
         let buffer = Self::load_as_string(filename)?;
         let mut has_errors = Self::check_for_dup_keys(&buffer).is_err();
+        // Invalid classifiers make the config fail to load, so they're checked beforehand to
+        // report all of them at once
+        if Self::check_for_bad_classifiers(&buffer).is_err() {
+            return Err(BadConfig.into());
+        }
         let cfg: Self = ::toml::from_str(&buffer)?;
         let db = crate::db::Database::open()?;
         let crates = crate::crates::lists::get_crates(&CrateSelect::Full, &db, &cfg)?;
         has_errors |= cfg.check_for_missing_crates(&crates).is_err();
         has_errors |= cfg.check_for_missing_repos(&crates).is_err();
+        has_errors |= cfg.check_for_bad_git_repos().is_err();
+        has_errors |= cfg.check_for_bad_path_crates().is_err();
+        has_errors |= cfg.check_for_bad_log_storage().is_err();
+        cfg.warn_about_expired_entries();
+        cfg.warn_about_overlapping_rules(&crates);
         if has_errors {
             Err(BadConfig.into())
         } else {
@@ -193,7 +524,18 @@ impl Config {
 
         let mut any_missing = false;
         for crate_name in self.crates.keys() {
-            if !list_of_crates.contains(&*crate_name) {
+            if glob::is_pattern(crate_name) {
+                if !list_of_crates
+                    .iter()
+                    .any(|name| glob::matches(crate_name, name))
+                {
+                    error!(
+                        "check-config failed: pattern `{}` doesn't match any crate.",
+                        crate_name
+                    );
+                    any_missing = true;
+                }
+            } else if !list_of_crates.contains(&*crate_name) {
                 error!(
                     "check-config failed: crate `{}` is not available.",
                     crate_name
@@ -225,7 +567,18 @@ impl Config {
 
         let mut any_missing = false;
         for repo_name in self.github_repos.keys() {
-            if !list_of_crates.contains(&*repo_name) {
+            if glob::is_pattern(repo_name) {
+                if !list_of_crates
+                    .iter()
+                    .any(|name| glob::matches(repo_name, name))
+                {
+                    error!(
+                        "check-config failed: pattern `{}` doesn't match any GitHub repo",
+                        repo_name
+                    );
+                    any_missing = true;
+                }
+            } else if !list_of_crates.contains(&*repo_name) {
                 error!(
                     "check-config failed: GitHub repo `{}` is missing",
                     repo_name
@@ -239,6 +592,155 @@ impl Config {
             Ok(())
         }
     }
+
+    fn check_for_bad_git_repos(&self) -> Fallible<()> {
+        let mut any_bad = false;
+        for url in self.git_repos.keys() {
+            if let Err(err) = Url::parse(url) {
+                error!(
+                    "check-config failed: git repo `{}` is not a valid URL: {}",
+                    url, err
+                );
+                any_bad = true;
+            }
+        }
+        if any_bad {
+            Err(BadConfig.into())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_for_bad_path_crates(&self) -> Fallible<()> {
+        let mut any_bad = false;
+        for path in self.path_crates.keys() {
+            if !Path::new(path).is_absolute() {
+                error!(
+                    "check-config failed: path crate `{}` is not an absolute path",
+                    path
+                );
+                any_bad = true;
+            }
+        }
+        if any_bad {
+            Err(BadConfig.into())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_for_bad_classifiers(buffer: &str) -> Fallible<()> {
+        let value: ::toml::Value = ::toml::from_str(buffer)?;
+        let classifiers = value.get("classifiers").and_then(|c| c.as_table());
+
+        let mut any_bad = false;
+        for regex in classifiers.iter().flat_map(|c| c.keys()) {
+            if let Err(err) = Regex::new(regex) {
+                error!(
+                    "check-config failed: classifier regex `{}` is invalid: {}",
+                    regex, err
+                );
+                any_bad = true;
+            }
+        }
+        if any_bad {
+            Err(BadConfig.into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Expired entries don't make the config invalid, they're only reminders to check whether the
+    /// crates are still broken.
+    fn warn_about_expired_entries(&self) {
+        for (name, expires) in self.expired_entries(Utc::today().naive_utc()) {
+            warn!(
+                "check-config: the entry of `{}` expired on {}, check whether it's still needed \
+                 (an experiment with --ignore-blacklist reports the entries to remove)",
+                name, expires
+            );
+        }
+    }
+
+    /// Overlapping rules don't make the config invalid, as exact matches are preferred over
+    /// patterns and the most specific pattern is used otherwise, but they're likely to be a mistake.
+    fn warn_about_overlapping_rules(&self, crates: &[Crate]) {
+        for (name, patterns) in self.shadowed_patterns() {
+            warn!(
+                "check-config: the entry of `{}` is used instead of the patterns `{}` matching it",
+                name,
+                patterns.join("`, `")
+            );
+        }
+
+        // The overlapping patterns, with an example of a crate they all match
+        let mut overlaps = BTreeMap::new();
+        for krate in crates {
+            let (section, name) = match krate {
+                Crate::Registry(details) => (&self.crates, details.name.clone()),
+                Crate::GitHub(repo) => (&self.github_repos, repo.slug()),
+                _ => continue,
+            };
+            if section.contains_key(&name) {
+                continue;
+            }
+            let patterns = section
+                .matching_patterns(&name)
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>();
+            if patterns.len() > 1 {
+                overlaps.entry(patterns).or_insert(name);
+            }
+        }
+
+        for (patterns, example) in overlaps {
+            warn!(
+                "check-config: the patterns `{}` all match `{}`, `{}` is used as it's the most \
+                 specific one",
+                patterns.join("`, `"),
+                example,
+                patterns[0]
+            );
+        }
+    }
+
+    /// Returns the exact entries of the crate sections also matched by patterns, with those
+    /// patterns from the most specific one.
+    fn shadowed_patterns(&self) -> Vec<(&str, Vec<&str>)> {
+        let mut shadowed = Vec::new();
+        for section in &[
+            &self.crates,
+            &self.github_repos,
+            &self.local_crates,
+            &self.git_repos,
+            &self.path_crates,
+        ] {
+            for name in section.keys().filter(|key| !glob::is_pattern(key)) {
+                let patterns = section
+                    .matching_patterns(name)
+                    .map(|(key, _)| key)
+                    .collect::<Vec<_>>();
+                if !patterns.is_empty() {
+                    shadowed.push((name.as_str(), patterns));
+                }
+            }
+        }
+        shadowed.sort();
+        shadowed
+    }
+
+    fn check_for_bad_log_storage(&self) -> Fallible<()> {
+        if let LogStorageConfig::S3 { ref prefix } = self.log_storage {
+            if let Err(err) = prefix.parse::<crate::report::S3Prefix>() {
+                error!(
+                    "check-config failed: log storage prefix `{}` is invalid: {}",
+                    prefix, err
+                );
+                return Err(BadConfig.into());
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -251,13 +753,17 @@ impl Default for Config {
                 github_repos: vec!["brson/hello-rs".into()],
                 local_crates: vec![],
             },
-            crates: HashMap::new(),
-            github_repos: HashMap::new(),
-            local_crates: HashMap::new(),
+            crates: CrateRules::default(),
+            github_repos: CrateRules::default(),
+            local_crates: CrateRules::default(),
+            git_repos: CrateRules::default(),
+            path_crates: CrateRules::default(),
             sandbox: SandboxConfig {
                 memory_limit: Size::Gigabytes(2),
                 build_log_max_size: Size::Megabytes(1),
                 build_log_max_lines: 1000,
+                no_output_timeout: default_no_output_timeout(),
+                total_timeout: default_total_timeout(),
             },
             server: ServerConfig {
                 bot_acl: BotACL {
@@ -271,6 +777,9 @@ impl Default for Config {
                 },
                 distributed: ChunkConfig { chunk_size: 1 },
             },
+            classifiers: BTreeMap::new(),
+            log_storage: LogStorageConfig::Database,
+            report_storage: ReportStorageConfig::S3,
         }
     }
 }
@@ -287,7 +796,11 @@ impl From<&'_ Config> for LogStorage {
 #[cfg(test)]
 mod tests {
     use super::Config;
-    use crate::crates::{Crate, GitHubRepo, RegistryCrate};
+    use crate::crates::{Crate, GitHubRepo, GitRepo, RegistryCrate};
+    use crate::experiments::Mode;
+    use crate::utils::size::Size;
+    use chrono::NaiveDate;
+    use std::time::Duration;
 
     #[test]
     fn test_config() {
@@ -311,10 +824,23 @@ mod tests {
             "build-log-max-size = \"2M\"\n",
             "build-log-max-lines = 1000\n",
             "[crates]\n",
-            "lazy_static = { skip = true }\n",
+            "lazy_static = { skip = true, reason = \"flaky build\", expires = \"2020-01-01\" }\n",
+            "rand = { slow = true, expires = \"2030-01-01\", no-output-timeout = 600 }\n",
+            "serde = { skip-modes = [\"clippy\", \"rustdoc\"], memory-limit = \"4G\" }\n",
+            "\"*-sys\" = { skip-tests = true }\n",
+            "\"libgit2-*\" = { skip = true }\n",
             "[github-repos]\n",
             "\"rust-lang/rust\" = { quiet = true }\n", // :(
-            "[local-crates]\n"
+            "\"rust-lang/*\" = { skip = true }\n",
+            "[local-crates]\n",
+            "build-fail = { skip = true, expires = \"2019-06-01\" }\n",
+            "[git-repos]\n",
+            "\"https://example.com/foo.git\" = { skip-tests = true }\n",
+            "[path-crates]\n",
+            "\"/opt/crates/bar\" = { broken = true }\n",
+            "[classifiers]\n",
+            "\"could not find system library '(openssl|ssl)'\" = { label = \"missing-openssl\" }\n",
+            "\"Connection reset by peer\" = { label = \"flaky-server\", spurious = true }\n",
         );
 
         let list: Config = ::toml::from_str(&config).unwrap();
@@ -327,11 +853,80 @@ mod tests {
             name: "rand".into(),
             version: "42".into(),
         })));
+        assert!(list.is_slow(&Crate::Registry(RegistryCrate {
+            name: "rand".into(),
+            version: "42".into(),
+        })));
+        assert_eq!(
+            list.crate_reason(&Crate::Registry(RegistryCrate {
+                name: "lazy_static".into(),
+                version: "42".into(),
+            })),
+            Some("flaky build")
+        );
+        assert_eq!(
+            list.expired_entries(NaiveDate::from_ymd(2025, 1, 1)),
+            vec![
+                ("build-fail", NaiveDate::from_ymd(2019, 6, 1)),
+                ("lazy_static", NaiveDate::from_ymd(2020, 1, 1))
+            ]
+        );
 
-        assert!(list.is_quiet(&Crate::GitHub(GitHubRepo {
+        let serde = Crate::Registry(RegistryCrate {
+            name: "serde".into(),
+            version: "42".into(),
+        });
+        assert!(!list.should_skip(&serde));
+        assert!(list.should_skip_in_mode(&serde, Mode::Clippy));
+        assert!(list.should_skip_in_mode(&serde, Mode::Rustdoc));
+        assert!(!list.should_skip_in_mode(&serde, Mode::BuildAndTest));
+
+        // Resource limits fall back to the ones of the sandbox
+        let rand = Crate::Registry(RegistryCrate {
+            name: "rand".into(),
+            version: "42".into(),
+        });
+        assert_eq!(list.memory_limit(&serde), Size::Gigabytes(4));
+        assert_eq!(list.memory_limit(&rand), Size::Gigabytes(2));
+        assert_eq!(list.no_output_timeout(&rand), Duration::from_secs(600));
+        assert_eq!(list.no_output_timeout(&serde), Duration::from_secs(5 * 60));
+        assert_eq!(list.total_timeout(&serde), Duration::from_secs(15 * 60));
+        // Slow crates get twice the default timeout
+        assert_eq!(list.total_timeout(&rand), Duration::from_secs(30 * 60));
+
+        // The most specific pattern is used
+        let curl_sys = Crate::Registry(RegistryCrate {
+            name: "curl-sys".into(),
+            version: "42".into(),
+        });
+        assert!(list.should_skip_tests(&curl_sys));
+        assert!(!list.should_skip(&curl_sys));
+        let libgit2_sys = Crate::Registry(RegistryCrate {
+            name: "libgit2-sys".into(),
+            version: "42".into(),
+        });
+        assert!(list.should_skip(&libgit2_sys));
+        assert!(!list.should_skip_tests(&libgit2_sys));
+        assert_eq!(list.crate_rule_name(&libgit2_sys), Some("libgit2-*"));
+        assert!(list.should_skip_in_mode(&libgit2_sys, Mode::BuildAndTest));
+
+        // Exact matches are preferred over patterns
+        let rust = Crate::GitHub(GitHubRepo {
             org: "rust-lang".into(),
             name: "rust".into(),
             sha: None,
+        });
+        assert!(list.is_quiet(&rust));
+        assert!(!list.should_skip(&rust));
+        assert_eq!(list.crate_rule_name(&rust), Some("rust-lang/rust"));
+        assert_eq!(
+            list.shadowed_patterns(),
+            vec![("rust-lang/rust", vec!["rust-lang/*"])]
+        );
+        assert!(list.should_skip(&Crate::GitHub(GitHubRepo {
+            org: "rust-lang".into(),
+            name: "cargo".into(),
+            sha: None,
         })));
         assert!(!list.is_quiet(&Crate::GitHub(GitHubRepo {
             org: "rust-lang".into(),
@@ -339,6 +934,26 @@ mod tests {
             sha: None,
         })));
 
+        assert!(list.should_skip_tests(&Crate::Git(GitRepo {
+            url: "https://example.com/foo.git".into(),
+            sha: Some("f00".into()),
+        })));
+        assert!(!list.should_skip_tests(&Crate::Git(GitRepo {
+            url: "https://example.com/bar.git".into(),
+            sha: None,
+        })));
+        assert!(list.is_broken(&Crate::Path("/opt/crates/bar".into())));
+        assert!(!list.is_broken(&Crate::Path("/opt/crates/foo".into())));
+
         assert_eq!(list.chunk_size(), 32);
+
+        assert_eq!(
+            list.classify("error: could not find system library 'openssl'")
+                .map(|c| c.label.as_str()),
+            Some("missing-openssl")
+        );
+        assert!(list.classify("error: linking with `cc` failed").is_none());
+        assert!(list.is_spurious_label("flaky-server"));
+        assert!(!list.is_spurious_label("missing-openssl"));
     }
 }
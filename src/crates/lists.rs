@@ -96,7 +96,8 @@ pub(crate) fn get_crates(
                     Crate::Registry(RegistryCrate { ref name, .. }) => demo_registry.remove(name),
                     Crate::GitHub(ref repo) => demo_github.remove(&repo.slug()),
                     Crate::Local(ref name) => demo_local.remove(name),
-                    Crate::Git(_) | Crate::Path(_) => unimplemented!("unsupported crate"),
+                    // Git repositories and path crates can't be part of the demo
+                    Crate::Git(_) | Crate::Path(_) => false,
                 };
 
                 if add {
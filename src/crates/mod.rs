@@ -6,6 +6,7 @@ use crate::prelude::*;
 use cargo_metadata::PackageId;
 use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
 use rustwide::Crate as RustwideCrate;
+use std::borrow::Cow;
 use std::convert::TryFrom;
 use std::fmt;
 use std::path::Path;
@@ -58,6 +59,28 @@ impl Crate {
         }
     }
 
+    /// The name of the crate, ignoring the version or commit that was tested. This is also the
+    /// key of the crate in its section of `config.toml`.
+    pub(crate) fn unversioned_name(&self) -> Cow<'_, str> {
+        match self {
+            Crate::Registry(details) => Cow::Borrowed(&details.name),
+            Crate::GitHub(repo) => Cow::Owned(repo.slug()),
+            Crate::Git(repo) => Cow::Borrowed(&repo.url),
+            Crate::Local(name) | Crate::Path(name) => Cow::Borrowed(name),
+        }
+    }
+
+    /// Identify a crate across experiments, ignoring the version or commit that was tested, as a
+    /// crate is likely to be updated between two runs.
+    pub(crate) fn unversioned_id(&self) -> String {
+        match self {
+            Crate::Registry(_) => format!("reg/{}", self.unversioned_name()),
+            Crate::GitHub(_) => format!("gh/{}", self.unversioned_name()),
+            Crate::Git(_) => format!("git/{}", self.unversioned_name()),
+            Crate::Local(_) | Crate::Path(_) => self.id(),
+        }
+    }
+
     pub(crate) fn to_rustwide(&self) -> RustwideCrate {
         match self {
             Self::Registry(krate) => RustwideCrate::crates_io(&krate.name, &krate.version),
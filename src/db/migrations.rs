@@ -363,6 +363,189 @@ fn migrations() -> Vec<(&'static str, MigrationKind)> {
         })),
     ));
 
+    migrations.push((
+        "create_diagnostics_table",
+        MigrationKind::SQL(
+            "
+            CREATE TABLE diagnostics (
+                experiment TEXT NOT NULL,
+                crate TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                level TEXT NOT NULL,
+                code TEXT,
+                lint TEXT,
+                message TEXT NOT NULL,
+                file TEXT,
+                line INTEGER,
+
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+
+            CREATE INDEX diagnostics__experiment_crate_toolchain
+                ON diagnostics (experiment, crate, toolchain);
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "add_classifier_to_results",
+        MigrationKind::SQL(
+            "
+            ALTER TABLE results ADD COLUMN classifier TEXT;
+            ",
+        ),
+    ));
+
+    // Logs can be stored outside of the database, so the results don't reference the logs table:
+    // the unused logs are removed by `DatabaseDB` instead.
+    migrations.push((
+        "deduplicate_logs",
+        MigrationKind::Code(Box::new(|t| {
+            use crate::results::{EncodedLog, EncodingType};
+
+            let fn_name = format!(
+                "crater_migration__{}",
+                rand::thread_rng()
+                    .sample_iter(&Alphanumeric)
+                    .take(10)
+                    .collect::<String>()
+            );
+            t.create_scalar_function(&fn_name, 2, true, |ctx| {
+                let log = ctx.get::<Vec<u8>>(0)?;
+                let encoding = ctx.get::<String>(1)?;
+
+                let plain = encoding
+                    .parse::<EncodingType>()
+                    .and_then(|encoding| EncodedLog::from_encoded(log, encoding).to_plain())
+                    .map_err(|e| ::rusqlite::Error::UserFunctionError(Box::new(e.compat())))?;
+                Ok(EncodedLog::hash_plain(&plain))
+            })?;
+
+            t.execute_batch(&format!(
+                "
+                CREATE TABLE logs (
+                    hash TEXT PRIMARY KEY,
+                    encoding TEXT NOT NULL,
+                    data BLOB NOT NULL
+                );
+
+                ALTER TABLE results ADD COLUMN log_hash TEXT;
+                UPDATE results SET log_hash = {func}(log, encoding);
+
+                INSERT OR IGNORE INTO logs (hash, encoding, data)
+                SELECT log_hash, encoding, log FROM results;
+
+                CREATE TABLE results_new (
+                    experiment TEXT NOT NULL,
+                    crate TEXT NOT NULL,
+                    toolchain TEXT NOT NULL,
+                    result TEXT NOT NULL,
+                    log_hash TEXT NOT NULL,
+                    classifier TEXT,
+
+                    PRIMARY KEY (experiment, crate, toolchain) ON CONFLICT REPLACE,
+                    FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+                );
+
+                INSERT INTO results_new (
+                    experiment, crate, toolchain, result, log_hash, classifier
+                ) SELECT
+                    experiment, crate, toolchain, result, log_hash, classifier
+                FROM results;
+
+                DROP TABLE results;
+                ALTER TABLE results_new RENAME TO results;
+
+                CREATE INDEX results__experiment ON results (experiment);
+                CREATE INDEX results__log_hash ON results (log_hash);
+                ",
+                func = fn_name
+            ))?;
+
+            Ok(())
+        })),
+    ));
+
+    migrations.push((
+        "create_triage_table",
+        MigrationKind::SQL(
+            "
+            CREATE TABLE triage (
+                experiment TEXT NOT NULL,
+                crate TEXT NOT NULL,
+                status TEXT NOT NULL,
+                note TEXT NOT NULL,
+                link TEXT,
+                updated_by TEXT NOT NULL,
+                updated_at DATETIME NOT NULL,
+
+                PRIMARY KEY (experiment, crate) ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "create_environments_table",
+        MigrationKind::SQL(
+            "
+            CREATE TABLE environments (
+                experiment TEXT NOT NULL,
+                agent TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                rustc_version TEXT NOT NULL,
+                cargo_version TEXT NOT NULL,
+                git_revision TEXT,
+                docker_image TEXT,
+                recorded_at DATETIME NOT NULL,
+
+                PRIMARY KEY (experiment, agent, toolchain) ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "add_timing_to_results",
+        MigrationKind::SQL(
+            "
+            ALTER TABLE results ADD COLUMN started_at DATETIME;
+            ALTER TABLE results ADD COLUMN finished_at DATETIME;
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "add_results_crate_index",
+        MigrationKind::SQL(
+            "
+            CREATE INDEX results__crate ON results (crate);
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "add_experiments_partial_report",
+        MigrationKind::SQL(
+            "
+            ALTER TABLE experiments ADD COLUMN partial_report_url TEXT;
+            ALTER TABLE experiments ADD COLUMN partial_report_generated_at DATETIME;
+            ",
+        ),
+    ));
+
+    // The throughput of the agents is measured on the recently finished jobs
+    migrations.push((
+        "add_results_finished_at_index",
+        MigrationKind::SQL(
+            "
+            CREATE INDEX results__finished_at ON results (finished_at);
+            ",
+        ),
+    ));
+
     migrations
 }
 
@@ -279,6 +279,19 @@ impl Experiment {
             .collect::<Fallible<_>>()
     }
 
+    /// The last `limit` completed experiments, starting from the most recently completed one.
+    pub fn last_completed(db: &Database, limit: usize) -> Fallible<Vec<Experiment>> {
+        let records = db.query(
+            "SELECT * FROM experiments WHERE status = ?1 ORDER BY completed_at DESC LIMIT ?2;",
+            &[&Status::Completed.to_str(), &(limit as i64)],
+            |r| ExperimentDBRecord::from_row(r),
+        )?;
+        records
+            .into_iter()
+            .map(|record| record.into_experiment())
+            .collect::<Fallible<_>>()
+    }
+
     pub fn run_by(db: &Database, assignee: &Assignee) -> Fallible<Option<Experiment>> {
         let record = db.get_row(
             "SELECT * FROM experiments \
@@ -574,6 +587,44 @@ impl Experiment {
         Ok(())
     }
 
+    /// The URL and the generation date of the last partial report of the experiment, if any.
+    pub fn last_partial_report(&self, db: &Database) -> Fallible<Option<(String, DateTime<Utc>)>> {
+        let partial = db.get_row(
+            "SELECT partial_report_url, partial_report_generated_at FROM experiments \
+             WHERE name = ?1;",
+            &[&self.name.as_str()],
+            |r| {
+                let url: Option<String> = r.get("partial_report_url");
+                let generated_at: Option<DateTime<Utc>> = r.get("partial_report_generated_at");
+                url.and_then(|url| Some((url, generated_at?)))
+            },
+        )?;
+        Ok(partial.and_then(|partial| partial))
+    }
+
+    pub fn set_partial_report(
+        &self,
+        db: &Database,
+        url: &str,
+        generated_at: DateTime<Utc>,
+    ) -> Fallible<()> {
+        db.execute(
+            "UPDATE experiments SET partial_report_url = ?1, partial_report_generated_at = ?2 \
+             WHERE name = ?3;",
+            &[&url, &generated_at, &self.name.as_str()],
+        )?;
+        Ok(())
+    }
+
+    pub fn clear_partial_report(&self, db: &Database) -> Fallible<()> {
+        db.execute(
+            "UPDATE experiments SET partial_report_url = NULL, partial_report_generated_at = NULL \
+             WHERE name = ?1;",
+            &[&self.name.as_str()],
+        )?;
+        Ok(())
+    }
+
     pub fn raw_progress(&self, db: &Database) -> Fallible<(u32, u32)> {
         let results_len: u32 = db
             .get_row(
@@ -800,6 +851,7 @@ mod tests {
     use crate::db::Database;
     use crate::server::agents::Agents;
     use crate::server::tokens::Tokens;
+    use chrono::{TimeZone, Utc};
     use std::collections::HashSet;
     use std::str::FromStr;
 
@@ -1084,4 +1136,31 @@ mod tests {
         assert_eq!(ex.status, Status::Failed);
         assert!(ex.get_running_crates(&db, &agent1).unwrap().is_empty());
     }
+
+    #[test]
+    fn test_partial_report() {
+        let db = Database::temp().unwrap();
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        CreateExperiment::dummy("dummy").apply(&ctx).unwrap();
+        let ex = Experiment::get(&db, "dummy").unwrap().unwrap();
+        assert_eq!(ex.last_partial_report(&db).unwrap(), None);
+
+        let generated_at = Utc.ymd(2020, 1, 1).and_hms(12, 0, 0);
+        ex.set_partial_report(&db, "https://example.com/partial/dummy/", generated_at)
+            .unwrap();
+        assert_eq!(
+            ex.last_partial_report(&db).unwrap(),
+            Some((
+                "https://example.com/partial/dummy/".to_string(),
+                generated_at
+            ))
+        );
+
+        ex.clear_partial_report(&db).unwrap();
+        assert_eq!(ex.last_partial_report(&db).unwrap(), None);
+    }
 }
@@ -4,7 +4,7 @@ use crate::results::{
     FailureReason,
     TestResult::{self, BuildFail},
 };
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 use std::collections::BTreeSet;
 
 pub enum ToolchainSelect {
@@ -31,6 +31,8 @@ pub enum ReportCrates {
 pub struct TestResults {
     pub categories: IndexMap<Comparison, ReportCrates>,
     pub info: IndexMap<Comparison, u32>,
+    /// The crates whose logs matched a classifier, grouped by the classifier label.
+    pub classified: IndexMap<String, Vec<CrateResult>>,
 }
 
 fn analyze_detailed(toolchain: usize, crates: Vec<CrateResult>) -> ReportCrates {
@@ -74,7 +76,31 @@ fn analyze_detailed(toolchain: usize, crates: Vec<CrateResult>) -> ReportCrates
     ReportCrates::Complete { tree, results }
 }
 
+fn group_by_classifier(crates: &[CrateResult]) -> IndexMap<String, Vec<CrateResult>> {
+    let mut classified = IndexMap::new();
+    for krate in crates {
+        // a crate is listed only once per label, even if both runs matched it
+        let labels = krate
+            .runs
+            .iter()
+            .flatten()
+            .filter_map(|run| run.classifier.as_ref())
+            .collect::<IndexSet<_>>();
+        for label in labels {
+            classified
+                .entry(label.clone())
+                .or_insert_with(Vec::new)
+                .push(krate.clone());
+        }
+    }
+
+    classified.sort_keys();
+    classified
+}
+
 pub fn analyze_report(test: RawTestResults) -> TestResults {
+    let classified = group_by_classifier(&test.crates);
+
     let mut comparison = IndexMap::new();
     for krate in test.crates {
         comparison
@@ -99,7 +125,11 @@ pub fn analyze_report(test: RawTestResults) -> TestResults {
         }
     }
 
-    TestResults { categories, info }
+    TestResults {
+        categories,
+        info,
+        classified,
+    }
 }
 
 #[cfg(test)]
@@ -179,6 +209,9 @@ mod tests {
             "fix-2" => (TestResult::BuildFail(Unknown), TestResult::TestPass)
         };
 
+        db.add_dummy_classifier(&ex, reg!("unknown"), TEST_TOOLCHAIN.clone(), "flaky-server");
+        db.add_dummy_classifier(&ex, reg!("fix-2"), MAIN_TOOLCHAIN.clone(), "flaky-server");
+
         let raw = generate_report(&db, &config, &ex, &crates)?;
         let mut crates = raw
             .crates
@@ -246,6 +279,15 @@ mod tests {
             results: fix_root,
         };
 
+        let mut classified = IndexMap::new();
+        classified.insert(
+            "flaky-server".to_string(),
+            vec![
+                crates.get("fix-2").unwrap().clone(),
+                crates.get("unknown").unwrap().clone(),
+            ],
+        );
+
         let test_pass = ReportCrates::Plain(vec![crates.remove("test-pass").unwrap()]);
 
         let mut categories = IndexMap::new();
@@ -253,7 +295,11 @@ mod tests {
         categories.insert(Comparison::Fixed, fixed);
         categories.insert(Comparison::SameTestPass, test_pass);
 
-        let expected = TestResults { categories, info };
+        let expected = TestResults {
+            categories,
+            info,
+            classified,
+        };
         assert_eq!(expected, analyzed);
 
         Ok(())
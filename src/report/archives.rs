@@ -26,13 +26,13 @@ pub fn write_logs_archives<DB: ReadResults, W: ReportWriter>(
     let mut by_comparison = IndexMap::new();
 
     for krate in crates {
-        if config.should_skip(krate) {
+        if config.should_skip_in_mode(krate, ex.mode) {
             continue;
         }
 
         let res1 = db.load_test_result(ex, &ex.toolchains[0], krate)?;
         let res2 = db.load_test_result(ex, &ex.toolchains[1], krate)?;
-        let comparison = compare(config, krate, res1.as_ref(), res2.as_ref());
+        let comparison = compare(config, ex.mode, krate, res1.as_ref(), res2.as_ref());
 
         for tc in &ex.toolchains {
             let log = db
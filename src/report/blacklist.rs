@@ -0,0 +1,190 @@
+use crate::config::Config;
+use crate::experiments::{Experiment, Mode};
+use crate::report::{CrateResult, RawTestResults};
+use crate::results::TestResult;
+use std::collections::BTreeMap;
+
+/// An entry of the blacklist that the results of an experiment run with `ignore_blacklist` show
+/// is no longer needed.
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Serialize)]
+pub struct UnneededEntry {
+    /// The name of the entry in `config.toml`, which might be a pattern.
+    pub name: String,
+    /// The URL of a crate the entry applies to.
+    pub url: String,
+    /// The option of the entry that can be removed.
+    pub option: &'static str,
+    pub reason: Option<String>,
+}
+
+fn all_runs(krate: &CrateResult, check: impl Fn(&TestResult) -> bool) -> bool {
+    krate
+        .runs
+        .iter()
+        .all(|run| run.as_ref().map(|run| check(&run.res)).unwrap_or(false))
+}
+
+/// Finds the skip entries whose crates all built with both toolchains, and the skip-tests entries
+/// whose crates all passed the tests with both toolchains. Entries can be patterns, matching
+/// multiple crates.
+pub fn find_unneeded_entries(
+    ex: &Experiment,
+    config: &Config,
+    raw: &RawTestResults,
+) -> Vec<UnneededEntry> {
+    // Blacklisted crates are only run when the experiment ignores the blacklist
+    if !ex.ignore_blacklist {
+        return Vec::new();
+    }
+
+    // Whether each entry is still needed, with the first crate it applies to
+    let mut entries: BTreeMap<(&str, &'static str), (bool, &CrateResult)> = BTreeMap::new();
+    for krate in &raw.crates {
+        let name = match config.crate_rule_name(&krate.krate) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        let (option, needed) = if config.should_skip_in_mode(&krate.krate, ex.mode) {
+            let built = all_runs(krate, |res| match res {
+                TestResult::TestPass | TestResult::TestFail(_) | TestResult::TestSkipped => true,
+                TestResult::BrokenCrate(_)
+                | TestResult::BuildFail(_)
+                | TestResult::Skipped
+                | TestResult::Error => false,
+            });
+            let option = if config.should_skip(&krate.krate) {
+                "skip"
+            } else {
+                "skip-modes"
+            };
+            (option, !built)
+        } else if config.should_skip_tests(&krate.krate) && ex.mode == Mode::BuildAndTest {
+            (
+                "skip-tests",
+                !all_runs(krate, |res| *res == TestResult::TestPass),
+            )
+        } else {
+            continue;
+        };
+
+        entries.entry((name, option)).or_insert((false, krate)).0 |= needed;
+    }
+
+    entries
+        .into_iter()
+        .filter(|(_, (needed, _))| !needed)
+        .map(|((name, option), (_, krate))| UnneededEntry {
+            name: name.into(),
+            url: krate.url.clone(),
+            option,
+            reason: config.crate_reason(&krate.krate).map(String::from),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_unneeded_entries, UnneededEntry};
+    use crate::config::{Config, CrateConfig};
+    use crate::experiments::{CapLints, Experiment, Mode, Status};
+    use crate::report::{BuildTestResult, Comparison, CrateResult, RawTestResults};
+    use crate::results::{FailureReason, TestResult};
+    use crate::toolchain::{MAIN_TOOLCHAIN, TEST_TOOLCHAIN};
+
+    fn krate(name: &str, start: TestResult, end: TestResult) -> CrateResult {
+        CrateResult {
+            runs: [
+                Some(BuildTestResult::dummy(start)),
+                Some(BuildTestResult::dummy(end)),
+            ],
+            ..CrateResult::dummy(name, "1.0.0", Comparison::Unknown)
+        }
+    }
+
+    fn entry(skip: bool, skip_tests: bool, reason: Option<&str>) -> CrateConfig {
+        CrateConfig {
+            skip,
+            skip_tests,
+            reason: reason.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_unneeded_entries() {
+        let mut config = Config::default();
+        config
+            .crates
+            .insert("fixed".into(), entry(true, false, Some("flaky build")));
+        config
+            .crates
+            .insert("still-broken".into(), entry(true, false, None));
+        config
+            .crates
+            .insert("fixed-tests".into(), entry(false, true, None));
+        config
+            .crates
+            .insert("flaky-tests".into(), entry(false, true, None));
+        config
+            .crates
+            .insert("partly-*".into(), entry(true, false, None));
+
+        let fail = || TestResult::BuildFail(FailureReason::Unknown);
+        let test_fail = || TestResult::TestFail(FailureReason::Unknown);
+        let raw = RawTestResults {
+            crates: vec![
+                krate("fixed", test_fail(), TestResult::TestPass),
+                krate("still-broken", TestResult::TestPass, fail()),
+                krate("fixed-tests", TestResult::TestPass, TestResult::TestPass),
+                krate("flaky-tests", TestResult::TestPass, test_fail()),
+                krate(
+                    "not-blacklisted",
+                    TestResult::TestPass,
+                    TestResult::TestPass,
+                ),
+                // Patterns are only unneeded if all their crates built
+                krate("partly-fixed", TestResult::TestPass, TestResult::TestPass),
+                krate("partly-broken", fail(), fail()),
+            ],
+        };
+
+        let mut ex = Experiment {
+            name: "foo".to_string(),
+            toolchains: [MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()],
+            mode: Mode::BuildAndTest,
+            cap_lints: CapLints::Forbid,
+            priority: 0,
+            created_at: chrono::Utc::now(),
+            started_at: None,
+            completed_at: None,
+            github_issue: None,
+            status: Status::GeneratingReport,
+            assigned_to: None,
+            report_url: None,
+            ignore_blacklist: false,
+            requirement: None,
+        };
+        assert!(find_unneeded_entries(&ex, &config, &raw).is_empty());
+
+        ex.ignore_blacklist = true;
+        assert_eq!(
+            find_unneeded_entries(&ex, &config, &raw),
+            vec![
+                UnneededEntry {
+                    name: "fixed".into(),
+                    url: "https://crates.io/crates/fixed/1.0.0".into(),
+                    option: "skip",
+                    reason: Some("flaky build".into()),
+                },
+                UnneededEntry {
+                    name: "fixed-tests".into(),
+                    url: "https://crates.io/crates/fixed-tests/1.0.0".into(),
+                    option: "skip-tests",
+                    reason: None,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,352 @@
+use crate::config::Config;
+use crate::crates::Crate;
+use crate::db::Database;
+use crate::experiments::Experiment;
+use crate::prelude::*;
+use crate::results::{open_log_store, DatabaseDB, TestResult};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+/// The name of a crate in the sections of `config.toml`, matching all its versions or commits.
+#[cfg_attr(test, derive(Debug))]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConfigKey {
+    Registry(String),
+    GitHub(String),
+    Git(String),
+    Path(String),
+}
+
+impl ConfigKey {
+    fn new(krate: &Crate) -> Option<Self> {
+        let name = krate.unversioned_name().into_owned();
+        match krate {
+            Crate::Registry(_) => Some(ConfigKey::Registry(name)),
+            Crate::GitHub(_) => Some(ConfigKey::GitHub(name)),
+            Crate::Git(_) => Some(ConfigKey::Git(name)),
+            Crate::Path(_) => Some(ConfigKey::Path(name)),
+            // Local crates are only used to test crater itself
+            Crate::Local(_) => None,
+        }
+    }
+
+    fn section(&self) -> &'static str {
+        match self {
+            ConfigKey::Registry(_) => "crates",
+            ConfigKey::GitHub(_) => "github-repos",
+            ConfigKey::Git(_) => "git-repos",
+            ConfigKey::Path(_) => "path-crates",
+        }
+    }
+
+    fn toml_key(&self) -> String {
+        match self {
+            ConfigKey::Registry(name) => name.clone(),
+            ConfigKey::GitHub(key) | ConfigKey::Git(key) | ConfigKey::Path(key) => {
+                format!("\"{}\"", key)
+            }
+        }
+    }
+}
+
+pub struct BrokenCrates {
+    /// How many completed experiments were looked at.
+    pub experiments: usize,
+    /// The crates that never built, with the number of experiments they failed to build in.
+    pub crates: BTreeMap<ConfigKey, usize>,
+    /// The crates that built but never passed their tests, with the number of experiments their
+    /// tests failed in.
+    pub failing_tests: BTreeMap<ConfigKey, usize>,
+}
+
+/// Whether the results of a crate show it failed to build, was built, or neither (for example
+/// because it was skipped).
+fn build_failed(runs: &[Option<TestResult>; 2]) -> Option<bool> {
+    let mut failed = None;
+    for res in runs.iter().flatten() {
+        match res {
+            TestResult::TestPass | TestResult::TestFail(_) | TestResult::TestSkipped => {
+                return Some(false)
+            }
+            TestResult::BuildFail(_) | TestResult::BrokenCrate(_) => failed = Some(true),
+            TestResult::Skipped | TestResult::Error => {}
+        }
+    }
+    failed
+}
+
+/// Whether the results of a crate show its tests failed, passed, or neither (for example because
+/// the experiment didn't run them).
+fn tests_failed(runs: &[Option<TestResult>; 2]) -> Option<bool> {
+    let mut failed = None;
+    for res in runs.iter().flatten() {
+        match res {
+            TestResult::TestPass => return Some(false),
+            TestResult::TestFail(_) => failed = Some(true),
+            _ => {}
+        }
+    }
+    failed
+}
+
+/// Count the experiments each crate failed in, according to `failed`. Crates that didn't fail in
+/// one of the experiments, or that are `ignored`, are left out.
+fn count_failures(
+    experiments: &[HashMap<Crate, [Option<TestResult>; 2]>],
+    min_experiments: usize,
+    ignored: impl Fn(&Crate) -> bool,
+    failed: impl Fn(&[Option<TestResult>; 2]) -> Option<bool>,
+) -> BTreeMap<ConfigKey, usize> {
+    // The number of experiments each crate failed in, or None if it didn't fail at least once
+    let mut failures: BTreeMap<ConfigKey, Option<usize>> = BTreeMap::new();
+    for results in experiments {
+        // Multiple versions of a crate can be part of the same experiment
+        let mut failed_in_experiment: HashMap<ConfigKey, bool> = HashMap::new();
+        for (krate, runs) in results {
+            let key = match ConfigKey::new(krate) {
+                Some(key) => key,
+                None => continue,
+            };
+            if ignored(krate) {
+                continue;
+            }
+            if let Some(failed) = failed(runs) {
+                *failed_in_experiment.entry(key).or_insert(true) &= failed;
+            }
+        }
+
+        for (key, failed) in failed_in_experiment {
+            let count = failures.entry(key).or_insert(Some(0));
+            *count = match (*count, failed) {
+                (Some(count), true) => Some(count + 1),
+                _ => None,
+            };
+        }
+    }
+
+    failures
+        .into_iter()
+        .filter_map(|(key, count)| count.map(|count| (key, count)))
+        .filter(|&(_, count)| count >= min_experiments)
+        .collect()
+}
+
+fn analyze_broken_crates(
+    experiments: &[HashMap<Crate, [Option<TestResult>; 2]>],
+    config: &Config,
+    min_experiments: usize,
+) -> BTreeMap<ConfigKey, usize> {
+    // Crates already skipped don't need to be added to the list again
+    count_failures(
+        experiments,
+        min_experiments,
+        |krate| config.should_skip(krate),
+        build_failed,
+    )
+}
+
+fn analyze_failing_tests(
+    experiments: &[HashMap<Crate, [Option<TestResult>; 2]>],
+    config: &Config,
+    min_experiments: usize,
+) -> BTreeMap<ConfigKey, usize> {
+    count_failures(
+        experiments,
+        min_experiments,
+        |krate| config.should_skip(krate) || config.should_skip_tests(krate),
+        tests_failed,
+    )
+}
+
+/// Find the crates that never built in the last `experiments` completed experiments, failing to
+/// build in at least `min_experiments` of them, and the crates whose tests never passed in them.
+pub fn find_broken_crates(
+    db: &Database,
+    config: &Config,
+    experiments: usize,
+    min_experiments: usize,
+) -> Fallible<BrokenCrates> {
+    let results_db = DatabaseDB::with_log_store(db, open_log_store(config, db)?);
+    let results = Experiment::last_completed(db, experiments)?
+        .iter()
+        .map(|ex| {
+            info!("loading the results of {}", ex.name);
+            results_db.load_all_results(ex)
+        })
+        .collect::<Fallible<Vec<_>>>()?;
+
+    Ok(BrokenCrates {
+        experiments: results.len(),
+        crates: analyze_broken_crates(&results, config, min_experiments),
+        failing_tests: analyze_failing_tests(&results, config, min_experiments),
+    })
+}
+
+/// Render the broken crates as the crate sections of `config.toml`, skipping all of them, and
+/// skipping the tests of the crates whose tests never passed.
+pub fn render_skip_list(broken: &BrokenCrates) -> Fallible<String> {
+    let mut rendered = String::new();
+    for section in &["crates", "github-repos", "git-repos", "path-crates"] {
+        let skip = broken
+            .crates
+            .iter()
+            .map(|(key, count)| (key, count, "skip", "built"));
+        let skip_tests = broken
+            .failing_tests
+            .iter()
+            .map(|(key, count)| (key, count, "skip-tests", "passed its tests"));
+        let mut entries = skip
+            .chain(skip_tests)
+            .filter(|(key, _, _, _)| key.section() == *section)
+            .peekable();
+        if entries.peek().is_none() {
+            continue;
+        }
+
+        writeln!(rendered, "[{}]", section)?;
+        for (key, count, option, never) in entries {
+            writeln!(
+                rendered,
+                "{} = {{ {} = true }} # never {} in the last {} experiments, failed in {}",
+                key.toml_key(),
+                option,
+                never,
+                broken.experiments,
+                count
+            )?;
+        }
+        writeln!(rendered)?;
+    }
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        analyze_broken_crates, analyze_failing_tests, render_skip_list, BrokenCrates, ConfigKey,
+    };
+    use crate::config::{Config, CrateConfig};
+    use crate::crates::{Crate, GitHubRepo, RegistryCrate};
+    use crate::results::{BrokenReason, FailureReason, TestResult};
+    use std::collections::HashMap;
+
+    fn reg(name: &str, version: &str) -> Crate {
+        Crate::Registry(RegistryCrate {
+            name: name.into(),
+            version: version.into(),
+        })
+    }
+
+    #[test]
+    fn test_analyze_broken_crates() {
+        let fail = || Some(TestResult::BuildFail(FailureReason::Unknown));
+        let broken = || Some(TestResult::BrokenCrate(BrokenReason::CargoToml));
+        let pass = || Some(TestResult::TestPass);
+
+        let gh = Crate::GitHub(GitHubRepo {
+            org: "brson".into(),
+            name: "hello-rs".into(),
+            sha: Some("f00".into()),
+        });
+
+        let mut first = HashMap::new();
+        first.insert(reg("broken", "1.0"), [fail(), broken()]);
+        first.insert(reg("fixed", "1.0"), [fail(), fail()]);
+        first.insert(reg("once", "1.0"), [fail(), fail()]);
+        first.insert(reg("skipped", "1.0"), [fail(), fail()]);
+        first.insert(gh.clone(), [fail(), fail()]);
+        let mut second = HashMap::new();
+        second.insert(reg("broken", "1.1"), [fail(), fail()]);
+        // Only one version of the crate needs to build
+        second.insert(reg("fixed", "1.0"), [fail(), fail()]);
+        second.insert(reg("fixed", "2.0"), [pass(), fail()]);
+        second.insert(reg("skipped", "1.0"), [fail(), fail()]);
+        second.insert(gh, [fail(), None]);
+
+        let mut config = Config::default();
+        config.crates.insert(
+            "skipped".into(),
+            CrateConfig {
+                skip: true,
+                ..Default::default()
+            },
+        );
+
+        let crates = analyze_broken_crates(&[first, second], &config, 2);
+        assert_eq!(
+            crates.into_iter().collect::<Vec<_>>(),
+            vec![
+                (ConfigKey::Registry("broken".into()), 2),
+                (ConfigKey::GitHub("brson/hello-rs".into()), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_analyze_failing_tests() {
+        let build_fail = || Some(TestResult::BuildFail(FailureReason::Unknown));
+        let test_fail = || Some(TestResult::TestFail(FailureReason::Unknown));
+        let pass = || Some(TestResult::TestPass);
+
+        let mut first = HashMap::new();
+        first.insert(reg("failing", "1.0"), [test_fail(), test_fail()]);
+        first.insert(reg("fixed", "1.0"), [test_fail(), test_fail()]);
+        first.insert(reg("broken", "1.0"), [build_fail(), build_fail()]);
+        first.insert(reg("skipped", "1.0"), [test_fail(), test_fail()]);
+        let mut second = HashMap::new();
+        // A build failure doesn't show whether the tests pass
+        second.insert(reg("failing", "1.1"), [build_fail(), test_fail()]);
+        second.insert(reg("fixed", "1.0"), [test_fail(), pass()]);
+        second.insert(reg("broken", "1.0"), [build_fail(), build_fail()]);
+        second.insert(reg("skipped", "1.0"), [test_fail(), test_fail()]);
+
+        let mut config = Config::default();
+        config.crates.insert(
+            "skipped".into(),
+            CrateConfig {
+                skip_tests: true,
+                ..Default::default()
+            },
+        );
+
+        let crates = analyze_failing_tests(&[first, second], &config, 2);
+        assert_eq!(
+            crates.into_iter().collect::<Vec<_>>(),
+            vec![(ConfigKey::Registry("failing".into()), 2)]
+        );
+    }
+
+    #[test]
+    fn test_render_skip_list() {
+        let broken = BrokenCrates {
+            experiments: 5,
+            crates: vec![
+                (ConfigKey::Registry("foo".into()), 5),
+                (ConfigKey::GitHub("brson/hello-rs".into()), 3),
+                (ConfigKey::Git("https://example.com/foo.git".into()), 2),
+            ]
+            .into_iter()
+            .collect(),
+            failing_tests: vec![(ConfigKey::Registry("bar".into()), 4)]
+                .into_iter()
+                .collect(),
+        };
+
+        assert_eq!(
+            render_skip_list(&broken).unwrap(),
+            "[crates]\n\
+             foo = { skip = true } # never built in the last 5 experiments, failed in 5\n\
+             bar = { skip-tests = true } # never passed its tests in the last 5 experiments, \
+             failed in 4\n\
+             \n\
+             [github-repos]\n\
+             \"brson/hello-rs\" = { skip = true } # never built in the last 5 experiments, \
+             failed in 3\n\
+             \n\
+             [git-repos]\n\
+             \"https://example.com/foo.git\" = { skip = true } # never built in the last 5 \
+             experiments, failed in 2\n\
+             \n"
+        );
+    }
+}
@@ -0,0 +1,467 @@
+use crate::crates::Crate;
+use crate::experiments::{Experiment, Mode};
+use crate::prelude::*;
+use crate::report::{
+    Comparison, CrateResult, RawTestResults, ReportWriter, ResultName, SanitizationContext,
+};
+use crate::results::{Environment, FailureReason, TestResult};
+use crate::toolchain::Toolchain;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// A ready-to-file issue about a regressed crate. Drafts are only written to the report: filing
+/// them is left to whoever triages the experiment.
+struct BugReportDraft {
+    name: String,
+    title: String,
+    body: String,
+    krate: Crate,
+}
+
+/// Only crates regressing on their own get a draft, as crates failing because of one of their
+/// dependencies are fixed along with it.
+fn is_root_regression(krate: &CrateResult) -> bool {
+    if krate.res != Comparison::Regressed {
+        return false;
+    }
+
+    !matches!(
+        krate.runs[1].as_ref().map(|run| &run.res),
+        Some(TestResult::BuildFail(FailureReason::DependsOn(_)))
+            | Some(TestResult::TestFail(FailureReason::DependsOn(_)))
+    )
+}
+
+fn draft_file_name(krate: &Crate, dest: SanitizationContext) -> String {
+    let name = match krate {
+        Crate::Registry(details) => format!("reg-{}-{}", details.name, details.version),
+        Crate::GitHub(repo) => format!("gh-{}.{}", repo.org, repo.name),
+        Crate::Local(name) => format!("local-{}", name),
+        Crate::Path(path) => format!("path-{}", path),
+        Crate::Git(repo) => format!("git-{}", repo.url),
+    };
+    format!("{}.md", dest.sanitize(&name.replace('/', "_")))
+}
+
+fn crate_version(krate: &Crate) -> Option<&str> {
+    match krate {
+        Crate::Registry(details) => Some(&details.version),
+        Crate::GitHub(repo) => repo.sha.as_deref(),
+        Crate::Git(repo) => repo.sha.as_deref(),
+        Crate::Local(_) | Crate::Path(_) => None,
+    }
+}
+
+fn fetch_commands(krate: &Crate) -> Vec<String> {
+    let (mut commands, sha) = match krate {
+        Crate::Registry(details) => {
+            return vec![
+                format!(
+                    "curl -sSL https://static.crates.io/crates/{0}/{0}-{1}.crate | tar -xz",
+                    details.name, details.version
+                ),
+                format!("cd {}-{}", details.name, details.version),
+            ];
+        }
+        Crate::GitHub(repo) => (
+            vec![
+                format!("git clone https://github.com/{}/{}", repo.org, repo.name),
+                format!("cd {}", repo.name),
+            ],
+            &repo.sha,
+        ),
+        Crate::Git(repo) => (
+            vec![format!("git clone {} crate", repo.url), "cd crate".into()],
+            &repo.sha,
+        ),
+        Crate::Local(name) => return vec![format!("cd local-crates/{}", name)],
+        Crate::Path(path) => return vec![format!("cd {}", path)],
+    };
+    if let Some(sha) = sha {
+        commands.push(format!("git checkout {}", sha));
+    }
+    commands
+}
+
+/// The name to pass to `cargo +<name>`, and the command installing the toolchain.
+fn toolchain_setup(tc: &Toolchain) -> Fallible<(String, String)> {
+    if let Some(dist) = tc.source.as_dist() {
+        Ok((
+            dist.name().to_string(),
+            format!("rustup toolchain install {}", dist.name()),
+        ))
+    } else if let Some(ci) = tc.source.as_ci() {
+        Ok((
+            ci.sha().to_string(),
+            format!("rustup-toolchain-install-master {}", ci.sha()),
+        ))
+    } else {
+        bail!("unsupported rustwide toolchain: {}", tc);
+    }
+}
+
+/// The cargo subcommand run by the experiment's mode, as done in `runner::test`.
+fn cargo_command(mode: Mode) -> &'static str {
+    match mode {
+        Mode::BuildAndTest => "test",
+        Mode::BuildOnly | Mode::UnstableFeatures => "build",
+        Mode::CheckOnly => "check --all --all-targets",
+        Mode::Clippy => "clippy --all --all-targets",
+        Mode::Rustdoc => "doc --no-deps --document-private-items",
+    }
+}
+
+fn draft_title(krate: &CrateResult, end: &Toolchain) -> String {
+    let what = match krate.runs[1].as_ref().map(|run| &run.res) {
+        Some(TestResult::BuildFail(_)) => "fails to build",
+        Some(TestResult::TestFail(_)) => "fails its tests",
+        _ => "regressed",
+    };
+    format!("`{}` {} with `{}`", krate.name, what, end)
+}
+
+fn write_reproduction(body: &mut String, ex: &Experiment, krate: &CrateResult) -> Fallible<()> {
+    let flags_var = if ex.mode == Mode::Rustdoc {
+        "RUSTDOCFLAGS"
+    } else {
+        "RUSTFLAGS"
+    };
+
+    writeln!(body, "```sh")?;
+    for tc in ex.toolchains.iter() {
+        writeln!(body, "{}", toolchain_setup(tc)?.1)?;
+    }
+    for command in fetch_commands(&krate.krate) {
+        writeln!(body, "{}", command)?;
+    }
+    for (tc, outcome) in ex.toolchains.iter().zip(&["works", "fails"]) {
+        for patch in &tc.patches {
+            writeln!(
+                body,
+                "# with `{} = {{ git = \"{}\", branch = \"{}\" }}` in `[patch.crates-io]`",
+                patch.name, patch.repo, patch.branch
+            )?;
+        }
+        let mut flags = format!("--cap-lints={}", ex.cap_lints);
+        if let Some(tc_flags) = &tc.rustflags {
+            flags.push(' ');
+            flags.push_str(tc_flags);
+        }
+        writeln!(
+            body,
+            "{}=\"{}\" cargo +{} {}  # {}",
+            flags_var,
+            flags,
+            toolchain_setup(tc)?.0,
+            cargo_command(ex.mode),
+            outcome
+        )?;
+    }
+    writeln!(body, "```")?;
+
+    Ok(())
+}
+
+fn write_versions(
+    body: &mut String,
+    ex: &Experiment,
+    environments: &BTreeMap<String, Environment>,
+) -> Fallible<()> {
+    for tc in ex.toolchains.iter() {
+        let name = tc.to_string();
+        let versions = environments
+            .values()
+            .flat_map(|env| env.toolchains.iter())
+            .filter(|versions| versions.toolchain == name)
+            .map(|versions| format!("{}\n{}", versions.rustc, versions.cargo))
+            .collect::<BTreeSet<_>>();
+
+        if versions.is_empty() {
+            writeln!(
+                body,
+                "\nThe version of `{}` wasn't recorded: please add the output of `rustc +{} -Vv` \
+                 before filing this issue.",
+                tc,
+                toolchain_setup(tc)?.0
+            )?;
+        } else {
+            if versions.len() > 1 {
+                writeln!(
+                    body,
+                    "\nThe agents of the experiment had different versions of `{}`:",
+                    tc
+                )?;
+            } else {
+                writeln!(body, "\nVersion of `{}`:", tc)?;
+            }
+            for version in versions {
+                writeln!(body, "\n```text\n{}\n```", version)?;
+            }
+        }
+    }
+    writeln!(body)?;
+
+    Ok(())
+}
+
+/// Renders the body of the issue. `log_base` is prepended to the paths of the logs in the report,
+/// and must end with a slash.
+fn render_body(
+    ex: &Experiment,
+    krate: &CrateResult,
+    environments: &BTreeMap<String, Environment>,
+    log_base: &str,
+) -> Fallible<String> {
+    let mut body = String::new();
+    let (start, end) = (&ex.toolchains[0], &ex.toolchains[1]);
+
+    let experiment = match &ex.github_issue {
+        Some(issue) => format!("[`{}`]({})", ex.name, issue.html_url),
+        None => format!("`{}`", ex.name),
+    };
+    writeln!(
+        body,
+        "The crate [`{}`]({}) regressed between `{}` and `{}` in the Crater experiment {}.\n",
+        krate.name, krate.url, start, end, experiment
+    )?;
+
+    writeln!(body, "### Crate\n")?;
+    writeln!(body, "* Crate: `{}`", krate.name)?;
+    if let Some(version) = crate_version(&krate.krate) {
+        writeln!(body, "* Version: `{}`", version)?;
+    }
+    writeln!(body, "* Source: {}\n", krate.url)?;
+
+    writeln!(body, "### Toolchains\n")?;
+    for (tc, run) in ex.toolchains.iter().zip(krate.runs.iter()) {
+        match run {
+            Some(run) => writeln!(
+                body,
+                "* `{}`: {} ([log]({}{}/log.txt))",
+                tc,
+                run.res.long_name(),
+                log_base,
+                run.log
+            )?,
+            None => writeln!(body, "* `{}`: no result", tc)?,
+        }
+    }
+    write_versions(&mut body, ex, environments)?;
+
+    writeln!(body, "### Failure\n")?;
+    if let Some(run) = &krate.runs[1] {
+        writeln!(
+            body,
+            "With `{}` the crate fails with: {}.\n",
+            end,
+            run.res.long_name()
+        )?;
+    }
+    if let Some(excerpt) = &krate.excerpt {
+        writeln!(body, "```text\n{}\n```\n", excerpt.trim_end())?;
+    }
+
+    writeln!(body, "### Reproduction\n")?;
+    write_reproduction(&mut body, ex, krate)?;
+
+    Ok(body)
+}
+
+/// Generates the drafts of all the crates of the report regressing on their own.
+fn bug_report_drafts(
+    ex: &Experiment,
+    raw: &RawTestResults,
+    environments: &BTreeMap<String, Environment>,
+    log_base: &str,
+) -> Fallible<Vec<BugReportDraft>> {
+    raw.crates
+        .iter()
+        .filter(|krate| is_root_regression(krate))
+        .map(|krate| {
+            Ok(BugReportDraft {
+                name: krate.name.clone(),
+                title: draft_title(krate, &ex.toolchains[1]),
+                body: render_body(ex, krate, environments, log_base)?,
+                krate: krate.krate.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Writes a Markdown file for each draft in the `bug-reports` directory of the report, along with
+/// an index listing them.
+pub(super) fn write_bug_reports<W: ReportWriter>(
+    ex: &Experiment,
+    raw: &RawTestResults,
+    environments: &BTreeMap<String, Environment>,
+    dest: &W,
+) -> Fallible<()> {
+    // The drafts are one directory below the logs
+    let drafts = bug_report_drafts(ex, raw, environments, "../")?;
+
+    let mut index = format!("# Bug report drafts for {}\n\n", ex.name);
+    if drafts.is_empty() {
+        index.push_str("No crate regressed on its own.\n");
+    } else {
+        index.push_str(
+            "Nothing is filed automatically: check the failures aren't spurious or already \
+             reported before filing these issues.\n\n",
+        );
+    }
+
+    for draft in &drafts {
+        let path = draft_file_name(&draft.krate, SanitizationContext::Path);
+        dest.write_string(
+            format!("bug-reports/{}", path),
+            format!("# {}\n\n{}", draft.title, draft.body).into(),
+            &mime::TEXT_PLAIN,
+        )?;
+        writeln!(
+            index,
+            "* [{}]({})",
+            draft.name,
+            draft_file_name(&draft.krate, SanitizationContext::Url)
+        )?;
+    }
+
+    dest.write_string("bug-reports/index.md", index.into(), &mime::TEXT_PLAIN)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bug_report_drafts, write_bug_reports};
+    use crate::crates::{Crate, RegistryCrate};
+    use crate::experiments::{CapLints, Experiment, GitHubIssue, Mode, Status};
+    use crate::report::{BuildTestResult, Comparison, CrateResult, DummyWriter, RawTestResults};
+    use crate::results::{Environment, FailureReason, TestResult, ToolchainVersions};
+    use crate::toolchain::{MAIN_TOOLCHAIN, TEST_TOOLCHAIN};
+    use std::collections::{BTreeMap, BTreeSet};
+
+    fn crate_result(name: &str, res: Comparison, end: TestResult) -> CrateResult {
+        CrateResult {
+            runs: [
+                Some(BuildTestResult {
+                    log: format!("stable/reg/{}-1.0.0", name),
+                    ..BuildTestResult::dummy(TestResult::TestPass)
+                }),
+                Some(BuildTestResult {
+                    log: format!("beta/reg/{}-1.0.0", name),
+                    ..BuildTestResult::dummy(end)
+                }),
+            ],
+            excerpt: Some("error[E0308]: mismatched types".into()),
+            ..CrateResult::dummy(name, "1.0.0", res)
+        }
+    }
+
+    #[test]
+    fn test_bug_report_drafts() {
+        let ex = Experiment {
+            name: "foo".to_string(),
+            toolchains: [MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()],
+            mode: Mode::CheckOnly,
+            cap_lints: CapLints::Forbid,
+            priority: 0,
+            created_at: ::chrono::Utc::now(),
+            started_at: None,
+            completed_at: None,
+            github_issue: Some(GitHubIssue {
+                api_url: "https://api.github.com/repos/rust-lang/rust/issues/1".into(),
+                html_url: "https://github.com/rust-lang/rust/issues/1".into(),
+                number: 1,
+            }),
+            status: Status::GeneratingReport,
+            assigned_to: None,
+            report_url: None,
+            ignore_blacklist: false,
+            requirement: None,
+        };
+
+        let raw = RawTestResults {
+            crates: vec![
+                crate_result(
+                    "root",
+                    Comparison::Regressed,
+                    TestResult::BuildFail(FailureReason::CompilerError(btreeset!["E0308"
+                        .parse()
+                        .unwrap()])),
+                ),
+                crate_result(
+                    "dependent",
+                    Comparison::Regressed,
+                    TestResult::BuildFail(FailureReason::DependsOn(btreeset![Crate::Registry(
+                        RegistryCrate {
+                            name: "root".into(),
+                            version: "1.0.0".into(),
+                        }
+                    )])),
+                ),
+                crate_result(
+                    "spurious",
+                    Comparison::SpuriousRegressed,
+                    TestResult::BuildFail(FailureReason::Timeout),
+                ),
+            ],
+        };
+
+        let env = |beta: &str| Environment {
+            git_revision: None,
+            docker_image: None,
+            toolchains: vec![
+                ToolchainVersions {
+                    toolchain: "stable".into(),
+                    rustc: "rustc 1.40.0".into(),
+                    cargo: "cargo 1.40.0".into(),
+                },
+                ToolchainVersions {
+                    toolchain: "beta".into(),
+                    rustc: beta.into(),
+                    cargo: "cargo 1.41.0-beta.1".into(),
+                },
+            ],
+        };
+        let mut environments = BTreeMap::new();
+        environments.insert("agent:1".to_string(), env("rustc 1.41.0-beta.1"));
+        environments.insert("agent:2".to_string(), env("rustc 1.41.0-beta.2"));
+
+        let drafts =
+            bug_report_drafts(&ex, &raw, &environments, "https://example.com/foo/").unwrap();
+        assert_eq!(drafts.len(), 1);
+        let draft = &drafts[0];
+        assert_eq!(draft.name, "root-1.0.0");
+        assert_eq!(draft.title, "`root-1.0.0` fails to build with `beta`");
+        for expected in &[
+            "[`foo`](https://github.com/rust-lang/rust/issues/1)",
+            "* Version: `1.0.0`",
+            "* `beta`: build compiler-error(E0308) \
+             ([log](https://example.com/foo/beta/reg/root-1.0.0/log.txt))",
+            "Version of `stable`:\n\n```text\nrustc 1.40.0\ncargo 1.40.0\n```\n",
+            "different versions of `beta`:\n\n\
+             ```text\nrustc 1.41.0-beta.1\ncargo 1.41.0-beta.1\n```\n\n\
+             ```text\nrustc 1.41.0-beta.2\ncargo 1.41.0-beta.1\n```\n",
+            "```text\nerror[E0308]: mismatched types\n```",
+            "curl -sSL https://static.crates.io/crates/root/root-1.0.0.crate | tar -xz\n\
+             cd root-1.0.0\n",
+            "RUSTFLAGS=\"--cap-lints=forbid\" cargo +stable check --all --all-targets  # works\n",
+            "RUSTFLAGS=\"--cap-lints=forbid\" cargo +beta check --all --all-targets  # fails\n",
+        ] {
+            assert!(draft.body.contains(expected), "missing {:?}", expected);
+        }
+
+        let writer = DummyWriter::default();
+        write_bug_reports(&ex, &raw, &BTreeMap::new(), &writer).unwrap();
+        let index =
+            String::from_utf8(writer.get("bug-reports/index.md", &mime::TEXT_PLAIN)).unwrap();
+        assert!(index.contains("* [root-1.0.0](reg-root-1.0.0.md)\n"));
+        let file =
+            String::from_utf8(writer.get("bug-reports/reg-root-1.0.0.md", &mime::TEXT_PLAIN))
+                .unwrap();
+        assert!(file.starts_with("# `root-1.0.0` fails to build with `beta`\n\n"));
+        assert!(file.contains("([log](../beta/reg/root-1.0.0/log.txt))"));
+        assert!(file.contains(
+            "The version of `beta` wasn't recorded: please add the output of `rustc +beta -Vv`"
+        ));
+    }
+}
@@ -0,0 +1,161 @@
+use crate::crates::Crate;
+use crate::prelude::*;
+use crate::report::excerpt::MAX_EXCERPT_LINES;
+use crate::report::{Comparison, CrateResult, RawTestResults};
+use indexmap::IndexMap;
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Clusters with fewer crates than this don't point to a shared cause, and are not shown.
+const MIN_CLUSTER_SIZE: usize = 2;
+
+lazy_static! {
+    /// Directories the crates are built in, which differ between crates and agents.
+    static ref BUILD_PATH: Regex =
+        Regex::new(r"(/[\w.+-]+)*/(registry/src/[^/\s]+|git/checkouts|target|workdir)/").unwrap();
+    static ref HASH: Regex = Regex::new(r"\b[0-9a-f]{16,40}\b").unwrap();
+    static ref VERSION: Regex =
+        Regex::new(r"\bv?\d+\.\d+\.\d+(-[0-9A-Za-z.]+)?(\+[0-9A-Za-z.]+)?").unwrap();
+    static ref LINE_NUMBER: Regex = Regex::new(r":\d+(:\d+)?\b").unwrap();
+}
+
+/// Regressed crates whose first error has the same signature, and are likely to share a cause.
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct Cluster {
+    /// The normalized first error shared by all the crates.
+    pub signature: String,
+    /// The first error of the first crate, as it appears in its log.
+    pub excerpt: Vec<String>,
+    pub crates: Vec<CrateResult>,
+}
+
+fn normalize(line: &str) -> String {
+    let line = BUILD_PATH.replace_all(line.trim(), "");
+    let line = HASH.replace_all(&line, "<hash>");
+    let line = VERSION.replace_all(&line, "<version>");
+    LINE_NUMBER.replace_all(&line, "").into_owned()
+}
+
+/// The signature of an error block is its message and location, without the snippets of code
+/// and notes that are specific to each crate.
+fn signature<S: AsRef<str>>(block: &[S]) -> String {
+    block
+        .iter()
+        .map(|line| line.as_ref())
+        .enumerate()
+        .filter(|(i, line)| *i == 0 || line.trim_start().starts_with("-->"))
+        .map(|(_, line)| normalize(line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn cluster_blocks<'a>(
+    blocks: impl Iterator<Item = (&'a CrateResult, &'a [String])>,
+) -> Vec<Cluster> {
+    let mut clusters = IndexMap::new();
+    for (krate, block) in blocks {
+        let signature = signature(block);
+        clusters
+            .entry(signature.clone())
+            .or_insert_with(|| Cluster {
+                signature,
+                excerpt: block.iter().take(MAX_EXCERPT_LINES).cloned().collect(),
+                crates: Vec::new(),
+            })
+            .crates
+            .push(krate.clone());
+    }
+
+    let mut clusters = clusters
+        .into_iter()
+        .map(|(_, cluster)| cluster)
+        .filter(|cluster| cluster.crates.len() >= MIN_CLUSTER_SIZE)
+        .collect::<Vec<_>>();
+    // The sort is stable, so clusters of the same size keep the order of the crates
+    clusters.sort_by(|a, b| b.crates.len().cmp(&a.crates.len()));
+    clusters
+}
+
+/// Group the regressed crates by the signature of the first error in their logs, extracted while
+/// generating the report.
+pub fn cluster_regressions(
+    raw: &RawTestResults,
+    error_blocks: &HashMap<Crate, Vec<String>>,
+) -> Vec<Cluster> {
+    cluster_blocks(
+        raw.crates
+            .iter()
+            .filter(|krate| krate.res == Comparison::Regressed)
+            .filter_map(|krate| Some((krate, error_blocks.get(&krate.krate)?.as_slice()))),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cluster_blocks, signature};
+    use crate::report::{Comparison, CrateResult};
+
+    #[test]
+    fn test_signature() {
+        let a = signature(&[
+            "error[E0425]: cannot find value `x` in this scope",
+            "  --> /opt/rustwide/cargo-home/registry/src/github.com-1ecc6299db9ec823/\
+             dep-1.2.3/src/lib.rs:10:1",
+            "10 | x",
+        ]);
+        let b = signature(&[
+            "error[E0425]: cannot find value `x` in this scope",
+            "  --> /home/crater/.cargo/registry/src/github.com-88ac128001ac3a9a/\
+             dep-1.2.4/src/lib.rs:11:1",
+            "11 |     x",
+        ]);
+        assert_eq!(a, b);
+        assert_eq!(
+            a,
+            "error[E0425]: cannot find value `x` in this scope\n--> dep-<version>/src/lib.rs"
+        );
+    }
+
+    #[test]
+    fn test_cluster_logs() {
+        let krate = |name: &str| CrateResult::dummy(name, "1.0", Comparison::Regressed);
+        let crates = vec![
+            krate("a"),
+            krate("b"),
+            krate("c"),
+            krate("d"),
+            krate("e"),
+            krate("f"),
+        ];
+
+        let block = |error: &str| Some(vec![error.to_string()]);
+        let blocks = vec![
+            block("error: unknown feature `x`"),
+            block("error[E0308]: mismatched types"),
+            block("error: unknown feature `x`"),
+            // A cluster with a single crate is not shown
+            block("error: linking with `cc` failed"),
+            None,
+            block("error[E0308]: mismatched types"),
+        ];
+
+        let clusters = cluster_blocks(
+            crates
+                .iter()
+                .zip(&blocks)
+                .filter_map(|(krate, block)| Some((krate, block.as_ref()?.as_slice()))),
+        );
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].signature, "error[E0308]: mismatched types");
+        assert_eq!(
+            clusters[0].crates,
+            vec![crates[1].clone(), crates[5].clone()]
+        );
+        assert_eq!(clusters[1].signature, "error: unknown feature `x`");
+        assert_eq!(clusters[1].excerpt, vec!["error: unknown feature `x`"]);
+        assert_eq!(
+            clusters[1].crates,
+            vec![crates[0].clone(), crates[2].clone()]
+        );
+    }
+}
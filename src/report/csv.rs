@@ -0,0 +1,153 @@
+use crate::experiments::Experiment;
+use crate::prelude::*;
+use crate::report::{RawTestResults, ReportWriter};
+use crate::results::EncodingType;
+
+const HEADER: &[&str] = &[
+    "crate",
+    "name",
+    "url",
+    "comparison",
+    "start_toolchain",
+    "start_result",
+    "start_log",
+    "end_toolchain",
+    "end_result",
+    "end_log",
+    "triage_status",
+    "triage_note",
+    "triage_link",
+];
+
+pub fn write_csv_report<W: ReportWriter>(
+    ex: &Experiment,
+    raw: &RawTestResults,
+    dest: &W,
+) -> Fallible<()> {
+    let mut writer = ::csv::Writer::from_writer(Vec::new());
+    writer.write_record(HEADER)?;
+
+    for krate in &raw.crates {
+        let mut record = vec![
+            krate.krate.id(),
+            krate.name.clone(),
+            krate.url.clone(),
+            krate.res.to_string(),
+        ];
+        for (tc, run) in ex.toolchains.iter().zip(krate.runs.iter()) {
+            record.push(tc.to_string());
+            if let Some(run) = run {
+                record.push(run.res.to_string());
+                record.push(format!("{}/log.txt", run.log));
+            } else {
+                record.push(String::new());
+                record.push(String::new());
+            }
+        }
+        if let Some(triage) = &krate.triage {
+            record.push(triage.status.to_string());
+            record.push(triage.note.clone());
+            record.push(triage.link.clone().unwrap_or_default());
+        } else {
+            record.extend(vec![String::new(); 3]);
+        }
+        writer.write_record(&record)?;
+    }
+
+    info!("generating results.csv");
+    dest.write_bytes(
+        "results.csv",
+        writer.into_inner().map_err(|e| e.into_error())?,
+        &mime::TEXT_CSV,
+        EncodingType::Plain,
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write_csv_report;
+    use crate::crates::{Crate, GitHubRepo};
+    use crate::experiments::{CapLints, Experiment, Mode, Status};
+    use crate::report::{BuildTestResult, Comparison, CrateResult, DummyWriter, RawTestResults};
+    use crate::results::{FailureReason, TestResult, Triage, TriageStatus};
+    use crate::toolchain::{MAIN_TOOLCHAIN, TEST_TOOLCHAIN};
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_write_csv_report() {
+        let ex = Experiment {
+            name: "foo".to_string(),
+            toolchains: [MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()],
+            mode: Mode::BuildAndTest,
+            cap_lints: CapLints::Forbid,
+            priority: 0,
+            created_at: ::chrono::Utc::now(),
+            started_at: None,
+            completed_at: None,
+            github_issue: None,
+            status: Status::GeneratingReport,
+            assigned_to: None,
+            report_url: None,
+            ignore_blacklist: false,
+            requirement: None,
+        };
+
+        let raw = RawTestResults {
+            crates: vec![CrateResult {
+                name: "brson/hello-rs".into(),
+                url: "https://github.com/brson/hello-rs".into(),
+                krate: Crate::GitHub(GitHubRepo {
+                    org: "brson".into(),
+                    name: "hello-rs".into(),
+                    sha: None,
+                }),
+                status: None,
+                res: Comparison::Regressed,
+                runs: [
+                    Some(BuildTestResult {
+                        log: "stable/gh/brson.hello-rs".into(),
+                        ..BuildTestResult::dummy(TestResult::TestPass)
+                    }),
+                    Some(BuildTestResult {
+                        log: "beta/gh/brson.hello-rs".into(),
+                        ..BuildTestResult::dummy(TestResult::BuildFail(
+                            FailureReason::CompilerError(btreeset![
+                                "E0001".parse().unwrap(),
+                                "E0002".parse().unwrap(),
+                            ]),
+                        ))
+                    }),
+                ],
+                excerpt: None,
+                triage: Some(Triage {
+                    status: TriageStatus::IssueFiled,
+                    note: "broken by #1, fixed upstream".into(),
+                    link: Some("https://github.com/brson/hello-rs/issues/1".into()),
+                    updated_by: "octocat".into(),
+                    updated_at: ::chrono::Utc::now(),
+                    carried_over_from: None,
+                }),
+            }],
+        };
+
+        let writer = DummyWriter::default();
+        write_csv_report(&ex, &raw, &writer).unwrap();
+        let csv = String::from_utf8(writer.get("results.csv", &mime::TEXT_CSV)).unwrap();
+
+        assert_eq!(
+            csv,
+            format!(
+                "crate,name,url,comparison,start_toolchain,start_result,start_log,\
+                 end_toolchain,end_result,end_log,triage_status,triage_note,triage_link\n\
+                 gh/brson/hello-rs,brson/hello-rs,https://github.com/brson/hello-rs,regressed,\
+                 {},test-pass,stable/gh/brson.hello-rs/log.txt,\
+                 {},\"build-fail:compiler-error(E0001, E0002)\",\
+                 beta/gh/brson.hello-rs/log.txt,issue-filed,\"broken by #1, fixed upstream\",\
+                 https://github.com/brson/hello-rs/issues/1\n",
+                *MAIN_TOOLCHAIN, *TEST_TOOLCHAIN
+            )
+        );
+    }
+}
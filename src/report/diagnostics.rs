@@ -0,0 +1,106 @@
+use crate::experiments::Experiment;
+use crate::prelude::*;
+use crate::report::{Comparison, RawTestResults};
+use crate::results::{Diagnostic, ReadResults};
+
+/// The diagnostics of a crate that changed between the two toolchains of an experiment.
+pub struct DiagnosticsDiff {
+    pub name: String,
+    pub url: String,
+    pub res: Comparison,
+    /// Diagnostics emitted only by the second toolchain.
+    pub added: Vec<Diagnostic>,
+    /// Diagnostics emitted only by the first toolchain.
+    pub removed: Vec<Diagnostic>,
+}
+
+fn diff(start: &[Diagnostic], end: &[Diagnostic]) -> (Vec<Diagnostic>, Vec<Diagnostic>) {
+    let missing_from = |haystack: &[Diagnostic], needles: &[Diagnostic]| {
+        needles
+            .iter()
+            .filter(|needle| !haystack.iter().any(|diag| diag.same_as(needle)))
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+
+    (missing_from(start, end), missing_from(end, start))
+}
+
+pub fn generate_diagnostics_diffs<DB: ReadResults>(
+    db: &DB,
+    ex: &Experiment,
+    raw: &RawTestResults,
+) -> Fallible<Vec<DiagnosticsDiff>> {
+    let mut diffs = Vec::new();
+
+    // Only the crates whose result changed are interesting: diffing every crate would only
+    // surface new warnings, and would require loading all the diagnostics of the experiment
+    for krate in raw
+        .crates
+        .iter()
+        .filter(|krate| krate.res == Comparison::Regressed || krate.res == Comparison::Fixed)
+    {
+        let start = db.load_diagnostics(ex, &ex.toolchains[0], &krate.krate)?;
+        let end = db.load_diagnostics(ex, &ex.toolchains[1], &krate.krate)?;
+
+        let (added, removed) = diff(&start, &end);
+        if added.is_empty() && removed.is_empty() {
+            continue;
+        }
+
+        diffs.push(DiagnosticsDiff {
+            name: krate.name.clone(),
+            url: krate.url.clone(),
+            res: krate.res,
+            added,
+            removed,
+        });
+    }
+
+    Ok(diffs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diff;
+    use crate::results::{Diagnostic, DiagnosticLevel};
+
+    fn diag(level: DiagnosticLevel, code: &str, message: &str, line: u32) -> Diagnostic {
+        Diagnostic {
+            level,
+            code: Some(code.into()),
+            lint: None,
+            message: message.into(),
+            file: Some("src/lib.rs".into()),
+            line: Some(line),
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let start = vec![
+            diag(
+                DiagnosticLevel::Error,
+                "E0277",
+                "trait bound not satisfied",
+                10,
+            ),
+            diag(DiagnosticLevel::Error, "E0599", "no method named `foo`", 20),
+        ];
+        let end = vec![
+            // Only the wording of the message changed
+            diag(
+                DiagnosticLevel::Error,
+                "E0277",
+                "the trait bound is not satisfied",
+                10,
+            ),
+            diag(DiagnosticLevel::Error, "E0308", "mismatched types", 42),
+        ];
+
+        let (added, removed) = diff(&start, &end);
+        assert_eq!(added, vec![end[1].clone()]);
+        assert_eq!(removed, vec![start[1].clone()]);
+        assert_eq!(added[0].to_string(), "error E0308 at src/lib.rs:42");
+    }
+}
@@ -0,0 +1,365 @@
+use crate::config::Config;
+use crate::db::Database;
+use crate::experiments::Experiment;
+use crate::prelude::*;
+use crate::report::{generate_report, html, markdown, Comparison, RawTestResults, ReportWriter};
+use crate::results::{open_log_store, DatabaseDB};
+use indexmap::IndexMap;
+use std::fmt::{self, Display};
+use std::fs::File;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Where to load one of the two reports to diff from.
+pub enum ReportSource {
+    /// Generate the report from the results of an experiment stored in the database.
+    Experiment(String),
+    /// Load a `results.json` file produced by a previous report.
+    File(PathBuf),
+}
+
+impl ReportSource {
+    pub fn load(&self, db: &Database, config: &Config) -> Fallible<RawTestResults> {
+        match self {
+            ReportSource::Experiment(name) => {
+                let ex = Experiment::get(db, name)?
+                    .ok_or_else(|| err_msg(format!("missing experiment {}", name)))?;
+                let crates = ex.get_crates(db)?;
+                let results = DatabaseDB::with_log_store(db, open_log_store(config, db)?);
+                generate_report(&results, config, &ex, &crates)
+            }
+            ReportSource::File(path) => {
+                let file = File::open(path)
+                    .with_context(|_| format!("failed to open {}", path.display()))?;
+                Ok(serde_json::from_reader(file)
+                    .with_context(|_| format!("failed to parse {}", path.display()))?)
+            }
+        }
+    }
+}
+
+impl FromStr for ReportSource {
+    type Err = ::failure::Error;
+
+    fn from_str(input: &str) -> Fallible<ReportSource> {
+        // The raw results of a report are stored as JSON, so anything else is an experiment name
+        if input.ends_with(".json") {
+            Ok(ReportSource::File(input.into()))
+        } else {
+            Ok(ReportSource::Experiment(input.into()))
+        }
+    }
+}
+
+impl Display for ReportSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReportSource::Experiment(name) => write!(f, "{}", name),
+            ReportSource::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+string_enum!(pub enum ChangeKind {
+    NewRegression => "new regressions",
+    Fixed => "regressions fixed",
+    Spurious => "regressions now spurious",
+    StillFailing => "regressions now failing with both toolchains",
+    Unknown => "regressions with an unknown outcome",
+    Other => "other changes",
+});
+
+impl ChangeKind {
+    fn classify(old: Option<Comparison>, new: Option<Comparison>) -> ChangeKind {
+        match (old, new) {
+            (old, Some(Comparison::Regressed)) if old != Some(Comparison::Regressed) => {
+                ChangeKind::NewRegression
+            }
+            (Some(Comparison::Regressed), Some(new)) => match new {
+                Comparison::SameTestPass | Comparison::SameTestSkipped | Comparison::Fixed => {
+                    ChangeKind::Fixed
+                }
+                Comparison::SpuriousRegressed | Comparison::SpuriousFixed => ChangeKind::Spurious,
+                Comparison::SameBuildFail | Comparison::SameTestFail | Comparison::Broken => {
+                    ChangeKind::StillFailing
+                }
+                Comparison::Regressed
+                | Comparison::Skipped
+                | Comparison::Unknown
+                | Comparison::Error => ChangeKind::Unknown,
+            },
+            (Some(Comparison::Regressed), None) => ChangeKind::Unknown,
+            _ => ChangeKind::Other,
+        }
+    }
+}
+
+/// A crate whose comparison changed between the two reports. The comparison is missing if the
+/// crate wasn't part of that report.
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Serialize, Clone)]
+pub struct CrateChange {
+    pub name: String,
+    pub url: String,
+    pub old: Option<Comparison>,
+    pub new: Option<Comparison>,
+}
+
+#[derive(Serialize)]
+pub struct ReportDiff {
+    pub old: String,
+    pub new: String,
+    pub changes: IndexMap<ChangeKind, Vec<CrateChange>>,
+}
+
+pub fn diff_reports(
+    old_name: &str,
+    old: &RawTestResults,
+    new_name: &str,
+    new: &RawTestResults,
+) -> ReportDiff {
+    let old_results = old
+        .crates
+        .iter()
+        .map(|krate| (krate.krate.unversioned_id(), krate))
+        .collect::<IndexMap<_, _>>();
+    let new_results = new
+        .crates
+        .iter()
+        .map(|krate| (krate.krate.unversioned_id(), krate))
+        .collect::<IndexMap<_, _>>();
+
+    let mut changes = IndexMap::new();
+    let mut keys = old_results
+        .keys()
+        .chain(new_results.keys())
+        .collect::<Vec<_>>();
+    keys.sort();
+    keys.dedup();
+
+    for key in keys {
+        let old = old_results.get(key);
+        let new = new_results.get(key);
+        let old_res = old.map(|krate| krate.res);
+        let new_res = new.map(|krate| krate.res);
+        if old_res == new_res {
+            continue;
+        }
+
+        // Link to the most recent version of the crate
+        let krate = new.or(old).unwrap();
+        changes
+            .entry(ChangeKind::classify(old_res, new_res))
+            .or_insert_with(Vec::new)
+            .push(CrateChange {
+                name: krate.name.clone(),
+                url: krate.url.clone(),
+                old: old_res,
+                new: new_res,
+            });
+    }
+
+    // Show the categories in the order they're declared in
+    changes.sort_by(|kind1, _, kind2, _| {
+        let pos = |kind: &ChangeKind| {
+            ChangeKind::possible_values()
+                .iter()
+                .position(|value| *value == kind.to_str())
+        };
+        pos(kind1).cmp(&pos(kind2))
+    });
+
+    ReportDiff {
+        old: old_name.into(),
+        new: new_name.into(),
+        changes,
+    }
+}
+
+pub fn gen_diff<W: ReportWriter + Display>(
+    old: &ReportSource,
+    new: &ReportSource,
+    db: &Database,
+    config: &Config,
+    dest: &W,
+    output_templates: bool,
+) -> Fallible<ReportDiff> {
+    info!("loading report for {}", old);
+    let old_results = old.load(db, config)?;
+    info!("loading report for {}", new);
+    let new_results = new.load(db, config)?;
+
+    let diff = diff_reports(
+        &old.to_string(),
+        &old_results,
+        &new.to_string(),
+        &new_results,
+    );
+
+    info!("writing diff to {}", dest);
+    dest.write_string(
+        "diff.json",
+        serde_json::to_string(&diff)?.into(),
+        &mime::APPLICATION_JSON,
+    )?;
+    html::write_html_diff(&diff, dest, output_templates)?;
+    markdown::write_markdown_diff(&diff, dest, output_templates)?;
+
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_reports, ChangeKind, CrateChange};
+    use crate::report::{html, markdown, Comparison, CrateResult, DummyWriter, RawTestResults};
+
+    fn change(name: &str, old: Option<Comparison>, new: Option<Comparison>) -> CrateChange {
+        CrateChange {
+            name: name.into(),
+            url: format!("https://crates.io/crates/{}", name.replace("-", "/")),
+            old,
+            new,
+        }
+    }
+
+    #[test]
+    fn test_diff_reports() {
+        let old = RawTestResults {
+            crates: vec![
+                CrateResult::dummy("fixed", "1", Comparison::Regressed),
+                CrateResult::dummy("spurious", "1", Comparison::Regressed),
+                CrateResult::dummy("still", "1", Comparison::Regressed),
+                CrateResult::dummy("regressed", "1", Comparison::SameTestPass),
+                CrateResult::dummy("removed", "1", Comparison::SameTestPass),
+                CrateResult::dummy("updated", "1", Comparison::Regressed),
+            ],
+        };
+        let new = RawTestResults {
+            crates: vec![
+                CrateResult::dummy("added", "1", Comparison::Regressed),
+                CrateResult::dummy("fixed", "1", Comparison::SameTestPass),
+                CrateResult::dummy("spurious", "1", Comparison::SpuriousRegressed),
+                CrateResult::dummy("still", "1", Comparison::Regressed),
+                CrateResult::dummy("regressed", "1", Comparison::Regressed),
+                CrateResult::dummy("updated", "2", Comparison::SameBuildFail),
+            ],
+        };
+
+        let diff = diff_reports("old", &old, "new", &new);
+        assert_eq!(diff.old, "old");
+        assert_eq!(diff.new, "new");
+        assert_eq!(
+            diff.changes.keys().cloned().collect::<Vec<_>>(),
+            vec![
+                ChangeKind::NewRegression,
+                ChangeKind::Fixed,
+                ChangeKind::Spurious,
+                ChangeKind::StillFailing,
+                ChangeKind::Other,
+            ]
+        );
+        assert_eq!(
+            diff.changes[&ChangeKind::NewRegression],
+            vec![
+                change("added-1", None, Some(Comparison::Regressed)),
+                change(
+                    "regressed-1",
+                    Some(Comparison::SameTestPass),
+                    Some(Comparison::Regressed)
+                ),
+            ]
+        );
+        assert_eq!(
+            diff.changes[&ChangeKind::Fixed],
+            vec![change(
+                "fixed-1",
+                Some(Comparison::Regressed),
+                Some(Comparison::SameTestPass)
+            )]
+        );
+        // Crates are matched even if they were updated between the two runs
+        assert_eq!(
+            diff.changes[&ChangeKind::StillFailing],
+            vec![change(
+                "updated-2",
+                Some(Comparison::Regressed),
+                Some(Comparison::SameBuildFail)
+            )]
+        );
+        assert_eq!(
+            diff.changes[&ChangeKind::Spurious],
+            vec![change(
+                "spurious-1",
+                Some(Comparison::Regressed),
+                Some(Comparison::SpuriousRegressed)
+            )]
+        );
+        assert_eq!(
+            diff.changes[&ChangeKind::Other],
+            vec![change("removed-1", Some(Comparison::SameTestPass), None)]
+        );
+    }
+
+    #[test]
+    fn test_classify() {
+        use crate::report::Comparison::*;
+
+        let classify = |old, new| ChangeKind::classify(old, new);
+        assert_eq!(classify(None, Some(Regressed)), ChangeKind::NewRegression);
+        assert_eq!(
+            classify(Some(Fixed), Some(Regressed)),
+            ChangeKind::NewRegression
+        );
+
+        // Only actual passes fix a regression
+        for new in &[SameTestPass, SameTestSkipped, Fixed] {
+            assert_eq!(classify(Some(Regressed), Some(*new)), ChangeKind::Fixed);
+        }
+        for new in &[SpuriousRegressed, SpuriousFixed] {
+            assert_eq!(classify(Some(Regressed), Some(*new)), ChangeKind::Spurious);
+        }
+        for new in &[SameBuildFail, SameTestFail, Broken] {
+            assert_eq!(
+                classify(Some(Regressed), Some(*new)),
+                ChangeKind::StillFailing
+            );
+        }
+        for new in &[Some(Skipped), Some(Unknown), Some(Error), None] {
+            assert_eq!(classify(Some(Regressed), *new), ChangeKind::Unknown);
+        }
+
+        assert_eq!(classify(Some(SameTestPass), None), ChangeKind::Other);
+        assert_eq!(classify(Some(Fixed), Some(SameTestPass)), ChangeKind::Other);
+    }
+
+    #[test]
+    fn test_write_diff() {
+        let old = RawTestResults {
+            crates: vec![CrateResult::dummy(
+                "lazy_static",
+                "1",
+                Comparison::SameTestPass,
+            )],
+        };
+        let new = RawTestResults {
+            crates: vec![CrateResult::dummy(
+                "lazy_static",
+                "1",
+                Comparison::Regressed,
+            )],
+        };
+        let diff = diff_reports("old", &old, "new", &new);
+
+        let writer = DummyWriter::default();
+        html::write_html_diff(&diff, &writer, false).unwrap();
+        markdown::write_markdown_diff(&diff, &writer, false).unwrap();
+
+        let html = String::from_utf8(writer.get("diff.html", &mime::TEXT_HTML)).unwrap();
+        assert!(html.contains("lazy_static-1"));
+        let markdown = String::from_utf8(writer.get("diff.md", &mime::TEXT_PLAIN)).unwrap();
+        assert!(markdown.contains(
+            "### new regressions\n* [lazy_static-1](https://crates.io/crates/lazy_static/1) \
+             test-pass → regressed\n"
+        ));
+    }
+}
@@ -14,6 +14,11 @@ impl ResultName for FailureReason {
             FailureReason::Timeout => "timed out".into(),
             FailureReason::OOM => "OOM".into(),
             FailureReason::ICE => "ICE".into(),
+            FailureReason::Linker => "linker error".into(),
+            FailureReason::BuildScript => "build script failed".into(),
+            FailureReason::ProcMacro => "proc-macro panicked".into(),
+            FailureReason::Network => "network access".into(),
+            FailureReason::DiskFull => "disk full".into(),
             FailureReason::CompilerError(_) => "compiler error".into(),
             FailureReason::DependsOn(_) => "faulty deps".into(),
         }
@@ -25,7 +30,12 @@ impl ResultName for FailureReason {
             FailureReason::Unknown
             | FailureReason::Timeout
             | FailureReason::OOM
-            | FailureReason::ICE => self.short_name(),
+            | FailureReason::ICE
+            | FailureReason::Linker
+            | FailureReason::BuildScript
+            | FailureReason::ProcMacro
+            | FailureReason::Network
+            | FailureReason::DiskFull => self.short_name(),
         }
     }
 }
@@ -0,0 +1,177 @@
+use crate::prelude::*;
+use crate::results::TestResult;
+use regex::Regex;
+
+/// Maximum number of lines of an excerpt shown in the reports.
+pub(super) const MAX_EXCERPT_LINES: usize = 20;
+
+lazy_static! {
+    /// Prefixes added to every line of the logs, like `[INFO] [stderr] `.
+    static ref LOG_PREFIX: Regex = Regex::new(r"^(\[[^\]]*\] )*").unwrap();
+}
+
+fn strip_prefix(line: &str) -> &str {
+    &line[LOG_PREFIX.find(line).map(|m| m.end()).unwrap_or(0)..]
+}
+
+fn is_error(line: &str) -> bool {
+    (line.starts_with("error:") || line.starts_with("error["))
+        // Summaries printed at the end of a failed build, after the actual errors
+        && !line.starts_with("error: aborting due to")
+        && !line.starts_with("error: could not compile")
+        && !line.starts_with("error: Could not compile")
+        && !line.starts_with("error: build failed")
+        && !line.starts_with("error: test failed")
+}
+
+fn is_panic(line: &str) -> bool {
+    line.starts_with("thread '") && line.contains("' panicked at ")
+}
+
+/// Extract the first error emitted in a log, up to the first blank line.
+pub(super) fn first_error_block(log: &str) -> Option<Vec<&str>> {
+    let mut block = Vec::new();
+    for line in log.lines().map(strip_prefix) {
+        if block.is_empty() {
+            if is_error(line) {
+                block.push(line);
+            }
+        } else if line.trim().is_empty() || line.starts_with("error") || line.starts_with("warning")
+        {
+            break;
+        } else {
+            block.push(line);
+        }
+    }
+
+    if block.is_empty() {
+        None
+    } else {
+        Some(block)
+    }
+}
+
+/// Extract the message of the first panic in a log, which is spread over multiple lines by
+/// assertions and by newer compilers.
+fn first_panic(log: &str) -> Option<Vec<&str>> {
+    let mut block = Vec::new();
+    for line in log.lines().map(strip_prefix) {
+        if block.is_empty() {
+            if is_panic(line) {
+                block.push(line);
+            }
+        } else if line.trim().is_empty()
+            || line.starts_with("note: ")
+            || line.starts_with("stack backtrace:")
+            || is_panic(line)
+        {
+            break;
+        } else {
+            block.push(line);
+        }
+    }
+
+    if block.is_empty() {
+        None
+    } else {
+        Some(block)
+    }
+}
+
+/// Extract the reason of a failure from its log: the panic message of the first failing test,
+/// or the first error emitted by the compiler.
+pub(super) fn extract_excerpt(log: &str, res: &TestResult) -> Option<String> {
+    let block = match res {
+        TestResult::TestFail(_) => first_panic(log).or_else(|| first_error_block(log)),
+        _ => first_error_block(log),
+    }?;
+
+    Some(
+        block
+            .into_iter()
+            .take(MAX_EXCERPT_LINES)
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract_excerpt, first_error_block, first_panic};
+    use crate::results::{FailureReason, TestResult};
+
+    #[test]
+    fn test_first_error_block() {
+        let log = "\
+            [INFO] [stderr]    Compiling foo v0.1.0 (/opt/rustwide/workdir)\n\
+            [INFO] [stderr] warning: unused variable: `x`\n\
+            [INFO] [stderr] error[E0308]: mismatched types\n\
+            [INFO] [stderr]   --> src/lib.rs:12:5\n\
+            [INFO] [stderr]    |\n\
+            [INFO] [stderr] 12 |     x\n\
+            [INFO] [stderr] \n\
+            [INFO] [stderr] error: aborting due to previous error\n";
+        assert_eq!(
+            first_error_block(log).unwrap(),
+            vec![
+                "error[E0308]: mismatched types",
+                "  --> src/lib.rs:12:5",
+                "   |",
+                "12 |     x",
+            ]
+        );
+
+        let summary_only = "[INFO] [stderr] error: could not compile `foo`.\n";
+        assert!(first_error_block(summary_only).is_none());
+    }
+
+    #[test]
+    fn test_first_panic() {
+        let old_format = "\
+            [INFO] [stdout] ---- tests::it_works stdout ----\n\
+            [INFO] [stdout] thread 'tests::it_works' panicked at 'assertion failed: `(left == right)`\n\
+            [INFO] [stdout]   left: `1`,\n\
+            [INFO] [stdout]  right: `2`', src/lib.rs:5:9\n\
+            [INFO] [stdout] note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace\n";
+        assert_eq!(
+            first_panic(old_format).unwrap(),
+            vec![
+                "thread 'tests::it_works' panicked at 'assertion failed: `(left == right)`",
+                "  left: `1`,",
+                " right: `2`', src/lib.rs:5:9",
+            ]
+        );
+
+        let new_format = "\
+            [INFO] [stdout] thread 'main' panicked at src/main.rs:2:5:\n\
+            [INFO] [stdout] explicit panic\n\
+            [INFO] [stdout] \n\
+            [INFO] [stdout] failures:\n";
+        assert_eq!(
+            first_panic(new_format).unwrap(),
+            vec![
+                "thread 'main' panicked at src/main.rs:2:5:",
+                "explicit panic"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_excerpt() {
+        let log = "\
+            [INFO] [stderr] error: doctest failed to compile\n\
+            [INFO] [stderr] \n\
+            [INFO] [stdout] thread 'main' panicked at src/main.rs:2:5:\n\
+            [INFO] [stdout] explicit panic\n";
+
+        assert_eq!(
+            extract_excerpt(log, &TestResult::TestFail(FailureReason::Unknown)).unwrap(),
+            "thread 'main' panicked at src/main.rs:2:5:\nexplicit panic"
+        );
+        assert_eq!(
+            extract_excerpt(log, &TestResult::BuildFail(FailureReason::Unknown)).unwrap(),
+            "error: doctest failed to compile"
+        );
+        assert!(extract_excerpt("[INFO] nothing to see here\n", &TestResult::TestPass).is_none());
+    }
+}
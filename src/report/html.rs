@@ -2,11 +2,19 @@ use crate::assets;
 use crate::experiments::Experiment;
 use crate::prelude::*;
 use crate::report::{
-    analyzer::ReportCrates, archives::Archive, Color, Comparison, CrateResult, ReportWriter,
-    ResultColor, ResultName, TestResults,
+    analyzer::ReportCrates,
+    archives::Archive,
+    blacklist::UnneededEntry,
+    clusters::Cluster,
+    diagnostics::DiagnosticsDiff,
+    diff::{ChangeKind, ReportDiff},
+    impact::RootCause,
+    timings::{format_delta, format_duration, CrateTiming, Timings},
+    Color, Comparison, CrateResult, ReportWriter, ResultColor, ResultName, TestResults,
 };
-use crate::results::EncodingType;
+use crate::results::{environment_differences, Diagnostic, EncodingType, Environment, Triage};
 use indexmap::IndexMap;
+use std::collections::BTreeMap;
 
 #[derive(Serialize)]
 struct NavbarItem {
@@ -19,6 +27,7 @@ struct NavbarItem {
 enum CurrentPage {
     Summary,
     Full,
+    Diagnostics,
     Downloads,
 }
 
@@ -48,6 +57,11 @@ impl CurrentPage {
                 url: "full.html",
                 active: *self == CurrentPage::Full,
             },
+            NavbarItem {
+                label: "Diagnostics",
+                url: "diagnostics.html",
+                active: *self == CurrentPage::Diagnostics,
+            },
             NavbarItem {
                 label: "Downloads",
                 url: "downloads.html",
@@ -61,6 +75,7 @@ impl CurrentPage {
 struct ResultsContext<'a> {
     ex: &'a Experiment,
     nav: Vec<NavbarItem>,
+    environment: &'a EnvironmentHTML,
     categories: Vec<(Comparison, ReportCratesHTML)>,
     info: IndexMap<Comparison, u32>,
     full: bool,
@@ -68,17 +83,116 @@ struct ResultsContext<'a> {
     comparison_colors: IndexMap<Comparison, Color>,
     result_colors: Vec<Color>,
     result_names: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    root_causes: Vec<RootCauseHTML>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    clusters: Vec<ClusterHTML>,
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    classified: IndexMap<String, Vec<CrateResultHTML>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    slowest: Vec<CrateTimingHTML>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    deltas: Vec<CrateTimingHTML>,
+    unneeded_blacklist: &'a [UnneededEntry],
+}
+
+/// The environments of the agents, shown in the header of the report.
+#[derive(Serialize)]
+struct EnvironmentHTML {
+    agents: BTreeMap<String, Environment>,
+    differences: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct RootCauseHTML {
+    name: String,
+    url: String,
+    res: Option<String>,
+    affected: Vec<CrateResultHTML>,
+}
+
+#[derive(Serialize)]
+struct ClusterHTML {
+    signature: String,
+    excerpt: Vec<String>,
+    crates: Vec<CrateResultHTML>,
+}
+
+#[derive(Serialize)]
+struct CrateTimingHTML {
+    name: String,
+    url: String,
+    runs: [Option<String>; 2],
+    delta: Option<String>,
+}
+
+impl CrateTimingHTML {
+    fn new(timing: &CrateTiming) -> Self {
+        CrateTimingHTML {
+            name: timing.name.clone(),
+            url: timing.url.clone(),
+            runs: [
+                timing.runs[0].map(format_duration),
+                timing.runs[1].map(format_duration),
+            ],
+            delta: timing.delta().map(format_delta),
+        }
+    }
 }
 
 #[derive(Serialize)]
 struct DownloadsContext<'a> {
     ex: &'a Experiment,
     nav: Vec<NavbarItem>,
+    environment: &'a EnvironmentHTML,
     crates_count: usize,
 
     available_archives: Vec<Archive>,
 }
 
+#[derive(Serialize)]
+struct DiagnosticsContext<'a> {
+    ex: &'a Experiment,
+    nav: Vec<NavbarItem>,
+    environment: &'a EnvironmentHTML,
+    crates_count: usize,
+    comparison_colors: IndexMap<Comparison, Color>,
+
+    diffs: Vec<DiagnosticsDiffHTML>,
+}
+
+#[derive(Serialize)]
+struct DiagnosticsDiffHTML {
+    name: String,
+    url: String,
+    res: Comparison,
+    added: Vec<DiagnosticHTML>,
+    removed: Vec<DiagnosticHTML>,
+}
+
+#[derive(Serialize)]
+struct DiagnosticHTML {
+    summary: String,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct DiffContext<'a> {
+    old: &'a str,
+    new: &'a str,
+    crates_count: usize,
+    comparison_colors: IndexMap<Comparison, Color>,
+
+    changes: IndexMap<ChangeKind, Vec<CrateChangeHTML>>,
+}
+
+#[derive(Serialize)]
+struct CrateChangeHTML {
+    name: String,
+    url: String,
+    runs: [Option<Comparison>; 2],
+}
+
 #[derive(Serialize)]
 struct CrateResultHTML {
     name: String,
@@ -87,6 +201,10 @@ struct CrateResultHTML {
     #[serde(skip_serializing_if = "Option::is_none")]
     status: Option<String>,
     runs: [Option<BuildTestResultHTML>; 2],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    excerpt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    triage: Option<Triage>,
 }
 
 // Map TestResult to usize to avoid the presence of special characters in html
@@ -98,8 +216,13 @@ struct BuildTestResultHTML {
 
 fn write_report<W: ReportWriter>(
     ex: &Experiment,
+    environment: &EnvironmentHTML,
     crates_count: usize,
     res: &TestResults,
+    root_causes: &[RootCause],
+    clusters: &[Cluster],
+    timings: &Timings,
+    unneeded_blacklist: &[UnneededEntry],
     full: bool,
     to: &str,
     dest: &W,
@@ -135,6 +258,8 @@ fn write_report<W: ReportWriter>(
             status: result.status.map(|status| status.to_string()),
             res: result.res,
             runs,
+            excerpt: result.excerpt,
+            triage: result.triage,
         }
     };
 
@@ -204,8 +329,50 @@ fn write_report<W: ReportWriter>(
         })
         .collect();
 
+    let classified = res
+        .classified
+        .iter()
+        .map(|(label, crates)| {
+            let crates = crates
+                .iter()
+                .filter(|krate| full || krate.res.show_in_summary())
+                .map(|krate| to_html_crate_result(krate.clone()))
+                .collect::<Vec<_>>();
+            (label.clone(), crates)
+        })
+        .filter(|(_, crates)| !crates.is_empty())
+        .collect();
+
+    let root_causes = root_causes
+        .iter()
+        .map(|root| RootCauseHTML {
+            name: root.name.clone(),
+            url: root.url.clone(),
+            res: root.res.as_ref().map(|res| res.long_name()),
+            affected: root
+                .affected
+                .iter()
+                .map(|krate| to_html_crate_result(krate.clone()))
+                .collect(),
+        })
+        .collect();
+
+    let clusters = clusters
+        .iter()
+        .map(|cluster| ClusterHTML {
+            signature: cluster.signature.clone(),
+            excerpt: cluster.excerpt.clone(),
+            crates: cluster
+                .crates
+                .iter()
+                .map(|krate| to_html_crate_result(krate.clone()))
+                .collect(),
+        })
+        .collect();
+
     let context = ResultsContext {
         ex,
+        environment,
         nav: if full {
             CurrentPage::Full
         } else {
@@ -219,6 +386,12 @@ fn write_report<W: ReportWriter>(
         comparison_colors,
         result_colors,
         result_names,
+        root_causes,
+        clusters,
+        classified,
+        slowest: timings.slowest.iter().map(CrateTimingHTML::new).collect(),
+        deltas: timings.deltas.iter().map(CrateTimingHTML::new).collect(),
+        unneeded_blacklist,
     };
 
     info!("generating {}", to);
@@ -238,6 +411,7 @@ fn write_report<W: ReportWriter>(
 
 fn write_downloads<W: ReportWriter>(
     ex: &Experiment,
+    environment: &EnvironmentHTML,
     crates_count: usize,
     available_archives: Vec<Archive>,
     dest: &W,
@@ -245,6 +419,7 @@ fn write_downloads<W: ReportWriter>(
 ) -> Fallible<()> {
     let context = DownloadsContext {
         ex,
+        environment,
         nav: CurrentPage::Downloads.navbar(),
         crates_count,
         available_archives,
@@ -265,20 +440,115 @@ fn write_downloads<W: ReportWriter>(
     Ok(())
 }
 
+fn write_diagnostics<W: ReportWriter>(
+    ex: &Experiment,
+    environment: &EnvironmentHTML,
+    crates_count: usize,
+    diffs: &[DiagnosticsDiff],
+    dest: &W,
+    output_templates: bool,
+) -> Fallible<()> {
+    let mut comparison_colors = IndexMap::new();
+    let to_html = |diags: &[Diagnostic]| {
+        diags
+            .iter()
+            .map(|diag| DiagnosticHTML {
+                summary: diag.to_string(),
+                message: diag.message.clone(),
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let diffs = diffs
+        .iter()
+        .map(|diff| {
+            comparison_colors.insert(diff.res, diff.res.color());
+            DiagnosticsDiffHTML {
+                name: diff.name.clone(),
+                url: diff.url.clone(),
+                res: diff.res,
+                added: to_html(&diff.added),
+                removed: to_html(&diff.removed),
+            }
+        })
+        .collect();
+
+    let context = DiagnosticsContext {
+        ex,
+        environment,
+        nav: CurrentPage::Diagnostics.navbar(),
+        crates_count,
+        comparison_colors,
+        diffs,
+    };
+
+    info!("generating diagnostics.html");
+    let html = minifier::html::minify(&assets::render_template(
+        "report/diagnostics.html",
+        &context,
+    )?);
+    dest.write_string("diagnostics.html", html.into(), &mime::TEXT_HTML)?;
+
+    if output_templates {
+        dest.write_string(
+            "diagnostics.html.context.json",
+            serde_json::to_string(&context)?.into(),
+            &mime::APPLICATION_JSON,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn write_assets<W: ReportWriter>(dest: &W) -> Fallible<()> {
+    let js_in = assets::load("report.js")?;
+    let css_in = assets::load("report.css")?;
+
+    info!("copying static assets");
+    dest.write_bytes(
+        "report.js",
+        js_in.content()?.into_owned(),
+        js_in.mime(),
+        EncodingType::Plain,
+    )?;
+    dest.write_bytes(
+        "report.css",
+        css_in.content()?.into_owned(),
+        css_in.mime(),
+        EncodingType::Plain,
+    )?;
+
+    Ok(())
+}
+
 pub fn write_html_report<W: ReportWriter>(
     ex: &Experiment,
+    environments: &BTreeMap<String, Environment>,
     crates_count: usize,
     res: &TestResults,
+    root_causes: &[RootCause],
+    clusters: &[Cluster],
+    timings: &Timings,
+    unneeded_blacklist: &[UnneededEntry],
+    diagnostics: &[DiagnosticsDiff],
     available_archives: Vec<Archive>,
     dest: &W,
     output_templates: bool,
 ) -> Fallible<()> {
-    let js_in = assets::load("report.js")?;
-    let css_in = assets::load("report.css")?;
+    let environment = EnvironmentHTML {
+        agents: environments.clone(),
+        differences: environment_differences(environments),
+    };
+
     write_report(
         ex,
+        &environment,
         crates_count,
         res,
+        root_causes,
+        clusters,
+        timings,
+        unneeded_blacklist,
         false,
         "index.html",
         dest,
@@ -286,28 +556,85 @@ pub fn write_html_report<W: ReportWriter>(
     )?;
     write_report(
         ex,
+        &environment,
         crates_count,
         res,
+        root_causes,
+        clusters,
+        timings,
+        unneeded_blacklist,
         true,
         "full.html",
         dest,
         output_templates,
     )?;
-    write_downloads(ex, crates_count, available_archives, dest, output_templates)?;
-
-    info!("copying static assets");
-    dest.write_bytes(
-        "report.js",
-        js_in.content()?.into_owned(),
-        js_in.mime(),
-        EncodingType::Plain,
+    write_diagnostics(
+        ex,
+        &environment,
+        crates_count,
+        diagnostics,
+        dest,
+        output_templates,
     )?;
-    dest.write_bytes(
-        "report.css",
-        css_in.content()?.into_owned(),
-        css_in.mime(),
-        EncodingType::Plain,
+    write_downloads(
+        ex,
+        &environment,
+        crates_count,
+        available_archives,
+        dest,
+        output_templates,
     )?;
+    write_assets(dest)?;
 
     Ok(())
 }
+
+pub fn write_html_diff<W: ReportWriter>(
+    diff: &ReportDiff,
+    dest: &W,
+    output_templates: bool,
+) -> Fallible<()> {
+    let mut comparison_colors = IndexMap::new();
+    let changes = diff
+        .changes
+        .iter()
+        .map(|(&kind, crates)| {
+            let crates = crates
+                .iter()
+                .map(|krate| {
+                    for res in krate.old.iter().chain(krate.new.iter()) {
+                        comparison_colors.insert(*res, res.color());
+                    }
+                    CrateChangeHTML {
+                        name: krate.name.clone(),
+                        url: krate.url.clone(),
+                        runs: [krate.old, krate.new],
+                    }
+                })
+                .collect::<Vec<_>>();
+            (kind, crates)
+        })
+        .collect();
+
+    let context = DiffContext {
+        old: &diff.old,
+        new: &diff.new,
+        crates_count: diff.changes.values().map(|crates| crates.len()).sum(),
+        comparison_colors,
+        changes,
+    };
+
+    info!("generating diff.html");
+    let html = minifier::html::minify(&assets::render_template("report/diff.html", &context)?);
+    dest.write_string("diff.html", html.into(), &mime::TEXT_HTML)?;
+
+    if output_templates {
+        dest.write_string(
+            "diff.html.context.json",
+            serde_json::to_string(&context)?.into(),
+            &mime::APPLICATION_JSON,
+        )?;
+    }
+
+    write_assets(dest)
+}
@@ -0,0 +1,218 @@
+use crate::crates::Crate;
+use crate::dirs::WORK_DIR;
+use crate::report::{crate_to_name, crate_to_url, Comparison, CrateResult, RawTestResults};
+use crate::results::{FailureReason, TestResult};
+use crates_index::{DependencyKind, Index};
+use indexmap::{IndexMap, IndexSet};
+use std::collections::{HashMap, HashSet};
+
+/// A crate breaking other crates of the experiment, with all the regressed crates depending on
+/// it either directly or transitively.
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct RootCause {
+    pub name: String,
+    pub url: String,
+    /// The result of the crate on the second toolchain, missing if the crate is only a
+    /// dependency reported as failing and wasn't tested in the experiment.
+    pub res: Option<TestResult>,
+    pub affected: Vec<CrateResult>,
+}
+
+/// Load the normal and build dependencies of the tested version of a crates.io crate.
+fn index_dependencies(index: &Index, krate: &Crate) -> Vec<String> {
+    let details = match krate {
+        Crate::Registry(details) => details,
+        _ => return Vec::new(),
+    };
+
+    index
+        .crate_(&details.name)
+        .and_then(|index_krate| {
+            index_krate
+                .versions()
+                .iter()
+                .find(|version| version.version() == details.version)
+                .map(|version| {
+                    version
+                        .dependencies()
+                        .iter()
+                        // dev-dependencies can be cyclic, and rarely break the crates using them
+                        .filter(|dep| !matches!(dep.kind(), DependencyKind::Dev))
+                        .map(|dep| format!("reg/{}", dep.crate_name()))
+                        .collect()
+                })
+        })
+        .unwrap_or_default()
+}
+
+fn find_root_causes<F>(raw: &RawTestResults, dependencies: F) -> Vec<RootCause>
+where
+    F: Fn(&Crate) -> Vec<String>,
+{
+    let regressed = raw
+        .crates
+        .iter()
+        .filter(|krate| krate.res == Comparison::Regressed)
+        .map(|krate| (krate.krate.unversioned_id(), krate))
+        .collect::<IndexMap<_, _>>();
+
+    // Dependencies reported as failing but not tested in the experiment
+    let mut untested = IndexMap::new();
+    let mut crate_deps = Vec::new();
+    for (key, krate) in &regressed {
+        let mut deps = dependencies(&krate.krate);
+        if let Some(run) = &krate.runs[1] {
+            if let TestResult::BuildFail(FailureReason::DependsOn(failed))
+            | TestResult::TestFail(FailureReason::DependsOn(failed)) = &run.res
+            {
+                for dep in failed {
+                    let dep_key = dep.unversioned_id();
+                    if !regressed.contains_key(&dep_key) {
+                        untested.insert(dep_key.clone(), dep.clone());
+                    }
+                    deps.push(dep_key);
+                }
+            }
+        }
+        crate_deps.push((key.as_str(), deps));
+    }
+
+    let mut dependents = HashMap::new();
+    let mut has_failing_deps = HashSet::new();
+    for (key, deps) in crate_deps {
+        for dep in deps.into_iter().collect::<IndexSet<_>>() {
+            if dep == key || !(regressed.contains_key(&dep) || untested.contains_key(&dep)) {
+                continue;
+            }
+            has_failing_deps.insert(key);
+            dependents.entry(dep).or_insert_with(Vec::new).push(key);
+        }
+    }
+
+    let affected_by = |root: &str| {
+        let mut affected = IndexSet::new();
+        let mut queue = vec![root];
+        while let Some(key) = queue.pop() {
+            for dependent in dependents.get(key).into_iter().flatten() {
+                if *dependent != root && affected.insert(*dependent) {
+                    queue.push(*dependent);
+                }
+            }
+        }
+
+        let mut affected = affected
+            .into_iter()
+            .map(|key| regressed[key].clone())
+            .collect::<Vec<_>>();
+        affected.sort_by(|a, b| a.name.cmp(&b.name));
+        affected
+    };
+
+    let tested_roots = regressed
+        .iter()
+        .filter(|(key, _)| !has_failing_deps.contains(key.as_str()))
+        .map(|(key, krate)| RootCause {
+            name: krate.name.clone(),
+            url: krate.url.clone(),
+            res: krate.runs[1].as_ref().map(|run| run.res.clone()),
+            affected: affected_by(key),
+        });
+    let untested_roots = untested.iter().map(|(key, krate)| RootCause {
+        name: crate_to_name(krate),
+        url: crate_to_url(krate),
+        res: None,
+        affected: affected_by(key),
+    });
+
+    let mut roots = tested_roots
+        .chain(untested_roots)
+        .filter(|root| !root.affected.is_empty())
+        .collect::<Vec<_>>();
+    roots.sort_by(|a, b| b.affected.len().cmp(&a.affected.len()));
+    roots
+}
+
+/// Rank the crates causing regressions by the number of regressed crates depending on them,
+/// using the dependencies listed in the crates.io index and the ones reported by the builds.
+pub fn analyze_impact(raw: &RawTestResults) -> Vec<RootCause> {
+    let index = Index::new(WORK_DIR.join("crates.io-index"));
+    find_root_causes(raw, |krate| index_dependencies(&index, krate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_root_causes, RootCause};
+    use crate::crates::{Crate, RegistryCrate};
+    use crate::report::{BuildTestResult, Comparison, CrateResult, RawTestResults};
+    use crate::results::{FailureReason, TestResult};
+    use std::collections::BTreeSet;
+
+    fn result(name: &str, res: Comparison, end: TestResult) -> CrateResult {
+        CrateResult {
+            runs: [None, Some(BuildTestResult::dummy(end))],
+            ..CrateResult::dummy(name, "1.0.0", res)
+        }
+    }
+
+    #[test]
+    fn test_find_root_causes() {
+        let ice = TestResult::BuildFail(FailureReason::ICE);
+        let depends_on = |name: &str| {
+            TestResult::BuildFail(FailureReason::DependsOn(btreeset![Crate::Registry(
+                RegistryCrate {
+                    name: name.into(),
+                    version: "0.1.0".into(),
+                }
+            )]))
+        };
+
+        let raw = RawTestResults {
+            crates: vec![
+                result("root", Comparison::Regressed, ice.clone()),
+                // Only `direct` and `tests` list their dependencies in the index, the other
+                // crates are linked by the dependencies reported as failing
+                result("direct", Comparison::Regressed, depends_on("root")),
+                result("transitive", Comparison::Regressed, depends_on("direct")),
+                result(
+                    "tests",
+                    Comparison::Regressed,
+                    TestResult::TestFail(FailureReason::Unknown),
+                ),
+                result("unrelated", Comparison::Regressed, ice.clone()),
+                result("working", Comparison::SameTestPass, TestResult::TestPass),
+                result("untested-1", Comparison::Regressed, depends_on("untested")),
+                result("untested-2", Comparison::Regressed, depends_on("untested")),
+            ],
+        };
+
+        let roots = find_root_causes(&raw, |krate| match krate {
+            Crate::Registry(details) if details.name == "direct" => vec!["reg/root".into()],
+            Crate::Registry(details) if details.name == "tests" => {
+                vec!["reg/transitive".into(), "reg/working".into()]
+            }
+            _ => Vec::new(),
+        });
+
+        assert_eq!(
+            roots,
+            vec![
+                RootCause {
+                    name: "root-1.0.0".into(),
+                    url: "https://crates.io/crates/root/1.0.0".into(),
+                    res: Some(ice),
+                    affected: vec![
+                        raw.crates[1].clone(),
+                        raw.crates[3].clone(),
+                        raw.crates[2].clone()
+                    ],
+                },
+                RootCause {
+                    name: "untested-0.1.0".into(),
+                    url: "https://crates.io/crates/untested/0.1.0".into(),
+                    res: None,
+                    affected: vec![raw.crates[6].clone(), raw.crates[7].clone()],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use crate::experiments::Experiment;
+use crate::prelude::*;
+use crate::report::{BuildTestResult, CrateResult, RawTestResults, ReportWriter};
+use crate::results::TestResult;
+use std::fmt::Write;
+
+/// Escape a string to be included in an XML attribute or text node. Control characters are not
+/// allowed in XML 1.0 even when escaped, so they're removed (logs are full of color codes).
+fn escape(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[derive(Default)]
+struct Counts {
+    tests: usize,
+    failures: usize,
+    errors: usize,
+    skipped: usize,
+}
+
+impl Counts {
+    fn add(&mut self, other: &Counts) {
+        self.tests += other.tests;
+        self.failures += other.failures;
+        self.errors += other.errors;
+        self.skipped += other.skipped;
+    }
+
+    fn attributes(&self) -> String {
+        format!(
+            "tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\"",
+            self.tests, self.failures, self.errors, self.skipped
+        )
+    }
+}
+
+fn write_testcase(
+    out: &mut String,
+    counts: &mut Counts,
+    toolchain: usize,
+    krate: &CrateResult,
+) -> Fallible<()> {
+    counts.tests += 1;
+    write!(
+        out,
+        "<testcase name=\"{}\" classname=\"{}\">",
+        escape(&krate.name),
+        escape(&krate.krate.id()),
+    )?;
+
+    match &krate.runs[toolchain] {
+        Some(BuildTestResult { res, log, .. }) => match res {
+            TestResult::BuildFail(_) | TestResult::TestFail(_) => {
+                counts.failures += 1;
+                let kind = if let TestResult::BuildFail(_) = res {
+                    "build-fail"
+                } else {
+                    "test-fail"
+                };
+                write!(
+                    out,
+                    "<failure type=\"{}\" message=\"{}\">log: {}/log.txt\n\n{}</failure>",
+                    kind,
+                    escape(&res.to_string()),
+                    escape(log),
+                    // Only the failing run of regressed and fixed crates has an excerpt, which
+                    // was extracted while the log was loaded for the report
+                    escape(krate.excerpt.as_deref().unwrap_or_default()),
+                )?;
+            }
+            TestResult::Error => {
+                counts.errors += 1;
+                write!(out, "<error message=\"{}\"/>", escape(&res.to_string()))?;
+            }
+            TestResult::BrokenCrate(_) | TestResult::Skipped => {
+                counts.skipped += 1;
+                write!(out, "<skipped message=\"{}\"/>", escape(&res.to_string()))?;
+            }
+            TestResult::TestSkipped | TestResult::TestPass => {}
+        },
+        None => {
+            counts.skipped += 1;
+            write!(out, "<skipped message=\"no result available\"/>")?;
+        }
+    }
+
+    writeln!(out, "</testcase>")?;
+    Ok(())
+}
+
+pub fn write_junit_report<W: ReportWriter>(
+    ex: &Experiment,
+    raw: &RawTestResults,
+    dest: &W,
+) -> Fallible<()> {
+    let mut suites = String::new();
+    let mut total = Counts::default();
+
+    // Each toolchain is a test suite, with a test case for every crate
+    for (i, tc) in ex.toolchains.iter().enumerate() {
+        let mut cases = String::new();
+        let mut counts = Counts::default();
+        for krate in &raw.crates {
+            write_testcase(&mut cases, &mut counts, i, krate)?;
+        }
+
+        writeln!(
+            suites,
+            "<testsuite name=\"{}\" {}>",
+            escape(&tc.to_string()),
+            counts.attributes()
+        )?;
+        suites.push_str(&cases);
+        writeln!(suites, "</testsuite>")?;
+        total.add(&counts);
+    }
+
+    let mut xml = String::new();
+    writeln!(xml, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        xml,
+        "<testsuites name=\"Crater report for {}\" {}>",
+        escape(&ex.name),
+        total.attributes()
+    )?;
+    xml.push_str(&suites);
+    writeln!(xml, "</testsuites>")?;
+
+    info!("generating junit.xml");
+    dest.write_string("junit.xml", xml.into(), &mime::TEXT_XML)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape, write_junit_report};
+    use crate::experiments::{CapLints, Experiment, Mode, Status};
+    use crate::report::{BuildTestResult, Comparison, CrateResult, DummyWriter, RawTestResults};
+    use crate::results::{FailureReason, TestResult};
+    use crate::toolchain::{MAIN_TOOLCHAIN, TEST_TOOLCHAIN};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("<a href=\"x\">'&'</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&apos;&amp;&apos;&lt;/a&gt;"
+        );
+        assert_eq!(escape("\x1b[31merror\x1b[0m\n"), "[31merror[0m\n");
+    }
+
+    #[test]
+    fn test_write_junit_report() {
+        let ex = Experiment {
+            name: "foo".to_string(),
+            toolchains: [MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()],
+            mode: Mode::BuildAndTest,
+            cap_lints: CapLints::Forbid,
+            priority: 0,
+            created_at: ::chrono::Utc::now(),
+            started_at: None,
+            completed_at: None,
+            github_issue: None,
+            status: Status::GeneratingReport,
+            assigned_to: None,
+            report_url: None,
+            ignore_blacklist: false,
+            requirement: None,
+        };
+        let raw = RawTestResults {
+            crates: vec![CrateResult {
+                runs: [
+                    Some(BuildTestResult {
+                        log: "stable/reg/lazy_static-1.0".into(),
+                        ..BuildTestResult::dummy(TestResult::TestPass)
+                    }),
+                    Some(BuildTestResult {
+                        log: "beta/reg/lazy_static-1.0".into(),
+                        ..BuildTestResult::dummy(TestResult::BuildFail(FailureReason::OOM))
+                    }),
+                ],
+                excerpt: Some("error: <oops>".into()),
+                ..CrateResult::dummy("lazy_static", "1.0", Comparison::Regressed)
+            }],
+        };
+
+        let writer = DummyWriter::default();
+        write_junit_report(&ex, &raw, &writer).unwrap();
+        let xml = String::from_utf8(writer.get("junit.xml", &mime::TEXT_XML)).unwrap();
+
+        assert!(xml.contains(
+            "<testsuites name=\"Crater report for foo\" \
+             tests=\"2\" failures=\"1\" errors=\"0\" skipped=\"0\">"
+        ));
+        assert!(xml.contains(&format!(
+            "<testsuite name=\"{}\" tests=\"1\" failures=\"0\" errors=\"0\" skipped=\"0\">\n\
+             <testcase name=\"lazy_static-1.0\" classname=\"reg/lazy_static/1.0\"></testcase>",
+            *MAIN_TOOLCHAIN
+        )));
+        assert!(xml.contains(
+            "<failure type=\"build-fail\" message=\"build-fail:oom\">\
+             log: beta/reg/lazy_static-1.0/log.txt\n\n\
+             error: &lt;oops&gt;</failure>"
+        ));
+    }
+}
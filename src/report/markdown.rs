@@ -1,9 +1,14 @@
 use crate::crates::Crate;
-use crate::experiments::Experiment;
+use crate::experiments::{Experiment, Status};
 use crate::prelude::*;
 use crate::report::analyzer::{ReportConfig, ReportCrates, ToolchainSelect};
+use crate::report::blacklist::UnneededEntry;
+use crate::report::clusters::Cluster;
+use crate::report::impact::RootCause;
+use crate::report::timings::{format_delta, format_duration, CrateTiming, Timings};
 use crate::report::{
-    crate_to_url, BuildTestResult, Comparison, CrateResult, ReportWriter, ResultName, TestResults,
+    crate_to_url, diff::ReportDiff, BuildTestResult, Comparison, CrateResult, ReportWriter,
+    ResultName, TestResults,
 };
 use crate::utils::serialize::to_vec;
 use indexmap::{IndexMap, IndexSet};
@@ -28,6 +33,98 @@ struct ResultsContext<'a> {
     info: IndexMap<Comparison, u32>,
     full: bool,
     crates_count: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    root_causes: Vec<RootCauseMD>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    clusters: Vec<ClusterMD>,
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    classified: IndexMap<String, Vec<CrateResult>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    slowest: Vec<CrateTimingMD>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    deltas: Vec<CrateTimingMD>,
+    unneeded_blacklist: &'a [UnneededEntry],
+}
+
+#[derive(Serialize)]
+struct RootCauseMD {
+    name: String,
+    url: String,
+    res: Option<String>,
+    affected: usize,
+}
+
+#[derive(Serialize)]
+struct ClusterMD {
+    signature: String,
+    excerpt: Vec<String>,
+    crates: Vec<CrateResult>,
+}
+
+#[derive(Serialize)]
+struct CrateTimingMD {
+    name: String,
+    url: String,
+    runs: [Option<String>; 2],
+    delta: Option<String>,
+}
+
+impl CrateTimingMD {
+    fn new(timing: &CrateTiming) -> Self {
+        CrateTimingMD {
+            name: timing.name.clone(),
+            url: timing.url.clone(),
+            runs: [
+                timing.runs[0].map(format_duration),
+                timing.runs[1].map(format_duration),
+            ],
+            delta: timing.delta().map(format_delta),
+        }
+    }
+}
+
+fn write_timings(rendered: &mut String, title: &str, timings: &[CrateTimingMD]) -> Fallible<()> {
+    let or_missing = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".into());
+
+    writeln!(rendered, "\n### {}", title)?;
+    writeln!(
+        rendered,
+        "| crate | start toolchain | end toolchain | delta |"
+    )?;
+    writeln!(rendered, "| --- | --- | --- | --- |")?;
+    for timing in timings {
+        writeln!(
+            rendered,
+            "| [{}]({}) | {} | {} | {} |",
+            timing.name,
+            timing.url,
+            or_missing(&timing.runs[0]),
+            or_missing(&timing.runs[1]),
+            or_missing(&timing.delta)
+        )?;
+    }
+    Ok(())
+}
+
+/// Excerpts longer than this are only shown in the HTML report, to keep the Markdown one readable.
+const MAX_INLINE_EXCERPT_LINES: usize = 5;
+const MAX_INLINE_EXCERPT_LEN: usize = 500;
+
+fn triage_summary(krate: &CrateResult) -> String {
+    let triage = match &krate.triage {
+        Some(triage) => triage,
+        None => return String::new(),
+    };
+
+    let mut summary = format!(" (triage: {}", triage.status);
+    if !triage.note.is_empty() {
+        summary.push_str(&format!(", {}", triage.note));
+    }
+    if let Some(link) = &triage.link {
+        summary.push_str(&format!(", [link]({})", link));
+    }
+    summary.push(')');
+    summary
 }
 
 fn write_crate(
@@ -68,6 +165,7 @@ fn write_crate(
         .status
         .map(|status| format!(" ({})", status.to_string()))
         .unwrap_or_default();
+    let triage = triage_summary(krate);
 
     if let ReportConfig::Complete(toolchain) = comparison.report_config() {
         let (conj, run) = match toolchain {
@@ -77,7 +175,7 @@ fn write_crate(
 
         writeln!(
             &mut rendered,
-            "{}[{}{}]({}) {} {} **{}** [start]({}/log.txt) | [end]({}/log.txt)",
+            "{}[{}{}]({}) {} {} **{}** [start]({}/log.txt) | [end]({}/log.txt){}",
             prefix,
             krate.name,
             status_warning,
@@ -86,30 +184,60 @@ fn write_crate(
             conj,
             runs[run],
             runs[1],
-            runs[3]
+            runs[3],
+            triage,
         )?;
     } else {
         writeln!(
             &mut rendered,
-            "{}[{}{}]({}) {} [start]({}/log.txt) | [end]({}/log.txt)",
+            "{}[{}{}]({}) {} [start]({}/log.txt) | [end]({}/log.txt){}",
             prefix,
             krate.name,
             status_warning,
             krate.url,
             comparison.to_string(),
             runs[1],
-            runs[3]
+            runs[3],
+            triage,
         )?;
     };
 
     Ok(())
 }
 
+fn write_excerpt(rendered: &mut String, krate: &CrateResult, is_child: bool) -> Fallible<()> {
+    let excerpt = match &krate.excerpt {
+        Some(excerpt) => excerpt,
+        None => return Ok(()),
+    };
+    if excerpt.lines().count() > MAX_INLINE_EXCERPT_LINES || excerpt.len() > MAX_INLINE_EXCERPT_LEN
+    {
+        return Ok(());
+    }
+
+    let indent = if is_child { "    " } else { "  " };
+    writeln!(rendered, "{}> ```", indent)?;
+    for line in excerpt.lines() {
+        writeln!(rendered, "{}> {}", indent, line)?;
+    }
+    writeln!(rendered, "{}> ```", indent)?;
+
+    Ok(())
+}
+
 fn render_markdown(context: &ResultsContext) -> Fallible<String> {
     let mut rendered = String::new();
 
     //add title
     writeln!(&mut rendered, "# Crater report for {}\n\n", context.ex.name)?;
+    if context.ex.status == Status::Running {
+        writeln!(
+            &mut rendered,
+            "**This report is incomplete:** the experiment is still running, and only the \
+             crates already tested with both toolchains are included. The logs are only available \
+             in the final report.\n"
+        )?;
+    }
 
     for (comparison, results) in context.categories.iter() {
         writeln!(&mut rendered, "\n### {}", comparison.to_string())?;
@@ -117,13 +245,16 @@ fn render_markdown(context: &ResultsContext) -> Fallible<String> {
             ReportCratesMD::Plain(crates) => {
                 for krate in crates {
                     write_crate(&mut rendered, krate, *comparison, false)?;
+                    write_excerpt(&mut rendered, krate, false)?;
                 }
             }
             ReportCratesMD::Complete { res, orphans } => {
                 for (root, deps) in res {
                     write_crate(&mut rendered, root, *comparison, false)?;
+                    write_excerpt(&mut rendered, root, false)?;
                     for krate in deps {
                         write_crate(&mut rendered, krate, *comparison, true)?;
+                        write_excerpt(&mut rendered, krate, true)?;
                     }
                 }
 
@@ -136,12 +267,87 @@ fn render_markdown(context: &ResultsContext) -> Fallible<String> {
                     )?;
                     for krate in deps {
                         write_crate(&mut rendered, krate, *comparison, true)?;
+                        write_excerpt(&mut rendered, krate, true)?;
                     }
                 }
             }
         }
     }
 
+    if !context.root_causes.is_empty() {
+        writeln!(&mut rendered, "\n### most impactful root causes")?;
+        writeln!(&mut rendered, "| root cause | result | affected crates |")?;
+        writeln!(&mut rendered, "| --- | --- | --- |")?;
+        for root in context.root_causes.iter() {
+            writeln!(
+                &mut rendered,
+                "| [{}]({}) | {} | {} |",
+                root.name,
+                root.url,
+                root.res.as_deref().unwrap_or("not tested"),
+                root.affected
+            )?;
+        }
+    }
+
+    if !context.clusters.is_empty() {
+        writeln!(
+            &mut rendered,
+            "\n### regression clusters (grouped by their first error)"
+        )?;
+        for cluster in context.clusters.iter() {
+            writeln!(
+                &mut rendered,
+                "\n#### {} ({} crates)\n```\n{}\n```",
+                cluster.signature.lines().next().unwrap_or_default(),
+                cluster.crates.len(),
+                cluster.excerpt.join("\n")
+            )?;
+            for krate in &cluster.crates {
+                write_crate(&mut rendered, krate, krate.res, false)?;
+            }
+        }
+    }
+
+    if !context.classified.is_empty() {
+        writeln!(&mut rendered, "\n### classified failures")?;
+        for (label, crates) in context.classified.iter() {
+            writeln!(&mut rendered, "\n#### {}", label)?;
+            for krate in crates {
+                write_crate(&mut rendered, krate, krate.res, false)?;
+            }
+        }
+    }
+
+    if !context.slowest.is_empty() {
+        write_timings(&mut rendered, "slowest crates", &context.slowest)?;
+    }
+    if !context.deltas.is_empty() {
+        write_timings(
+            &mut rendered,
+            "biggest time deltas (crates with the same result on both toolchains)",
+            &context.deltas,
+        )?;
+    }
+
+    if !context.unneeded_blacklist.is_empty() {
+        writeln!(
+            &mut rendered,
+            "\n### blacklist entries no longer needed (crates run with the blacklist ignored)"
+        )?;
+        for entry in context.unneeded_blacklist {
+            write!(
+                &mut rendered,
+                "* [{}]({}): `{}` can be removed",
+                entry.name, entry.url, entry.option
+            )?;
+            if let Some(reason) = &entry.reason {
+                write!(&mut rendered, " (blacklisted because: {})", reason)?;
+            }
+            writeln!(&mut rendered)?;
+        }
+    }
+
     Ok(rendered)
 }
 
@@ -149,6 +355,10 @@ fn write_report<W: ReportWriter>(
     ex: &Experiment,
     crates_count: usize,
     res: &TestResults,
+    root_causes: &[RootCause],
+    clusters: &[Cluster],
+    timings: &Timings,
+    unneeded_blacklist: &[UnneededEntry],
     full: bool,
     to: &str,
     dest: &W,
@@ -182,12 +392,51 @@ fn write_report<W: ReportWriter>(
         })
         .collect();
 
+    let classified = res
+        .classified
+        .iter()
+        .map(|(label, crates)| {
+            let crates = crates
+                .iter()
+                .filter(|krate| full || krate.res.show_in_summary())
+                .cloned()
+                .collect::<Vec<_>>();
+            (label.clone(), crates)
+        })
+        .filter(|(_, crates)| !crates.is_empty())
+        .collect();
+
+    let root_causes = root_causes
+        .iter()
+        .map(|root| RootCauseMD {
+            name: root.name.clone(),
+            url: root.url.clone(),
+            res: root.res.as_ref().map(|res| res.long_name()),
+            affected: root.affected.len(),
+        })
+        .collect();
+
+    let clusters = clusters
+        .iter()
+        .map(|cluster| ClusterMD {
+            signature: cluster.signature.clone(),
+            excerpt: cluster.excerpt.clone(),
+            crates: cluster.crates.clone(),
+        })
+        .collect();
+
     let context = ResultsContext {
         ex,
         categories,
         info: res.info.clone(),
         full,
         crates_count,
+        root_causes,
+        clusters,
+        classified,
+        slowest: timings.slowest.iter().map(CrateTimingMD::new).collect(),
+        deltas: timings.deltas.iter().map(CrateTimingMD::new).collect(),
+        unneeded_blacklist,
     };
 
     let markdown = render_markdown(&context)?;
@@ -209,6 +458,10 @@ pub fn write_markdown_report<W: ReportWriter>(
     ex: &Experiment,
     crates_count: usize,
     res: &TestResults,
+    root_causes: &[RootCause],
+    clusters: &[Cluster],
+    timings: &Timings,
+    unneeded_blacklist: &[UnneededEntry],
     dest: &W,
     output_templates: bool,
 ) -> Fallible<()> {
@@ -216,6 +469,10 @@ pub fn write_markdown_report<W: ReportWriter>(
         ex,
         crates_count,
         res,
+        root_causes,
+        clusters,
+        timings,
+        unneeded_blacklist,
         false,
         "markdown.md",
         dest,
@@ -223,3 +480,54 @@ pub fn write_markdown_report<W: ReportWriter>(
     )?;
     Ok(())
 }
+
+fn render_markdown_diff(diff: &ReportDiff) -> Fallible<String> {
+    let mut rendered = String::new();
+    let name = |res: Option<Comparison>| res.map(|res| res.to_str()).unwrap_or("not tested");
+
+    writeln!(
+        &mut rendered,
+        "# Crater report diff between {} and {}\n\n",
+        diff.old, diff.new
+    )?;
+
+    for (kind, crates) in diff.changes.iter() {
+        writeln!(&mut rendered, "\n### {}", kind.to_string())?;
+        for krate in crates {
+            writeln!(
+                &mut rendered,
+                "* [{}]({}) {} → {}",
+                krate.name,
+                krate.url,
+                name(krate.old),
+                name(krate.new)
+            )?;
+        }
+    }
+
+    if diff.changes.is_empty() {
+        writeln!(&mut rendered, "No crate changed between the two reports.")?;
+    }
+
+    Ok(rendered)
+}
+
+pub fn write_markdown_diff<W: ReportWriter>(
+    diff: &ReportDiff,
+    dest: &W,
+    output_templates: bool,
+) -> Fallible<()> {
+    info!("generating diff.md");
+    let markdown = render_markdown_diff(diff)?;
+    dest.write_string("diff.md", markdown.into(), &mime::TEXT_PLAIN)?;
+
+    if output_templates {
+        dest.write_string(
+            "diff.md.context.json",
+            serde_json::to_string(diff)?.into(),
+            &mime::TEXT_PLAIN,
+        )?;
+    }
+
+    Ok(())
+}
@@ -1,10 +1,12 @@
 use crate::config::Config;
 use crate::crates::Crate;
 use crate::dirs::WORK_DIR;
-use crate::experiments::Experiment;
+use crate::experiments::{Experiment, Mode};
 use crate::prelude::*;
 use crate::report::analyzer::{analyze_report, ReportConfig, ToolchainSelect};
-use crate::results::{EncodedLog, EncodingType, FailureReason, ReadResults, TestResult};
+use crate::results::{
+    EncodedLog, EncodingType, FailureReason, ReadResults, TaskTiming, TestResult, Triage,
+};
 use crate::toolchain::Toolchain;
 use crate::utils;
 use crates_index::Index;
@@ -13,7 +15,6 @@ use percent_encoding::{utf8_percent_encode, AsciiSet};
 use std::borrow::Cow;
 #[cfg(test)]
 use std::cell::RefCell;
-#[cfg(test)]
 use std::collections::HashMap;
 use std::convert::AsRef;
 use std::fmt::{self, Display};
@@ -23,13 +24,27 @@ use std::path::{Path, PathBuf};
 
 mod analyzer;
 mod archives;
+mod blacklist;
+mod broken;
+mod bug_reports;
+mod clusters;
+mod csv;
+mod diagnostics;
+mod diff;
 mod display;
+mod excerpt;
 mod html;
+mod impact;
+mod junit;
 mod markdown;
 mod s3;
+mod schema;
+mod timings;
 
+pub use self::broken::{find_broken_crates, render_skip_list, BrokenCrates, ConfigKey};
+pub use self::diff::{diff_reports, gen_diff, ChangeKind, CrateChange, ReportDiff, ReportSource};
 pub use self::display::{Color, ResultColor, ResultName};
-pub use self::s3::{get_client_for_bucket, S3Prefix, S3Writer};
+pub use self::s3::{get_client_for_bucket, get_s3_client_for_bucket, S3Prefix, S3Writer};
 pub use analyzer::TestResults;
 
 pub(crate) const REPORT_ENCODE_SET: AsciiSet = percent_encoding::CONTROLS
@@ -44,7 +59,7 @@ pub(crate) const REPORT_ENCODE_SET: AsciiSet = percent_encoding::CONTROLS
     .add(b'}')
     .add(b'+');
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct RawTestResults {
     pub crates: Vec<CrateResult>,
 }
@@ -59,6 +74,13 @@ pub struct CrateResult {
     status: Option<CrateVersionStatus>,
     pub res: Comparison,
     runs: [Option<BuildTestResult>; 2],
+    /// The first error or panic message in the log of the failing run, for regressed and fixed
+    /// crates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    excerpt: Option<String>,
+    /// The decision of the reviewers about the result, if any was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    triage: Option<Triage>,
 }
 
 string_enum!(enum CrateVersionStatus {
@@ -68,6 +90,28 @@ string_enum!(enum CrateVersionStatus {
     MissingFromIndex => "missing from the index",
 });
 
+string_enum!(pub enum ReportFormat {
+    Html => "html",
+    Markdown => "markdown",
+    JUnit => "junit",
+    Csv => "csv",
+});
+
+impl ReportFormat {
+    pub fn all() -> Vec<ReportFormat> {
+        ReportFormat::possible_values()
+            .iter()
+            .map(|format| format.parse().unwrap())
+            .collect()
+    }
+
+    /// The formats generated when none is selected: the JUnit and CSV outputs are only useful
+    /// to some tools, so they're only written on request.
+    pub fn defaults() -> Vec<ReportFormat> {
+        vec![ReportFormat::Html, ReportFormat::Markdown]
+    }
+}
+
 string_enum!(pub enum Comparison {
     Regressed => "regressed",
     Fixed => "fixed",
@@ -124,6 +168,11 @@ impl Comparison {
 struct BuildTestResult {
     res: TestResult,
     log: String,
+    /// The label of the classifier matching the log, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    classifier: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timing: Option<TaskTiming>,
 }
 
 /// The type of sanitization required for a string.
@@ -217,99 +266,176 @@ pub fn generate_report<DB: ReadResults>(
     ex: &Experiment,
     crates: &[Crate],
 ) -> Fallible<RawTestResults> {
+    let (raw, _) = generate_report_with_logs(db, config, ex, crates, None)?;
+    Ok(raw)
+}
+
+/// The first error in the log of each regressed crate, used to cluster the regressions.
+type ErrorBlocks = HashMap<Crate, Vec<String>>;
+
+/// Generate the report, loading each log only once: every log is passed to `write_log` if it's
+/// provided, and the excerpts and the first errors of the regressed crates are extracted from the
+/// same copy. Without `write_log` only the logs of the failing runs are loaded.
+fn generate_report_with_logs<DB: ReadResults>(
+    db: &DB,
+    config: &Config,
+    ex: &Experiment,
+    crates: &[Crate],
+    mut write_log: Option<&mut dyn FnMut(&Crate, &Toolchain, EncodedLog) -> Fallible<()>>,
+) -> Fallible<(RawTestResults, ErrorBlocks)> {
     let mut crates = crates.to_vec();
     let index = Index::new(WORK_DIR.join("crates.io-index"));
+    let mut triage = db.load_triage(ex)?;
     //crate ids are unique so unstable sort is equivalent to stable sort but is generally faster
     crates.sort_unstable_by_key(|a| a.id());
-    let res = crates
-        .iter()
-        .map(|krate| {
-            // Any errors here will turn into unknown results
-            let crate_results = ex.toolchains.iter().map(|tc| -> Fallible<BuildTestResult> {
-                let res = db
-                    .load_test_result(ex, tc, &krate)?
-                    .ok_or_else(|| err_msg("no result"))?;
-
-                Ok(BuildTestResult {
-                    res,
-                    log: crate_to_path_fragment(tc, &krate, SanitizationContext::Url)
-                        .to_str()
-                        .unwrap()
-                        .replace(r"\", "/"), // Normalize paths in reports generated on Windows
-                })
-            });
-            // Convert errors to Nones
-            let mut crate_results = crate_results.map(|r| r.ok()).collect::<Vec<_>>();
-            let crate2 = crate_results.pop().unwrap();
-            let crate1 = crate_results.pop().unwrap();
-            let comp = compare(
-                config,
-                &krate,
-                crate1.as_ref().map(|b| &b.res),
-                crate2.as_ref().map(|b| &b.res),
-            );
 
-            Ok(CrateResult {
-                name: crate_to_name(&krate),
-                url: crate_to_url(&krate),
-                status: get_crate_version_status(&index, &krate)
-                    .unwrap_or(Some(CrateVersionStatus::MissingFromIndex)),
-                krate: krate.clone(),
-                res: comp,
-                runs: [crate1, crate2],
+    let num_crates = crates.len();
+    let progress_every = (num_crates / PROGRESS_FRACTION) + 1;
+    let mut error_blocks = HashMap::new();
+    let mut res = Vec::with_capacity(num_crates);
+    for (i, krate) in crates.iter().enumerate() {
+        if write_log.is_some() && i % progress_every == 0 {
+            info!("wrote logs for {}/{} crates", i, num_crates)
+        }
+
+        // Any errors here will turn into unknown results
+        let crate_results = ex.toolchains.iter().map(|tc| -> Fallible<BuildTestResult> {
+            let (res, timing) = db
+                .load_test_result_with_timing(ex, tc, &krate)?
+                .ok_or_else(|| err_msg("no result"))?;
+
+            Ok(BuildTestResult {
+                res,
+                log: crate_log_path(tc, &krate),
+                classifier: db.load_classifier(ex, tc, &krate)?,
+                timing,
             })
-        })
-        .collect::<Fallible<Vec<_>>>()?;
+        });
+        // Convert errors to Nones
+        let mut crate_results = crate_results.map(|r| r.ok()).collect::<Vec<_>>();
+        let crate2 = crate_results.pop().unwrap();
+        let crate1 = crate_results.pop().unwrap();
+        let comp = compare(
+            config,
+            ex.mode,
+            &krate,
+            crate1.as_ref().map(|b| &b.res),
+            crate2.as_ref().map(|b| &b.res),
+        );
+        let runs = [crate1, crate2];
+        let comp = apply_spurious_classifiers(config, comp, &runs);
 
-    Ok(RawTestResults { crates: res })
+        let failing_run = match comp {
+            Comparison::Regressed => Some(1),
+            Comparison::Fixed => Some(0),
+            _ => None,
+        };
+        let mut excerpt = None;
+        let skipped = config.should_skip_in_mode(krate, ex.mode);
+        for (idx, tc) in ex.toolchains.iter().enumerate() {
+            let is_failing = failing_run == Some(idx);
+            let writer = write_log.as_mut().filter(|_| !skipped);
+            if !is_failing && writer.is_none() {
+                continue;
+            }
+            let log = match load_log(db, ex, tc, krate) {
+                Some(log) => log,
+                None => continue,
+            };
+
+            if let (true, Some(run)) = (is_failing, &runs[idx]) {
+                if let Some(plain) = log_to_string(&log, tc, krate) {
+                    excerpt = excerpt::extract_excerpt(&plain, &run.res);
+                    if comp == Comparison::Regressed {
+                        if let Some(block) = excerpt::first_error_block(&plain) {
+                            let block = block.into_iter().map(String::from).collect();
+                            error_blocks.insert(krate.clone(), block);
+                        }
+                    }
+                }
+            }
+            if let Some(writer) = writer {
+                writer(krate, tc, log)?;
+            }
+        }
+
+        res.push(CrateResult {
+            name: crate_to_name(&krate),
+            url: crate_to_url(&krate),
+            status: get_crate_version_status(&index, &krate)
+                .unwrap_or(Some(CrateVersionStatus::MissingFromIndex)),
+            krate: krate.clone(),
+            res: comp,
+            runs,
+            excerpt,
+            triage: triage.remove(krate),
+        });
+    }
+
+    Ok((RawTestResults { crates: res }, error_blocks))
 }
 
 const PROGRESS_FRACTION: usize = 10; // write progress every ~1/N crates
 
-fn write_logs<DB: ReadResults, W: ReportWriter>(
-    db: &DB,
-    ex: &Experiment,
-    crates: &[Crate],
+fn write_log<W: ReportWriter>(
     dest: &W,
-    config: &Config,
+    krate: &Crate,
+    tc: &Toolchain,
+    content: EncodedLog,
 ) -> Fallible<()> {
-    let num_crates = crates.len();
-    let progress_every = (num_crates / PROGRESS_FRACTION) + 1;
-    for (i, krate) in crates.iter().enumerate() {
-        if i % progress_every == 0 {
-            info!("wrote logs for {}/{} crates", i, num_crates)
+    let log_path = crate_to_path_fragment(tc, krate, SanitizationContext::Path).join("log.txt");
+    match content {
+        EncodedLog::Plain(data) => {
+            dest.write_bytes(log_path, data, &mime::TEXT_PLAIN_UTF_8, EncodingType::Plain)
         }
-
-        if config.should_skip(krate) {
-            continue;
+        EncodedLog::Gzip(data) => {
+            dest.write_bytes(log_path, data, &mime::TEXT_PLAIN_UTF_8, EncodingType::Gzip)
+        }
+        EncodedLog::Zstd(_) => {
+            // Browsers can't decode zstd, so these logs are served gzipped instead
+            let gzip = EncodedLog::from_plain_slice(&content.to_plain()?, EncodingType::Gzip)?;
+            dest.write_bytes(
+                log_path,
+                gzip.as_slice().to_vec(),
+                &mime::TEXT_PLAIN_UTF_8,
+                EncodingType::Gzip,
+            )
         }
+    }
+}
 
-        for tc in &ex.toolchains {
-            let log_path =
-                crate_to_path_fragment(tc, krate, SanitizationContext::Path).join("log.txt");
-            let content = db
-                .load_log(ex, tc, krate)
-                .and_then(|c| c.ok_or_else(|| err_msg("missing logs")))
-                .with_context(|_| format!("failed to read log of {} on {}", krate, tc.to_string()));
-            let content = match content {
-                Ok(c) => c,
-                Err(e) => {
-                    utils::report_failure(&e);
-                    continue;
-                }
-            };
+/// Load the log of a crate, reporting the failure if it can't be loaded: a missing log shouldn't
+/// prevent the rest of the report from being generated.
+fn load_log<DB: ReadResults>(
+    db: &DB,
+    ex: &Experiment,
+    toolchain: &Toolchain,
+    krate: &Crate,
+) -> Option<EncodedLog> {
+    let log = db
+        .load_log(ex, toolchain, krate)
+        .and_then(|log| log.ok_or_else(|| err_msg("missing logs")))
+        .with_context(|_| format!("failed to read log of {} on {}", krate, toolchain));
+    match log {
+        Ok(log) => Some(log),
+        Err(err) => {
+            utils::report_failure(&err);
+            None
+        }
+    }
+}
 
-            match content {
-                EncodedLog::Plain(data) => {
-                    dest.write_bytes(log_path, data, &mime::TEXT_PLAIN_UTF_8, EncodingType::Plain)
-                }
-                EncodedLog::Gzip(data) => {
-                    dest.write_bytes(log_path, data, &mime::TEXT_PLAIN_UTF_8, EncodingType::Gzip)
-                }
-            }?;
+fn log_to_string(log: &EncodedLog, toolchain: &Toolchain, krate: &Crate) -> Option<String> {
+    let plain = log
+        .to_plain()
+        .with_context(|_| format!("failed to decode log of {} on {}", krate, toolchain));
+    match plain {
+        Ok(plain) => Some(String::from_utf8_lossy(&plain).into_owned()),
+        Err(err) => {
+            utils::report_failure(&err);
+            None
         }
     }
-    Ok(())
 }
 
 pub fn gen<DB: ReadResults, W: ReportWriter + Display>(
@@ -318,11 +444,57 @@ pub fn gen<DB: ReadResults, W: ReportWriter + Display>(
     crates: &[Crate],
     dest: &W,
     config: &Config,
+    formats: &[ReportFormat],
     output_templates: bool,
 ) -> Fallible<TestResults> {
-    let raw = generate_report(db, config, ex, crates)?;
+    gen_inner(
+        db,
+        ex,
+        crates,
+        dest,
+        config,
+        formats,
+        output_templates,
+        true,
+    )
+}
 
+/// Generate a partial report of a running experiment. Only the HTML and Markdown pages are
+/// written: the logs, their archives and the bug report drafts linking to them are left to the
+/// final report, so that generating it is quick.
+pub fn gen_partial<DB: ReadResults, W: ReportWriter + Display>(
+    db: &DB,
+    ex: &Experiment,
+    crates: &[Crate],
+    dest: &W,
+    config: &Config,
+) -> Fallible<TestResults> {
+    let formats = [ReportFormat::Html, ReportFormat::Markdown];
+    gen_inner(db, ex, crates, dest, config, &formats, false, false)
+}
+
+fn gen_inner<DB: ReadResults, W: ReportWriter + Display>(
+    db: &DB,
+    ex: &Experiment,
+    crates: &[Crate],
+    dest: &W,
+    config: &Config,
+    formats: &[ReportFormat],
+    output_templates: bool,
+    with_logs: bool,
+) -> Fallible<TestResults> {
     info!("writing results to {}", dest);
+    let mut write =
+        |krate: &Crate, tc: &Toolchain, log: EncodedLog| write_log(dest, krate, tc, log);
+    let write_logs: Option<&mut dyn FnMut(&Crate, &Toolchain, EncodedLog) -> Fallible<()>> =
+        if with_logs {
+            info!("writing logs");
+            Some(&mut write)
+        } else {
+            None
+        };
+    let (raw, error_blocks) = generate_report_with_logs(db, config, ex, crates, write_logs)?;
+
     info!("writing metadata");
     dest.write_string(
         "results.json",
@@ -340,23 +512,80 @@ pub fn gen<DB: ReadResults, W: ReportWriter + Display>(
         &mime::TEXT_PLAIN_UTF_8,
     )?;
 
-    let res = analyze_report(raw);
-    info!("writing archives");
-    let available_archives = archives::write_logs_archives(db, ex, crates, dest, config)?;
-    info!("writing html files");
-    html::write_html_report(
-        ex,
-        crates.len(),
-        &res,
-        available_archives,
-        dest,
-        output_templates,
+    let res = analyze_report(raw.clone());
+    dest.write_string(
+        "report.json",
+        serde_json::to_string(&schema::Report::new(ex, &raw, &res))?.into(),
+        &mime::APPLICATION_JSON,
     )?;
-    info!("writing markdown files");
-    markdown::write_markdown_report(ex, crates.len(), &res, dest, output_templates)?;
-    info!("writing logs");
-    write_logs(db, ex, crates, dest, config)?;
 
+    let environments = db.load_environments(ex)?;
+
+    let available_archives = if with_logs {
+        info!("writing bug report drafts");
+        bug_reports::write_bug_reports(ex, &raw, &environments, dest)?;
+
+        info!("writing archives");
+        archives::write_logs_archives(db, ex, crates, dest, config)?
+    } else {
+        Vec::new()
+    };
+    let (root_causes, clusters) =
+        if formats.contains(&ReportFormat::Html) || formats.contains(&ReportFormat::Markdown) {
+            info!("analyzing the impact of regressions");
+            let root_causes = impact::analyze_impact(&raw);
+            info!("clustering regressions");
+            (
+                root_causes,
+                clusters::cluster_regressions(&raw, &error_blocks),
+            )
+        } else {
+            (Vec::new(), Vec::new())
+        };
+    let timings = timings::analyze_timings(&raw);
+    let unneeded_blacklist = blacklist::find_unneeded_entries(ex, config, &raw);
+
+    if formats.contains(&ReportFormat::Html) {
+        info!("diffing diagnostics");
+        let diagnostics = diagnostics::generate_diagnostics_diffs(db, ex, &raw)?;
+        info!("writing html files");
+        html::write_html_report(
+            ex,
+            &environments,
+            crates.len(),
+            &res,
+            &root_causes,
+            &clusters,
+            &timings,
+            &unneeded_blacklist,
+            &diagnostics,
+            available_archives,
+            dest,
+            output_templates,
+        )?;
+    }
+    if formats.contains(&ReportFormat::Markdown) {
+        info!("writing markdown files");
+        markdown::write_markdown_report(
+            ex,
+            crates.len(),
+            &res,
+            &root_causes,
+            &clusters,
+            &timings,
+            &unneeded_blacklist,
+            dest,
+            output_templates,
+        )?;
+    }
+    if formats.contains(&ReportFormat::JUnit) {
+        info!("writing junit files");
+        junit::write_junit_report(ex, &raw, dest)?;
+    }
+    if formats.contains(&ReportFormat::Csv) {
+        info!("writing csv files");
+        csv::write_csv_report(ex, &raw, dest)?;
+    }
     Ok(res)
 }
 
@@ -375,8 +604,9 @@ fn gen_retry_list(res: &RawTestResults) -> String {
 
     for krate in regressed_crates {
         match krate {
-            Crate::Registry(details) => writeln!(out, "{}", details.name).unwrap(),
-            Crate::GitHub(repo) => writeln!(out, "{}/{}", repo.org, repo.name).unwrap(),
+            Crate::Registry(_) | Crate::GitHub(_) => {
+                writeln!(out, "{}", krate.unversioned_name()).unwrap()
+            }
             Crate::Local(_) | Crate::Git(_) | Crate::Path(_) => {}
         }
     }
@@ -384,7 +614,25 @@ fn gen_retry_list(res: &RawTestResults) -> String {
     out
 }
 
-fn crate_to_name(c: &Crate) -> String {
+/// The path of the directory containing the log of a crate, relative to the root of the report.
+pub(crate) fn crate_log_path(toolchain: &Toolchain, krate: &Crate) -> String {
+    crate_to_path_fragment(toolchain, krate, SanitizationContext::Url)
+        .to_str()
+        .unwrap()
+        .replace(r"\", "/") // Normalize paths in reports generated on Windows
+}
+
+/// The URL the paths inside an uploaded report are relative to, ending with a slash.
+pub(crate) fn report_base_url(report_url: &str) -> String {
+    let base = report_url.trim_end_matches("index.html");
+    if base.ends_with('/') {
+        base.to_string()
+    } else {
+        format!("{}/", base)
+    }
+}
+
+pub(crate) fn crate_to_name(c: &Crate) -> String {
     match *c {
         Crate::Registry(ref details) => format!("{}-{}", details.name, details.version),
         Crate::GitHub(ref repo) => {
@@ -410,7 +658,7 @@ fn crate_to_name(c: &Crate) -> String {
     }
 }
 
-fn crate_to_url(c: &Crate) -> String {
+pub(crate) fn crate_to_url(c: &Crate) -> String {
     match *c {
         Crate::Registry(ref details) => format!(
             "https://crates.io/crates/{}/{}",
@@ -433,8 +681,9 @@ fn crate_to_url(c: &Crate) -> String {
     }
 }
 
-fn compare(
+pub(crate) fn compare(
     config: &Config,
+    mode: Mode,
     krate: &Crate,
     r1: Option<&TestResult>,
     r2: Option<&TestResult>,
@@ -499,11 +748,31 @@ fn compare(
                 panic!("can't compare {} and {}", res1, res2);
             }
         },
-        _ if config.should_skip(krate) => Comparison::Skipped,
+        _ if config.should_skip_in_mode(krate, mode) => Comparison::Skipped,
         _ => Comparison::Unknown,
     }
 }
 
+/// Turns a regression or a fix into a spurious one if any of the logs matched a classifier
+/// marked as spurious in the configuration.
+fn apply_spurious_classifiers(
+    config: &Config,
+    comp: Comparison,
+    runs: &[Option<BuildTestResult>; 2],
+) -> Comparison {
+    let spurious = runs
+        .iter()
+        .flatten()
+        .filter_map(|run| run.classifier.as_ref())
+        .any(|label| config.is_spurious_label(label));
+
+    match comp {
+        Comparison::Regressed if spurious => Comparison::SpuriousRegressed,
+        Comparison::Fixed if spurious => Comparison::SpuriousFixed,
+        other => other,
+    }
+}
+
 pub trait ReportWriter {
     fn write_bytes<P: AsRef<Path>>(
         &self,
@@ -516,16 +785,33 @@ pub trait ReportWriter {
     fn copy<P: AsRef<Path>, R: Read>(&self, r: &mut R, path: P, mime: &Mime) -> Fallible<()>;
 }
 
-pub struct FileWriter(PathBuf);
+/// Writes the report to a directory.
+pub struct FileWriter {
+    dest: PathBuf,
+    encoded_names: bool,
+}
 
 impl FileWriter {
     pub fn create(dest: PathBuf) -> Fallible<FileWriter> {
         fs::create_dir_all(&dest)?;
-        Ok(FileWriter(dest))
+        Ok(FileWriter {
+            dest,
+            encoded_names: false,
+        })
     }
+
+    /// Create a writer for the report store of the server. As the files can't carry their
+    /// `Content-Encoding`, encoded files get the extension of the encoding appended (for example
+    /// `log.txt.gz`), which the server strips when serving them.
+    pub fn create_with_encoded_names(dest: PathBuf) -> Fallible<FileWriter> {
+        let mut writer = FileWriter::create(dest)?;
+        writer.encoded_names = true;
+        Ok(writer)
+    }
+
     fn create_prefix(&self, path: &Path) -> Fallible<()> {
         if let Some(parent) = path.parent() {
-            fs::create_dir_all(self.0.join(parent))?;
+            fs::create_dir_all(self.dest.join(parent))?;
         }
         Ok(())
     }
@@ -537,29 +823,80 @@ impl ReportWriter for FileWriter {
         path: P,
         b: Vec<u8>,
         _: &Mime,
-        _: EncodingType,
+        encoding_type: EncodingType,
     ) -> Fallible<()> {
         self.create_prefix(path.as_ref())?;
-        fs::write(&self.0.join(path.as_ref()), &b)?;
+        let path = if self.encoded_names {
+            encoded_path(path.as_ref(), encoding_type)
+        } else {
+            path.as_ref().to_path_buf()
+        };
+        fs::write(&self.dest.join(path), &b)?;
         Ok(())
     }
 
     fn write_string<P: AsRef<Path>>(&self, path: P, s: Cow<str>, _: &Mime) -> Fallible<()> {
         self.create_prefix(path.as_ref())?;
-        fs::write(&self.0.join(path.as_ref()), s.as_ref().as_bytes())?;
+        fs::write(&self.dest.join(path.as_ref()), s.as_ref().as_bytes())?;
         Ok(())
     }
 
     fn copy<P: AsRef<Path>, R: Read>(&self, r: &mut R, path: P, _: &Mime) -> Fallible<()> {
         self.create_prefix(path.as_ref())?;
-        io::copy(r, &mut File::create(self.0.join(path.as_ref()))?)?;
+        io::copy(r, &mut File::create(self.dest.join(path.as_ref()))?)?;
         Ok(())
     }
 }
 
+/// The path of a file written with the given encoding by [`FileWriter::create_with_encoded_names`].
+pub fn encoded_path(path: &Path, encoding_type: EncodingType) -> PathBuf {
+    let extension = match encoding_type {
+        EncodingType::Plain => return path.to_path_buf(),
+        EncodingType::Gzip => "gz",
+        EncodingType::Zstd => "zst",
+    };
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(extension);
+    path.with_file_name(file_name)
+}
+
 impl Display for FileWriter {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.0.display().fmt(f)
+        self.dest.display().fmt(f)
+    }
+}
+
+#[cfg(test)]
+impl CrateResult {
+    /// A registry crate without any recorded run, to be customized by the tests.
+    pub(crate) fn dummy(name: &str, version: &str, res: Comparison) -> CrateResult {
+        CrateResult {
+            name: format!("{}-{}", name, version),
+            url: format!("https://crates.io/crates/{}/{}", name, version),
+            krate: Crate::Registry(crate::crates::RegistryCrate {
+                name: name.into(),
+                version: version.into(),
+            }),
+            status: None,
+            res,
+            runs: [None, None],
+            excerpt: None,
+            triage: None,
+        }
+    }
+}
+
+#[cfg(test)]
+impl BuildTestResult {
+    /// A run without log, classifier or timing, to be customized by the tests.
+    pub(crate) fn dummy(res: TestResult) -> BuildTestResult {
+        BuildTestResult {
+            res,
+            log: String::new(),
+            classifier: None,
+            timing: None,
+        }
     }
 }
 
@@ -578,6 +915,13 @@ impl DummyWriter {
             .unwrap()
             .clone()
     }
+
+    pub fn contains<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.results
+            .borrow()
+            .keys()
+            .any(|(written, _)| written == path.as_ref())
+    }
 }
 
 #[cfg(test)]
@@ -624,14 +968,51 @@ impl Display for DummyWriter {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{Config, CrateConfig};
+    use crate::config::{ClassifierConfig, ClassifierRegex, Config, CrateConfig};
     use crate::crates::{Crate, GitHubRepo, RegistryCrate};
     use crate::dirs::WORK_DIR;
     use crate::experiments::{CapLints, Experiment, Mode, Status};
-    use crate::results::{BrokenReason, DummyDB, FailureReason, TestResult};
+    use crate::results::{
+        BrokenReason, DummyDB, Environment, FailureReason, TaskTiming, TestResult,
+        ToolchainVersions, Triage, TriageStatus,
+    };
     use crate::toolchain::{MAIN_TOOLCHAIN, TEST_TOOLCHAIN};
     use crates_index::Index;
 
+    #[test]
+    fn test_file_writer() {
+        let write = |writer: &FileWriter| {
+            writer
+                .write_bytes(
+                    "log.txt",
+                    b"log".to_vec(),
+                    &mime::TEXT_PLAIN_UTF_8,
+                    EncodingType::Gzip,
+                )
+                .unwrap();
+            writer
+                .write_bytes(
+                    "logs-archives/all.tar.gz",
+                    b"archive".to_vec(),
+                    &"application/gzip".parse().unwrap(),
+                    EncodingType::Plain,
+                )
+                .unwrap();
+        };
+
+        // Reports generated from the CLI keep the paths linked from the pages
+        let dir = tempfile::tempdir().unwrap();
+        write(&FileWriter::create(dir.path().into()).unwrap());
+        assert!(dir.path().join("log.txt").is_file());
+        assert!(dir.path().join("logs-archives/all.tar.gz").is_file());
+
+        let dir = tempfile::tempdir().unwrap();
+        write(&FileWriter::create_with_encoded_names(dir.path().into()).unwrap());
+        assert!(dir.path().join("log.txt.gz").is_file());
+        assert!(!dir.path().join("log.txt").exists());
+        assert!(dir.path().join("logs-archives/all.tar.gz").is_file());
+    }
+
     #[test]
     fn test_crate_to_path_fragment() {
         let reg = Crate::Registry(RegistryCrate {
@@ -768,6 +1149,7 @@ mod tests {
                     assert_eq!(
                         $cmp(
                             $config,
+                            Mode::BuildAndTest,
                             $reg,
                             Some(&$a),
                             Some(&$b),
@@ -820,6 +1202,9 @@ mod tests {
                 TestSkipped, BuildFail(OOM) => SpuriousRegressed;
                 TestFail(OOM), BuildFail(Unknown) => SpuriousRegressed;
                 TestFail(Unknown), BuildFail(OOM) => SpuriousRegressed;
+                BuildFail(Network), TestFail(Network) => SpuriousFixed;
+                TestPass, BuildFail(DiskFull) => SpuriousRegressed;
+                TestPass, BuildFail(Linker) => Regressed;
 
                 // Errors
                 Error, TestPass => Error;
@@ -855,18 +1240,96 @@ mod tests {
             ]
         );
 
-        assert_eq!(compare(&config, &reg, None, None), Comparison::Unknown);
+        assert_eq!(
+            compare(&config, Mode::BuildAndTest, &reg, None, None),
+            Comparison::Unknown
+        );
 
         config.crates.insert(
             "lazy_static".into(),
             CrateConfig {
                 skip: true,
-                skip_tests: false,
-                quiet: false,
-                broken: false,
+                ..Default::default()
             },
         );
-        assert_eq!(compare(&config, &reg, None, None), Comparison::Skipped);
+        assert_eq!(
+            compare(&config, Mode::BuildAndTest, &reg, None, None),
+            Comparison::Skipped
+        );
+
+        config.crates.get_mut("lazy_static").unwrap().skip = false;
+        config.crates.get_mut("lazy_static").unwrap().skip_modes = vec![Mode::Clippy];
+        assert_eq!(
+            compare(&config, Mode::Clippy, &reg, None, None),
+            Comparison::Skipped
+        );
+        assert_eq!(
+            compare(&config, Mode::BuildAndTest, &reg, None, None),
+            Comparison::Unknown
+        );
+    }
+
+    #[test]
+    fn test_apply_spurious_classifiers() {
+        let mut config = Config::default();
+        config.classifiers.insert(
+            ClassifierRegex::new("Connection reset by peer").unwrap(),
+            ClassifierConfig {
+                label: "flaky-server".into(),
+                spurious: true,
+            },
+        );
+        config.classifiers.insert(
+            ClassifierRegex::new("could not find system library").unwrap(),
+            ClassifierConfig {
+                label: "missing-system-library".into(),
+                spurious: false,
+            },
+        );
+
+        let run = |res: TestResult, classifier: Option<&str>| {
+            Some(BuildTestResult {
+                classifier: classifier.map(|c| c.to_string()),
+                ..BuildTestResult::dummy(res)
+            })
+        };
+        let fail = TestResult::BuildFail(FailureReason::Unknown);
+
+        let runs = [
+            run(TestResult::TestPass, None),
+            run(fail.clone(), Some("flaky-server")),
+        ];
+        assert_eq!(
+            apply_spurious_classifiers(&config, Comparison::Regressed, &runs),
+            Comparison::SpuriousRegressed
+        );
+
+        let runs = [
+            run(fail.clone(), Some("flaky-server")),
+            run(TestResult::TestPass, None),
+        ];
+        assert_eq!(
+            apply_spurious_classifiers(&config, Comparison::Fixed, &runs),
+            Comparison::SpuriousFixed
+        );
+
+        let runs = [
+            run(TestResult::TestPass, None),
+            run(fail.clone(), Some("missing-system-library")),
+        ];
+        assert_eq!(
+            apply_spurious_classifiers(&config, Comparison::Regressed, &runs),
+            Comparison::Regressed
+        );
+
+        let runs = [
+            run(fail.clone(), Some("flaky-server")),
+            run(fail, Some("flaky-server")),
+        ];
+        assert_eq!(
+            apply_spurious_classifiers(&config, Comparison::SameBuildFail, &runs),
+            Comparison::SameBuildFail
+        );
     }
 
     #[test]
@@ -890,7 +1353,7 @@ mod tests {
             mode: Mode::BuildAndTest,
             cap_lints: CapLints::Forbid,
             priority: 0,
-            created_at: ::chrono::Utc::now(),
+            created_at: chrono::Utc::now(),
             started_at: None,
             completed_at: None,
             github_issue: None,
@@ -926,6 +1389,21 @@ mod tests {
             TEST_TOOLCHAIN.clone(),
             EncodedLog::Plain(b"beta log".to_vec()),
         );
+        db.add_dummy_classifier(
+            &ex,
+            gh.clone(),
+            TEST_TOOLCHAIN.clone(),
+            "missing-system-library",
+        );
+        let triage = Triage {
+            status: TriageStatus::NeedsIssue,
+            note: "missing libssl".into(),
+            link: None,
+            updated_by: "octocat".into(),
+            updated_at: chrono::Utc::now(),
+            carried_over_from: None,
+        };
+        db.add_dummy_triage(&ex, gh.clone(), triage.clone());
 
         db.add_dummy_result(
             &ex,
@@ -949,11 +1427,45 @@ mod tests {
             &ex,
             reg.clone(),
             TEST_TOOLCHAIN.clone(),
-            EncodedLog::Plain(b"beta log".to_vec()),
+            EncodedLog::Plain(b"[INFO] [stderr] error: linking with `cc` failed\n".to_vec()),
+        );
+        let started_at = chrono::Utc::now();
+        for (tc, seconds) in &[(MAIN_TOOLCHAIN.clone(), 10), (TEST_TOOLCHAIN.clone(), 75)] {
+            db.add_dummy_timing(
+                &ex,
+                gh.clone(),
+                tc.clone(),
+                TaskTiming {
+                    started_at,
+                    finished_at: started_at + chrono::Duration::seconds(*seconds),
+                },
+            );
+        }
+        db.add_dummy_environment(
+            &ex,
+            "agent:foo",
+            Environment {
+                git_revision: Some("0123456".into()),
+                docker_image: Some("rustops/crates-build-env@sha256:f00".into()),
+                toolchains: vec![ToolchainVersions {
+                    toolchain: "beta".into(),
+                    rustc: "rustc 1.41.0-beta.1".into(),
+                    cargo: "cargo 1.41.0-beta.1".into(),
+                }],
+            },
         );
 
         let writer = DummyWriter::default();
-        gen(&db, &ex, &[gh, reg], &writer, &config, false).unwrap();
+        gen(
+            &db,
+            &ex,
+            &[gh, reg],
+            &writer,
+            &config,
+            &ReportFormat::all(),
+            false,
+        )
+        .unwrap();
 
         assert_eq!(
             writer.get("config.json", &mime::APPLICATION_JSON),
@@ -998,6 +1510,12 @@ mod tests {
             (&gh_result.runs[1]).as_ref().unwrap().log.as_str(),
             "beta/gh/brson.hello-rs"
         );
+        assert_eq!(
+            (&gh_result.runs[1]).as_ref().unwrap().classifier.as_deref(),
+            Some("missing-system-library")
+        );
+        assert!(gh_result.excerpt.is_none());
+        assert_eq!(gh_result.triage, Some(triage));
 
         assert_eq!(reg_result.name.as_str(), "syn-1.0.0");
         assert_eq!(
@@ -1021,10 +1539,94 @@ mod tests {
             (&reg_result.runs[1]).as_ref().unwrap().log.as_str(),
             "beta/reg/syn-1.0.0"
         );
+        assert_eq!(
+            reg_result.excerpt.as_deref(),
+            Some("error: linking with `cc` failed")
+        );
+        assert!(reg_result.triage.is_none());
 
         assert_eq!(
             writer.get("retry-regressed-list.txt", &mime::TEXT_PLAIN_UTF_8),
             b"brson/hello-rs\nsyn\n",
         );
+
+        let index = String::from_utf8(writer.get("index.html", &mime::TEXT_HTML)).unwrap();
+        // Slashes are escaped in the HTML output
+        assert!(index.contains("crates-build-env@sha256:f00"));
+        let draft =
+            String::from_utf8(writer.get("bug-reports/gh-brson.hello-rs.md", &mime::TEXT_PLAIN))
+                .unwrap();
+        assert!(draft.contains("```text\nrustc 1.41.0-beta.1\ncargo 1.41.0-beta.1\n```"));
+
+        let markdown = String::from_utf8(writer.get("markdown.md", &mime::TEXT_PLAIN)).unwrap();
+        assert!(markdown.contains(
+            "### slowest crates\n| crate | start toolchain | end toolchain | delta |\n\
+             | --- | --- | --- | --- |\n\
+             | [brson.hello-rs.f00](https://github.com/brson/hello-rs/tree/f00) \
+             | 10.0s | 1m 15s | +1m 05s |\n"
+        ));
+    }
+
+    #[test]
+    fn test_partial_report_generation() {
+        let config = Config::default();
+        let krate = Crate::Registry(RegistryCrate {
+            name: "syn".into(),
+            version: "1.0.0".into(),
+        });
+
+        let ex = Experiment {
+            name: "foo".to_string(),
+            toolchains: [MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()],
+            mode: Mode::BuildAndTest,
+            cap_lints: CapLints::Forbid,
+            priority: 0,
+            created_at: chrono::Utc::now(),
+            started_at: Some(chrono::Utc::now()),
+            completed_at: None,
+            github_issue: None,
+            status: Status::Running,
+            assigned_to: None,
+            report_url: None,
+            ignore_blacklist: false,
+            requirement: None,
+        };
+
+        let mut db = DummyDB::default();
+        for (tc, res, log) in &[
+            (MAIN_TOOLCHAIN.clone(), TestResult::TestPass, "stable log"),
+            (
+                TEST_TOOLCHAIN.clone(),
+                TestResult::BuildFail(FailureReason::Unknown),
+                "[INFO] [stderr] error: linking with `cc` failed\n",
+            ),
+        ] {
+            db.add_dummy_result(&ex, krate.clone(), tc.clone(), res.clone());
+            db.add_dummy_log(
+                &ex,
+                krate.clone(),
+                tc.clone(),
+                EncodedLog::Plain(log.as_bytes().to_vec()),
+            );
+        }
+
+        let writer = DummyWriter::default();
+        gen_partial(&db, &ex, &[krate], &writer, &config).unwrap();
+
+        let result: RawTestResults =
+            serde_json::from_slice(&writer.get("results.json", &mime::APPLICATION_JSON)).unwrap();
+        assert_eq!(result.crates[0].res, Comparison::Regressed);
+        assert_eq!(
+            result.crates[0].excerpt.as_deref(),
+            Some("error: linking with `cc` failed")
+        );
+        assert!(writer.contains("index.html"));
+        assert!(writer.contains("markdown.md"));
+
+        // The logs, their archives and the bug report drafts are left to the final report
+        assert!(!writer.contains("stable/reg/syn-1.0.0/log.txt"));
+        assert!(!writer.contains("beta/reg/syn-1.0.0/log.txt"));
+        assert!(!writer.contains("logs-archives/all.tar.gz"));
+        assert!(!writer.contains("bug-reports/index.md"));
     }
 }
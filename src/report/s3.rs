@@ -64,6 +64,10 @@ pub struct S3Writer {
 }
 
 pub fn get_client_for_bucket(bucket: &str) -> Fallible<Box<dyn S3>> {
+    Ok(Box::new(get_s3_client_for_bucket(bucket)?))
+}
+
+pub fn get_s3_client_for_bucket(bucket: &str) -> Fallible<S3Client> {
     let make_client = |region| -> Fallible<S3Client> {
         let credentials = DefaultCredentialsProvider::new().unwrap();
         Ok(S3Client::new_with(HttpClient::new()?, credentials, region))
@@ -81,7 +85,7 @@ pub fn get_client_for_bucket(bucket: &str) -> Fallible<Box<dyn S3>> {
         None => return Err(S3Error::UnknownBucketRegion.into()),
     };
 
-    Ok(Box::new(make_client(region)?))
+    make_client(region)
 }
 
 const S3RETRIES: u64 = 4;
@@ -116,6 +120,7 @@ impl ReportWriter for S3Writer {
                 content_encoding: match encoding_type {
                     EncodingType::Plain => None,
                     EncodingType::Gzip => Some("gzip".into()),
+                    EncodingType::Zstd => Some("zstd".into()),
                 },
                 ..Default::default()
             };
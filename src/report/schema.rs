@@ -0,0 +1,502 @@
+//! The machine-readable report, written to `report.json`.
+//!
+//! Unlike `results.json`, which is a dump of the internal structs, the shape of this file is
+//! stable: it's documented in `docs/report-schema.md`, and any incompatible change to it must bump
+//! [`SCHEMA_VERSION`] and update the documentation and the compatibility tests.
+
+use crate::experiments::{CapLints, Experiment, Mode};
+use crate::report::analyzer::{ReportCrates, TestResults};
+use crate::report::{BuildTestResult, Comparison, CrateResult, CrateVersionStatus, RawTestResults};
+use crate::results::{BrokenReason, FailureReason, TestResult};
+use crate::toolchain::Toolchain;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Serialize, Deserialize)]
+pub struct Report {
+    pub schema_version: u32,
+    pub experiment: ExperimentInfo,
+    pub crates: Vec<CrateInfo>,
+    pub categories: Vec<Category>,
+    pub classified: BTreeMap<String, Vec<String>>,
+}
+
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Serialize, Deserialize)]
+pub struct ExperimentInfo {
+    pub name: String,
+    pub mode: String,
+    pub cap_lints: String,
+    pub priority: i32,
+    pub ignore_blacklist: bool,
+    pub requirement: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub toolchains: Vec<ToolchainInfo>,
+}
+
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Serialize, Deserialize)]
+pub struct ToolchainInfo {
+    pub name: String,
+    pub rustflags: Option<String>,
+    pub patches: Vec<PatchInfo>,
+}
+
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Serialize, Deserialize)]
+pub struct PatchInfo {
+    pub name: String,
+    pub repo: String,
+    pub branch: String,
+}
+
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Serialize, Deserialize)]
+pub struct CrateInfo {
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    pub version_status: Option<String>,
+    pub comparison: String,
+    /// One entry per toolchain of the experiment, in the same order.
+    pub runs: Vec<Option<RunInfo>>,
+}
+
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Serialize, Deserialize)]
+pub struct RunInfo {
+    pub toolchain: String,
+    pub result: String,
+    pub reason: Option<String>,
+    pub error_codes: Vec<String>,
+    pub depends_on: Vec<String>,
+    /// Path of the log relative to the root of the report, not a URL: the report doesn't know
+    /// where it's going to be served from.
+    pub log: String,
+    pub classifier: Option<String>,
+}
+
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Serialize, Deserialize)]
+pub struct Category {
+    pub comparison: String,
+    pub count: u32,
+    pub crates: Vec<String>,
+    pub by_dependency: BTreeMap<String, Vec<String>>,
+    pub by_result: BTreeMap<String, Vec<String>>,
+}
+
+impl Report {
+    pub fn new(ex: &Experiment, raw: &RawTestResults, res: &TestResults) -> Self {
+        Report {
+            schema_version: SCHEMA_VERSION,
+            experiment: ExperimentInfo::new(ex),
+            crates: raw
+                .crates
+                .iter()
+                .map(|krate| CrateInfo::new(ex, krate))
+                .collect(),
+            categories: res
+                .categories
+                .iter()
+                .map(|(comparison, crates)| Category {
+                    comparison: comparison_name(*comparison).into(),
+                    count: res.info.get(comparison).cloned().unwrap_or(0),
+                    crates: raw
+                        .crates
+                        .iter()
+                        .filter(|krate| krate.res == *comparison)
+                        .map(|krate| krate.krate.id())
+                        .collect(),
+                    by_dependency: match crates {
+                        ReportCrates::Plain(_) => BTreeMap::new(),
+                        ReportCrates::Complete { tree, .. } => tree
+                            .iter()
+                            .map(|(dep, crates)| (dep.id(), crate_ids(crates)))
+                            .collect(),
+                    },
+                    by_result: match crates {
+                        ReportCrates::Plain(_) => BTreeMap::new(),
+                        ReportCrates::Complete { results, .. } => results
+                            .iter()
+                            .map(|(result, crates)| (result_key(result), crate_ids(crates)))
+                            .collect(),
+                    },
+                })
+                .collect(),
+            classified: res
+                .classified
+                .iter()
+                .map(|(label, crates)| (label.clone(), crate_ids(crates)))
+                .collect(),
+        }
+    }
+}
+
+fn crate_ids(crates: &[CrateResult]) -> Vec<String> {
+    crates.iter().map(|krate| krate.krate.id()).collect()
+}
+
+impl ExperimentInfo {
+    fn new(ex: &Experiment) -> Self {
+        ExperimentInfo {
+            name: ex.name.clone(),
+            mode: mode_name(ex.mode).into(),
+            cap_lints: cap_lints_name(ex.cap_lints).into(),
+            priority: ex.priority,
+            ignore_blacklist: ex.ignore_blacklist,
+            requirement: ex.requirement.clone(),
+            created_at: ex.created_at,
+            started_at: ex.started_at,
+            completed_at: ex.completed_at,
+            toolchains: ex.toolchains.iter().map(ToolchainInfo::new).collect(),
+        }
+    }
+}
+
+impl ToolchainInfo {
+    fn new(tc: &Toolchain) -> Self {
+        ToolchainInfo {
+            name: tc.to_string(),
+            rustflags: tc.rustflags.clone(),
+            patches: tc
+                .patches
+                .iter()
+                .map(|patch| PatchInfo {
+                    name: patch.name.clone(),
+                    repo: patch.repo.clone(),
+                    branch: patch.branch.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl CrateInfo {
+    fn new(ex: &Experiment, krate: &CrateResult) -> Self {
+        CrateInfo {
+            id: krate.krate.id(),
+            name: krate.name.clone(),
+            url: krate.url.clone(),
+            // The names are spelled out to keep them stable if the ones in the HTML report change
+            version_status: krate.status.map(|status| {
+                match status {
+                    CrateVersionStatus::UpToDate => "up-to-date",
+                    CrateVersionStatus::Outdated => "outdated",
+                    CrateVersionStatus::Yanked => "yanked",
+                    CrateVersionStatus::MissingFromIndex => "missing-from-index",
+                }
+                .into()
+            }),
+            comparison: comparison_name(krate.res).into(),
+            runs: krate
+                .runs
+                .iter()
+                .zip(ex.toolchains.iter())
+                .map(|(run, tc)| run.as_ref().map(|run| RunInfo::new(tc, run)))
+                .collect(),
+        }
+    }
+}
+
+impl RunInfo {
+    fn new(tc: &Toolchain, run: &BuildTestResult) -> Self {
+        let (result, reason) = result_kind(&run.res);
+
+        let mut error_codes = Vec::new();
+        let mut depends_on = Vec::new();
+        match &run.res {
+            TestResult::BuildFail(FailureReason::CompilerError(codes))
+            | TestResult::TestFail(FailureReason::CompilerError(codes)) => {
+                error_codes.extend(codes.iter().map(|code| code.to_string()));
+            }
+            TestResult::BuildFail(FailureReason::DependsOn(deps))
+            | TestResult::TestFail(FailureReason::DependsOn(deps)) => {
+                depends_on.extend(deps.iter().map(|dep| dep.id()));
+            }
+            _ => {}
+        }
+
+        RunInfo {
+            toolchain: tc.to_string(),
+            result: result.into(),
+            reason: reason.map(String::from),
+            error_codes,
+            depends_on,
+            log: format!("{}/log.txt", run.log),
+            classifier: run.classifier.clone(),
+        }
+    }
+}
+
+// The values below are spelled out instead of reusing the names of the internal enums, so that
+// renaming one of them doesn't silently change the schema.
+
+fn mode_name(mode: Mode) -> &'static str {
+    match mode {
+        Mode::BuildAndTest => "build-and-test",
+        Mode::BuildOnly => "build-only",
+        Mode::CheckOnly => "check-only",
+        Mode::Clippy => "clippy",
+        Mode::Rustdoc => "rustdoc",
+        Mode::UnstableFeatures => "unstable-features",
+    }
+}
+
+fn cap_lints_name(cap_lints: CapLints) -> &'static str {
+    match cap_lints {
+        CapLints::Allow => "allow",
+        CapLints::Warn => "warn",
+        CapLints::Deny => "deny",
+        CapLints::Forbid => "forbid",
+    }
+}
+
+fn comparison_name(comparison: Comparison) -> &'static str {
+    match comparison {
+        Comparison::Regressed => "regressed",
+        Comparison::Fixed => "fixed",
+        Comparison::Skipped => "skipped",
+        Comparison::Unknown => "unknown",
+        Comparison::Error => "error",
+        Comparison::Broken => "broken",
+        Comparison::SameBuildFail => "build-fail",
+        Comparison::SameTestFail => "test-fail",
+        Comparison::SameTestSkipped => "test-skipped",
+        Comparison::SameTestPass => "test-pass",
+        Comparison::SpuriousRegressed => "spurious-regressed",
+        Comparison::SpuriousFixed => "spurious-fixed",
+    }
+}
+
+/// Returns the kind of result and the kind of its reason, if it has one.
+fn result_kind(res: &TestResult) -> (&'static str, Option<&'static str>) {
+    match res {
+        TestResult::BrokenCrate(reason) => ("broken", Some(broken_reason_kind(reason))),
+        TestResult::BuildFail(reason) => ("build-fail", Some(failure_reason_kind(reason))),
+        TestResult::TestFail(reason) => ("test-fail", Some(failure_reason_kind(reason))),
+        TestResult::TestSkipped => ("test-skipped", None),
+        TestResult::TestPass => ("test-pass", None),
+        TestResult::Skipped => ("skipped", None),
+        TestResult::Error => ("error", None),
+    }
+}
+
+/// The key of a result in `by_result`, like `build-fail:compiler-error(E0308)`.
+fn result_key(res: &TestResult) -> String {
+    let details = match res {
+        TestResult::BuildFail(FailureReason::CompilerError(codes))
+        | TestResult::TestFail(FailureReason::CompilerError(codes)) => Some(
+            codes
+                .iter()
+                .map(|code| code.to_string())
+                .collect::<Vec<_>>(),
+        ),
+        TestResult::BuildFail(FailureReason::DependsOn(deps))
+        | TestResult::TestFail(FailureReason::DependsOn(deps)) => {
+            Some(deps.iter().map(|dep| dep.id()).collect())
+        }
+        _ => None,
+    };
+
+    match (result_kind(res), details) {
+        ((result, Some(reason)), Some(details)) => {
+            format!("{}:{}({})", result, reason, details.join(", "))
+        }
+        ((result, Some(reason)), None) => format!("{}:{}", result, reason),
+        ((result, None), _) => result.into(),
+    }
+}
+
+fn broken_reason_kind(reason: &BrokenReason) -> &'static str {
+    match reason {
+        BrokenReason::Unknown => "unknown",
+        BrokenReason::CargoToml => "cargo-toml",
+        BrokenReason::Yanked => "yanked",
+        BrokenReason::MissingDependencies => "missing-deps",
+        BrokenReason::MissingGitRepository => "missing-git-repository",
+    }
+}
+
+fn failure_reason_kind(reason: &FailureReason) -> &'static str {
+    match reason {
+        FailureReason::Unknown => "unknown",
+        FailureReason::OOM => "oom",
+        FailureReason::Timeout => "timeout",
+        FailureReason::ICE => "ice",
+        FailureReason::Linker => "linker",
+        FailureReason::BuildScript => "build-script",
+        FailureReason::ProcMacro => "proc-macro",
+        FailureReason::Network => "network",
+        FailureReason::DiskFull => "disk-full",
+        FailureReason::CompilerError(_) => "compiler-error",
+        FailureReason::DependsOn(_) => "depends-on",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{result_key, Report, SCHEMA_VERSION};
+    use crate::crates::{Crate, GitHubRepo, RegistryCrate};
+    use crate::experiments::{CapLints, Experiment, Mode, Status};
+    use crate::report::analyzer::analyze_report;
+    use crate::report::{
+        BuildTestResult, Comparison, CrateResult, CrateVersionStatus, RawTestResults,
+    };
+    use crate::results::{BrokenReason, DiagnosticCode, FailureReason, TestResult};
+    use crate::toolchain::{MAIN_TOOLCHAIN, TEST_TOOLCHAIN};
+    use chrono::{TimeZone, Utc};
+    use std::collections::BTreeSet;
+
+    // Changing this file breaks the scripts consuming the report: bump the schema version instead
+    const V1_REPORT: &str = include_str!("../../tests/report_schema/v1.json");
+
+    fn run(tc: &str, krate: &str, res: TestResult, classifier: Option<&str>) -> BuildTestResult {
+        BuildTestResult {
+            log: format!("{}/{}", tc, krate),
+            classifier: classifier.map(String::from),
+            ..BuildTestResult::dummy(res)
+        }
+    }
+
+    fn sample_report() -> Report {
+        let ex = Experiment {
+            name: "foo".to_string(),
+            toolchains: [MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()],
+            mode: Mode::BuildAndTest,
+            cap_lints: CapLints::Forbid,
+            priority: 0,
+            created_at: Utc.ymd(2020, 1, 1).and_hms(0, 0, 0),
+            started_at: None,
+            completed_at: None,
+            github_issue: None,
+            status: Status::GeneratingReport,
+            assigned_to: None,
+            report_url: None,
+            ignore_blacklist: false,
+            requirement: None,
+        };
+
+        let dep = Crate::Registry(RegistryCrate {
+            name: "dep".into(),
+            version: "1.0.0".into(),
+        });
+        let raw = RawTestResults {
+            crates: vec![
+                CrateResult {
+                    status: Some(CrateVersionStatus::UpToDate),
+                    runs: [
+                        Some(run("stable", "reg/dep-1.0.0", TestResult::TestPass, None)),
+                        Some(run(
+                            "beta",
+                            "reg/dep-1.0.0",
+                            TestResult::BuildFail(FailureReason::CompilerError(btreeset![
+                                DiagnosticCode::from("E0308".into())
+                            ])),
+                            None,
+                        )),
+                    ],
+                    ..CrateResult::dummy("dep", "1.0.0", Comparison::Regressed)
+                },
+                CrateResult {
+                    status: Some(CrateVersionStatus::Outdated),
+                    runs: [
+                        Some(run("stable", "reg/user-0.1.0", TestResult::TestPass, None)),
+                        Some(run(
+                            "beta",
+                            "reg/user-0.1.0",
+                            TestResult::BuildFail(FailureReason::DependsOn(btreeset![dep])),
+                            Some("missing-system-library"),
+                        )),
+                    ],
+                    ..CrateResult::dummy("user", "0.1.0", Comparison::Regressed)
+                },
+                CrateResult {
+                    name: "brson.hello-rs".into(),
+                    url: "https://github.com/brson/hello-rs".into(),
+                    krate: Crate::GitHub(GitHubRepo {
+                        org: "brson".into(),
+                        name: "hello-rs".into(),
+                        sha: None,
+                    }),
+                    status: None,
+                    res: Comparison::SameTestPass,
+                    runs: [
+                        Some(run(
+                            "stable",
+                            "gh/brson.hello-rs",
+                            TestResult::TestPass,
+                            None,
+                        )),
+                        Some(run("beta", "gh/brson.hello-rs", TestResult::TestPass, None)),
+                    ],
+                    excerpt: None,
+                    triage: None,
+                },
+                CrateResult {
+                    name: "broken (local)".into(),
+                    url: "https://github.com/rust-lang/crater/tree/master/local-crates/broken"
+                        .into(),
+                    krate: Crate::Local("broken".into()),
+                    status: None,
+                    res: Comparison::Broken,
+                    runs: [
+                        Some(run(
+                            "stable",
+                            "local/broken",
+                            TestResult::BrokenCrate(BrokenReason::CargoToml),
+                            None,
+                        )),
+                        None,
+                    ],
+                    excerpt: None,
+                    triage: None,
+                },
+            ],
+        };
+
+        let res = analyze_report(raw.clone());
+        Report::new(&ex, &raw, &res)
+    }
+
+    #[test]
+    fn test_schema_v1_compatibility() {
+        // The report must serialize to exactly what scripts written against v1 expect...
+        let expected: serde_json::Value = serde_json::from_str(V1_REPORT).unwrap();
+        let actual = serde_json::to_value(&sample_report()).unwrap();
+        assert_eq!(actual, expected);
+
+        // ...and reports saved in the v1 format must still be loadable
+        let parsed: Report = serde_json::from_str(V1_REPORT).unwrap();
+        assert_eq!(parsed.schema_version, SCHEMA_VERSION);
+        assert_eq!(parsed, sample_report());
+    }
+
+    #[test]
+    fn test_result_key() {
+        let dep = Crate::Registry(RegistryCrate {
+            name: "dep".into(),
+            version: "1.0.0".into(),
+        });
+        assert_eq!(
+            result_key(&TestResult::TestFail(FailureReason::DependsOn(btreeset![
+                dep
+            ]))),
+            "test-fail:depends-on(reg/dep/1.0.0)"
+        );
+        assert_eq!(
+            result_key(&TestResult::BuildFail(FailureReason::Timeout)),
+            "build-fail:timeout"
+        );
+        assert_eq!(
+            result_key(&TestResult::BrokenCrate(BrokenReason::Yanked)),
+            "broken:yanked"
+        );
+        assert_eq!(result_key(&TestResult::TestPass), "test-pass");
+    }
+}
@@ -0,0 +1,164 @@
+use crate::report::{CrateResult, RawTestResults};
+use chrono::Duration;
+use std::cmp::Reverse;
+
+/// How many crates are listed in each of the timing sections of the report.
+const MAX_LISTED_CRATES: usize = 20;
+
+/// The wall time of a crate with the start and end toolchains.
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct CrateTiming {
+    pub name: String,
+    pub url: String,
+    pub runs: [Option<Duration>; 2],
+}
+
+impl CrateTiming {
+    fn new(krate: &CrateResult) -> Self {
+        let wall_time = |idx: usize| {
+            krate.runs[idx]
+                .as_ref()
+                .and_then(|run| run.timing.as_ref())
+                .map(|timing| timing.wall_time())
+        };
+
+        CrateTiming {
+            name: krate.name.clone(),
+            url: krate.url.clone(),
+            runs: [wall_time(0), wall_time(1)],
+        }
+    }
+
+    /// How much slower the crate was with the end toolchain.
+    pub fn delta(&self) -> Option<Duration> {
+        match self.runs {
+            [Some(start), Some(end)] => Some(end - start),
+            _ => None,
+        }
+    }
+}
+
+pub struct Timings {
+    /// The crates taking the longest to test with either toolchain.
+    pub slowest: Vec<CrateTiming>,
+    /// The crates whose wall time changed the most between the toolchains.
+    pub deltas: Vec<CrateTiming>,
+}
+
+pub fn analyze_timings(raw: &RawTestResults) -> Timings {
+    let mut slowest = raw
+        .crates
+        .iter()
+        .map(CrateTiming::new)
+        .filter(|timing| timing.runs.iter().any(Option::is_some))
+        .collect::<Vec<_>>();
+    slowest.sort_by_key(|timing| Reverse(timing.runs.iter().flatten().max().cloned()));
+    slowest.truncate(MAX_LISTED_CRATES);
+
+    // A crate failing early with one of the toolchains is obviously faster with it, so only the
+    // crates with the same result on both toolchains are compared.
+    let mut deltas = raw
+        .crates
+        .iter()
+        .filter(|krate| match &krate.runs {
+            [Some(start), Some(end)] => start.res == end.res,
+            _ => false,
+        })
+        .map(CrateTiming::new)
+        .filter(|timing| timing.delta().is_some())
+        .collect::<Vec<_>>();
+    deltas
+        .sort_by_key(|timing| Reverse(timing.delta().map(|delta| delta.num_milliseconds().abs())));
+    deltas.truncate(MAX_LISTED_CRATES);
+
+    Timings { slowest, deltas }
+}
+
+/// Formats a duration for the reports, for example `1m 05s` or `4.2s`.
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.num_milliseconds();
+    let sign = if millis < 0 { "-" } else { "" };
+    let millis = millis.abs();
+    if millis < 60_000 {
+        format!("{}{:.1}s", sign, millis as f64 / 1000.0)
+    } else {
+        format!(
+            "{}{}m {:02}s",
+            sign,
+            millis / 60_000,
+            (millis % 60_000) / 1000
+        )
+    }
+}
+
+/// Formats the difference of wall time between the toolchains, always showing its sign.
+pub fn format_delta(delta: Duration) -> String {
+    if delta < Duration::zero() {
+        format_duration(delta)
+    } else {
+        format!("+{}", format_duration(delta))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{analyze_timings, format_delta, format_duration, CrateTiming};
+    use crate::report::{BuildTestResult, Comparison, CrateResult, RawTestResults};
+    use crate::results::{FailureReason, TaskTiming, TestResult};
+    use chrono::{Duration, Utc};
+
+    fn run(res: TestResult, seconds: i64) -> Option<BuildTestResult> {
+        let started_at = Utc::now();
+        Some(BuildTestResult {
+            timing: Some(TaskTiming {
+                started_at,
+                finished_at: started_at + Duration::seconds(seconds),
+            }),
+            ..BuildTestResult::dummy(res)
+        })
+    }
+
+    fn krate(name: &str, runs: [Option<BuildTestResult>; 2]) -> CrateResult {
+        CrateResult {
+            name: name.into(),
+            runs,
+            ..CrateResult::dummy(name, "1.0.0", Comparison::SameTestPass)
+        }
+    }
+
+    #[test]
+    fn test_analyze_timings() {
+        let pass = || TestResult::TestPass;
+        let fail = || TestResult::BuildFail(FailureReason::Unknown);
+        let raw = RawTestResults {
+            crates: vec![
+                krate("quick", [run(pass(), 5), run(pass(), 6)]),
+                krate("slower", [run(pass(), 60), run(pass(), 120)]),
+                krate("faster", [run(pass(), 100), run(pass(), 10)]),
+                // Regressed crates are not compared, as they fail earlier
+                krate("regressed", [run(pass(), 300), run(fail(), 1)]),
+                krate("untimed", [None, None]),
+            ],
+        };
+
+        fn names(list: &[CrateTiming]) -> Vec<&str> {
+            list.iter().map(|timing| timing.name.as_str()).collect()
+        }
+
+        let timings = analyze_timings(&raw);
+        assert_eq!(
+            names(&timings.slowest),
+            vec!["regressed", "slower", "faster", "quick"]
+        );
+        assert_eq!(names(&timings.deltas), vec!["faster", "slower", "quick"]);
+        assert_eq!(timings.deltas[0].delta(), Some(Duration::seconds(-90)));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::milliseconds(4200)), "4.2s");
+        assert_eq!(format_duration(Duration::seconds(65)), "1m 05s");
+        assert_eq!(format_delta(Duration::seconds(65)), "+1m 05s");
+        assert_eq!(format_delta(Duration::milliseconds(-1500)), "-1.5s");
+    }
+}
@@ -1,13 +1,26 @@
 use crate::config::Config;
 use crate::crates::Crate;
 use crate::db::{Database, QueryUtils};
-use crate::experiments::{Experiment, Status};
+use crate::experiments::{Assignee, Experiment, Status};
 use crate::prelude::*;
 use crate::results::{
-    DeleteResults, EncodedLog, EncodingType, ReadResults, TestResult, WriteResults,
+    capture_timed, DatabaseLogStore, DeleteResults, Diagnostic, EncodedLog, EncodingType,
+    Environment, LogStore, ReadResults, TaskTiming, TestResult, ToolchainVersions, Triage,
+    WriteResults,
 };
 use crate::toolchain::Toolchain;
-use rustwide::logging::{self, LogStorage};
+use chrono::{DateTime, Utc};
+use rusqlite::types::ToSql;
+use rustwide::logging::LogStorage;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+lazy_static! {
+    /// The logs live outside of the database and are shared between results, so storing a result
+    /// and removing the logs nothing points to anymore can't happen in a single transaction. This
+    /// lock serializes them, to avoid removing a log another result is being stored with.
+    static ref LOGS_LOCK: Mutex<()> = Mutex::new(());
+}
 
 #[derive(Deserialize)]
 pub struct TaskResult {
@@ -16,6 +29,12 @@ pub struct TaskResult {
     pub toolchain: Toolchain,
     pub result: TestResult,
     pub log: String,
+    #[serde(default)]
+    pub diagnostics: Vec<Diagnostic>,
+    #[serde(default)]
+    pub classifier: Option<String>,
+    #[serde(default)]
+    pub timing: Option<TaskTiming>,
 }
 
 #[derive(Deserialize)]
@@ -24,13 +43,47 @@ pub struct ProgressData {
     pub version: Option<(Crate, Crate)>,
 }
 
+/// The result of a crate with one of the toolchains of a past experiment.
+pub struct CrateHistoryEntry {
+    pub experiment: String,
+    /// When the experiment started, or when it was created if it didn't start yet.
+    pub date: DateTime<Utc>,
+    pub report_url: Option<String>,
+    pub krate: Crate,
+    pub toolchain: Toolchain,
+    pub result: TestResult,
+}
+
+fn load_log_hash(
+    db: &impl QueryUtils,
+    ex: &Experiment,
+    toolchain: &Toolchain,
+    krate: &Crate,
+) -> Fallible<Option<String>> {
+    db.get_row(
+        "SELECT log_hash FROM results \
+         WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3 \
+         LIMIT 1;",
+        &[&ex.name, &toolchain.to_string(), &krate.id()],
+        |row| row.get("log_hash"),
+    )
+}
+
 pub struct DatabaseDB<'a> {
     db: &'a Database,
+    logs: Arc<dyn LogStore>,
 }
 
 impl<'a> DatabaseDB<'a> {
+    /// Store the logs in the database. Everything else should use the log store of the
+    /// configuration, as the logs could be stored elsewhere.
+    #[cfg(test)]
     pub fn new(db: &'a Database) -> Self {
-        DatabaseDB { db }
+        Self::with_log_store(db, Arc::new(DatabaseLogStore::new(db.clone())))
+    }
+
+    pub fn with_log_store(db: &'a Database, logs: Arc<dyn LogStore>) -> Self {
+        DatabaseDB { db, logs }
     }
 
     pub fn store(
@@ -46,8 +99,11 @@ impl<'a> DatabaseDB<'a> {
                 &result.toolchain,
                 &result.result,
                 &base64::decode(&result.log).with_context(|_| "invalid base64 log provided")?,
+                result.classifier.as_deref(),
+                result.timing.as_ref(),
                 encoding_type,
             )?;
+            self.store_diagnostics(ex, &result.krate, &result.toolchain, &result.diagnostics)?;
 
             if let Some((old, new)) = &data.version {
                 self.update_crate_version(ex, old, new)?;
@@ -74,33 +130,245 @@ impl<'a> DatabaseDB<'a> {
         toolchain: &Toolchain,
         res: &TestResult,
         log: &[u8],
+        classifier: Option<&str>,
+        timing: Option<&TaskTiming>,
         desired_encoding_type: EncodingType,
     ) -> Fallible<()> {
-        let encoded_log = EncodedLog::from_plain_slice(log, desired_encoding_type)?;
-        self.insert_into_results(ex, krate, toolchain, res, encoded_log)?;
+        let hash = EncodedLog::hash_plain(log);
+        let _lock = LOGS_LOCK.lock().unwrap();
+
+        // Identical logs are stored only once, so they're encoded only if they're new. The log is
+        // stored before the result pointing to it, so the result never points to a missing log.
+        if !self.logs.exists(&hash)? {
+            let encoded_log = EncodedLog::from_plain_slice(log, desired_encoding_type)?;
+            self.logs.store(&hash, &encoded_log)?;
+        }
+
+        let old_hash = self.db.transaction(|t| {
+            let old_hash = load_log_hash(t, ex, toolchain, krate)?;
+            t.execute(
+                "INSERT INTO results \
+                 (experiment, crate, toolchain, result, log_hash, classifier, started_at, \
+                 finished_at) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8);",
+                &[
+                    &ex.name,
+                    &krate.id(),
+                    &toolchain.to_string(),
+                    &res.to_string(),
+                    &hash,
+                    &classifier,
+                    &timing.map(|timing| timing.started_at),
+                    &timing.map(|timing| timing.finished_at),
+                ],
+            )?;
+            Ok(old_hash)
+        })?;
+
+        self.delete_unused_logs(old_hash)
+    }
+
+    /// Remove the logs that aren't referenced by any result anymore, since they're shared between
+    /// results and the log store can't know when to remove them on its own.
+    fn delete_unused_logs<I: IntoIterator<Item = String>>(&self, hashes: I) -> Fallible<()> {
+        for hash in hashes {
+            if !self
+                .db
+                .exists("SELECT 1 FROM results WHERE log_hash = ?1;", &[&hash])?
+            {
+                self.logs.delete(&hash)?;
+            }
+        }
         Ok(())
     }
 
-    fn insert_into_results(
+    fn store_diagnostics(
         &self,
         ex: &Experiment,
         krate: &Crate,
         toolchain: &Toolchain,
-        res: &TestResult,
-        log: EncodedLog,
-    ) -> Fallible<usize> {
+        diagnostics: &[Diagnostic],
+    ) -> Fallible<()> {
+        self.db.transaction(|t| {
+            // Replace the diagnostics recorded by previous runs
+            t.execute(
+                "DELETE FROM diagnostics \
+                 WHERE experiment = ?1 AND crate = ?2 AND toolchain = ?3;",
+                &[&ex.name, &krate.id(), &toolchain.to_string()],
+            )?;
+            for diag in diagnostics {
+                t.execute(
+                    "INSERT INTO diagnostics \
+                     (experiment, crate, toolchain, level, code, lint, message, file, line) \
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9);",
+                    &[
+                        &ex.name,
+                        &krate.id(),
+                        &toolchain.to_string(),
+                        &diag.level.to_str(),
+                        &diag.code,
+                        &diag.lint,
+                        &diag.message,
+                        &diag.file,
+                        &diag.line,
+                    ],
+                )?;
+            }
+
+            Ok(())
+        })
+    }
+
+    pub fn store_triage(&self, ex: &Experiment, krate: &Crate, triage: &Triage) -> Fallible<()> {
         self.db.execute(
-            "INSERT INTO results (experiment, crate, toolchain, result, log, encoding) \
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6);",
+            "INSERT INTO triage \
+             (experiment, crate, status, note, link, updated_by, updated_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7);",
             &[
                 &ex.name,
                 &krate.id(),
-                &toolchain.to_string(),
-                &res.to_string(),
-                &log.as_slice(),
-                &log.get_encoding_type().to_str(),
+                &triage.status.to_str(),
+                &triage.note,
+                &triage.link,
+                &triage.updated_by,
+                &triage.updated_at,
             ],
-        )
+        )?;
+        Ok(())
+    }
+
+    /// Store the environment of an agent, replacing the one it previously recorded.
+    pub fn store_environment(
+        &self,
+        ex: &Experiment,
+        agent: &str,
+        env: &Environment,
+    ) -> Fallible<()> {
+        let now = Utc::now();
+        self.db.transaction(|t| {
+            t.execute(
+                "DELETE FROM environments WHERE experiment = ?1 AND agent = ?2;",
+                &[&ex.name, &agent],
+            )?;
+            for tc in &env.toolchains {
+                t.execute(
+                    "INSERT INTO environments \
+                     (experiment, agent, toolchain, rustc_version, cargo_version, git_revision, \
+                     docker_image, recorded_at) \
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8);",
+                    &[
+                        &ex.name,
+                        &agent,
+                        &tc.toolchain,
+                        &tc.rustc,
+                        &tc.cargo,
+                        &env.git_revision,
+                        &env.docker_image,
+                        &now,
+                    ],
+                )?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Load the triage entries returned by the query, along with the experiment they belong to.
+    fn query_triage(
+        &self,
+        sql: &str,
+        params: &[&dyn ToSql],
+    ) -> Fallible<Vec<(String, Crate, Triage)>> {
+        let triage = self
+            .db
+            .query(sql, params, |row| -> Fallible<(String, Crate, Triage)> {
+                let krate: String = row.get("crate");
+                let status: String = row.get("status");
+                Ok((
+                    row.get("experiment"),
+                    krate.parse()?,
+                    Triage {
+                        status: status.parse()?,
+                        note: row.get("note"),
+                        link: row.get("link"),
+                        updated_by: row.get("updated_by"),
+                        updated_at: row.get("updated_at"),
+                        carried_over_from: None,
+                    },
+                ))
+            })?;
+
+        triage.into_iter().collect()
+    }
+
+    /// Load the results of all the crates of the experiment at once, which is a lot faster than
+    /// loading them one by one.
+    pub fn load_all_results(
+        &self,
+        ex: &Experiment,
+    ) -> Fallible<HashMap<Crate, [Option<TestResult>; 2]>> {
+        let rows = self.db.query(
+            "SELECT crate, toolchain, result FROM results WHERE experiment = ?1;",
+            &[&ex.name],
+            |row| -> Fallible<(Crate, String, TestResult)> {
+                let krate: String = row.get("crate");
+                let result: String = row.get("result");
+                Ok((krate.parse()?, row.get("toolchain"), result.parse()?))
+            },
+        )?;
+
+        let start = ex.toolchains[0].to_string();
+        let end = ex.toolchains[1].to_string();
+        let mut results = HashMap::new();
+        for row in rows {
+            let (krate, toolchain, result) = row?;
+            let runs = results.entry(krate).or_insert([None, None]);
+            if toolchain == start {
+                runs[0] = Some(result);
+            } else if toolchain == end {
+                runs[1] = Some(result);
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Load every result of a crate across all the experiments, sorted by date. The name is
+    /// either the name of a crates.io crate, matching all its versions, or the `org/repo` of a
+    /// GitHub repository, matching all its commits.
+    pub fn load_crate_history(&self, name: &str) -> Fallible<Vec<CrateHistoryEntry>> {
+        let prefix = if name.contains('/') {
+            format!("gh/{}", name)
+        } else {
+            format!("reg/{}", name)
+        };
+        // Match the crate itself and everything below it with a range, so the index is used
+        let below_start = format!("{}/", prefix);
+        let below_end = format!("{}0", prefix);
+
+        let rows = self.db.query(
+            "SELECT results.experiment, results.crate, results.toolchain, results.result, \
+             COALESCE(experiments.started_at, experiments.created_at) AS date, \
+             experiments.report_url \
+             FROM results INNER JOIN experiments ON experiments.name = results.experiment \
+             WHERE results.crate = ?1 OR (results.crate >= ?2 AND results.crate < ?3) \
+             ORDER BY date, results.experiment, results.toolchain;",
+            &[&prefix, &below_start, &below_end],
+            |row| -> Fallible<CrateHistoryEntry> {
+                let krate: String = row.get("crate");
+                let toolchain: String = row.get("toolchain");
+                let result: String = row.get("result");
+                Ok(CrateHistoryEntry {
+                    experiment: row.get("experiment"),
+                    date: row.get("date"),
+                    report_url: row.get("report_url"),
+                    krate: krate.parse()?,
+                    toolchain: toolchain.parse()?,
+                    result: result.parse()?,
+                })
+            },
+        )?;
+
+        rows.into_iter().collect()
     }
 }
 
@@ -111,22 +379,15 @@ impl<'a> ReadResults for DatabaseDB<'a> {
         toolchain: &Toolchain,
         krate: &Crate,
     ) -> Fallible<Option<EncodedLog>> {
-        self.db.get_row(
-            "SELECT log, encoding FROM results \
-             WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3 \
-             LIMIT 1;",
-            &[&ex.name, &toolchain.to_string(), &krate.id()],
-            |row| {
-                let log: Vec<u8> = row.get("log");
-                let encoding: String = row.get("encoding");
-                let encoding = encoding.parse().unwrap();
-
-                match encoding {
-                    EncodingType::Plain => EncodedLog::Plain(log),
-                    EncodingType::Gzip => EncodedLog::Gzip(log),
-                }
-            },
-        )
+        if let Some(hash) = load_log_hash(self.db, ex, toolchain, krate)? {
+            let log = self.logs.load(&hash)?;
+            if log.is_none() {
+                bail!("log {} is missing from the log store", hash);
+            }
+            Ok(log)
+        } else {
+            Ok(None)
+        }
     }
 
     fn load_test_result(
@@ -152,6 +413,153 @@ impl<'a> ReadResults for DatabaseDB<'a> {
             Ok(None)
         }
     }
+
+    fn load_diagnostics(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<Vec<Diagnostic>> {
+        let diagnostics = self.db.query(
+            "SELECT * FROM diagnostics \
+             WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3 \
+             ORDER BY rowid;",
+            &[&ex.name, &toolchain.to_string(), &krate.id()],
+            |row| -> Fallible<Diagnostic> {
+                let level: String = row.get("level");
+                Ok(Diagnostic {
+                    level: level.parse()?,
+                    code: row.get("code"),
+                    lint: row.get("lint"),
+                    message: row.get("message"),
+                    file: row.get("file"),
+                    line: row.get("line"),
+                })
+            },
+        )?;
+
+        // Turns Vec<Fallible<Diagnostic>> into Fallible<Vec<Diagnostic>>
+        diagnostics.into_iter().collect()
+    }
+
+    fn load_classifier(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<Option<String>> {
+        let classifier: Option<Option<String>> = self.db.get_row(
+            "SELECT classifier FROM results \
+             WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3 \
+             LIMIT 1;",
+            &[&ex.name, &toolchain.to_string(), &krate.id()],
+            |row| row.get("classifier"),
+        )?;
+
+        Ok(classifier.and_then(|classifier| classifier))
+    }
+
+    fn load_test_result_with_timing(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<Option<(TestResult, Option<TaskTiming>)>> {
+        let row = self.db.get_row(
+            "SELECT result, started_at, finished_at FROM results \
+             WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3 \
+             LIMIT 1;",
+            &[&ex.name, &toolchain.to_string(), &krate.id()],
+            |row| {
+                let result: String = row.get("result");
+                // Results recorded before timings were collected don't have one
+                let timing = match (row.get("started_at"), row.get("finished_at")) {
+                    (Some(started_at), Some(finished_at)) => Some(TaskTiming {
+                        started_at,
+                        finished_at,
+                    }),
+                    _ => None,
+                };
+                (result, timing)
+            },
+        )?;
+
+        if let Some((result, timing)) = row {
+            Ok(Some((result.parse()?, timing)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn load_triage(&self, ex: &Experiment) -> Fallible<HashMap<Crate, Triage>> {
+        let mut triage = HashMap::new();
+
+        // Reruns are created for the same issue, and might test newer versions of the crates: the
+        // most recent triage of each crate in the other experiments of the issue is carried over.
+        if let Some(issue) = &ex.github_issue {
+            let mut previous = HashMap::new();
+            for (experiment, krate, mut entry) in self.query_triage(
+                "SELECT triage.* FROM triage \
+                 INNER JOIN experiments ON experiments.name = triage.experiment \
+                 WHERE experiments.github_issue = ?1 AND triage.experiment != ?2 \
+                 ORDER BY triage.updated_at;",
+                &[&issue.api_url, &ex.name],
+            )? {
+                entry.carried_over_from = Some(experiment);
+                previous.insert(krate.unversioned_id(), entry);
+            }
+
+            if !previous.is_empty() {
+                for krate in ex.get_crates(self.db)? {
+                    if let Some(entry) = previous.get(&krate.unversioned_id()) {
+                        triage.insert(krate, entry.clone());
+                    }
+                }
+            }
+        }
+
+        for (_, krate, entry) in
+            self.query_triage("SELECT * FROM triage WHERE experiment = ?1;", &[&ex.name])?
+        {
+            triage.insert(krate, entry);
+        }
+
+        Ok(triage)
+    }
+
+    fn load_environments(&self, ex: &Experiment) -> Fallible<BTreeMap<String, Environment>> {
+        let rows = self.db.query(
+            "SELECT * FROM environments WHERE experiment = ?1 ORDER BY rowid;",
+            &[&ex.name],
+            |row| -> (String, Option<String>, Option<String>, ToolchainVersions) {
+                (
+                    row.get("agent"),
+                    row.get("git_revision"),
+                    row.get("docker_image"),
+                    ToolchainVersions {
+                        toolchain: row.get("toolchain"),
+                        rustc: row.get("rustc_version"),
+                        cargo: row.get("cargo_version"),
+                    },
+                )
+            },
+        )?;
+
+        let mut environments = BTreeMap::new();
+        for (agent, git_revision, docker_image, toolchain) in rows {
+            environments
+                .entry(agent)
+                .or_insert_with(|| Environment {
+                    git_revision,
+                    docker_image,
+                    toolchains: Vec::new(),
+                })
+                .toolchains
+                .push(toolchain);
+        }
+
+        Ok(environments)
+    }
 }
 
 impl<'a> WriteResults for DatabaseDB<'a> {
@@ -172,6 +580,21 @@ impl<'a> WriteResults for DatabaseDB<'a> {
         Ok(())
     }
 
+    fn record_environment(&self, ex: &Experiment, env: &Environment) -> Fallible<()> {
+        // Experiments run from the CLI are executed locally
+        self.store_environment(ex, &Assignee::CLI.to_string(), env)
+    }
+
+    fn record_diagnostics(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        diagnostics: Vec<Diagnostic>,
+    ) -> Fallible<()> {
+        self.store_diagnostics(ex, krate, toolchain, &diagnostics)
+    }
+
     fn record_result<F>(
         &self,
         ex: &Experiment,
@@ -186,14 +609,17 @@ impl<'a> WriteResults for DatabaseDB<'a> {
         F: FnOnce() -> Fallible<TestResult>,
     {
         let storage = existing_logs.unwrap_or_else(|| LogStorage::from(config));
-        let result = logging::capture(&storage, f)?;
+        let (result, timing) = capture_timed(&storage, f)?;
         let output = storage.to_string();
+        let classifier = config.classify(&output).map(|c| c.label.as_str());
         self.store_result(
             ex,
             krate,
             toolchain,
             &result,
             output.as_bytes(),
+            classifier,
+            Some(&timing),
             encoding_type,
         )?;
         Ok(result)
@@ -202,17 +628,39 @@ impl<'a> WriteResults for DatabaseDB<'a> {
 
 impl<'a> DeleteResults for DatabaseDB<'a> {
     fn delete_all_results(&self, ex: &Experiment) -> Fallible<()> {
-        self.db
-            .execute("DELETE FROM results WHERE experiment = ?1;", &[&ex.name])?;
-        Ok(())
+        let _lock = LOGS_LOCK.lock().unwrap();
+        let hashes = self.db.transaction(|t| {
+            let hashes: Vec<String> = t.query(
+                "SELECT DISTINCT log_hash FROM results WHERE experiment = ?1;",
+                &[&ex.name],
+                |row| row.get("log_hash"),
+            )?;
+            t.execute("DELETE FROM results WHERE experiment = ?1;", &[&ex.name])?;
+            t.execute(
+                "DELETE FROM diagnostics WHERE experiment = ?1;",
+                &[&ex.name],
+            )?;
+            Ok(hashes)
+        })?;
+        self.delete_unused_logs(hashes)
     }
 
     fn delete_result(&self, ex: &Experiment, tc: &Toolchain, krate: &Crate) -> Fallible<()> {
-        self.db.execute(
-            "DELETE FROM results WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3;",
-            &[&ex.name, &tc.to_string(), &krate.id()],
-        )?;
-        Ok(())
+        let _lock = LOGS_LOCK.lock().unwrap();
+        let old_hash = self.db.transaction(|t| {
+            let old_hash = load_log_hash(t, ex, tc, krate)?;
+            t.execute(
+                "DELETE FROM results WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3;",
+                &[&ex.name, &tc.to_string(), &krate.id()],
+            )?;
+            t.execute(
+                "DELETE FROM diagnostics \
+                 WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3;",
+                &[&ex.name, &tc.to_string(), &krate.id()],
+            )?;
+            Ok(old_hash)
+        })?;
+        self.delete_unused_logs(old_hash)
     }
 }
 
@@ -220,16 +668,20 @@ impl<'a> DeleteResults for DatabaseDB<'a> {
 mod tests {
     use super::{DatabaseDB, ProgressData, TaskResult};
     use crate::actions::{Action, ActionsCtx, CreateExperiment};
-    use crate::config::Config;
+    use crate::config::{ClassifierConfig, ClassifierRegex, Config};
     use crate::crates::{Crate, RegistryCrate};
-    use crate::db::Database;
-    use crate::experiments::Experiment;
+    use crate::db::{Database, QueryUtils};
+    use crate::experiments::{Experiment, GitHubIssue};
     use crate::prelude::*;
     use crate::results::{
-        DeleteResults, EncodedLog, EncodingType, FailureReason, ReadResults, TestResult,
-        WriteResults,
+        DeleteResults, Diagnostic, DiagnosticLevel, EncodedLog, EncodingType, Environment,
+        FailureReason, FileLogStore, LogStore, ReadResults, TaskTiming, TestResult,
+        ToolchainVersions, Triage, TriageStatus, WriteResults,
     };
-    use crate::toolchain::{MAIN_TOOLCHAIN, TEST_TOOLCHAIN};
+    use crate::toolchain::{Toolchain, MAIN_TOOLCHAIN, TEST_TOOLCHAIN};
+    use chrono::Utc;
+    use std::sync::Arc;
+    use tempfile::tempdir;
 
     use std::collections::BTreeSet;
 
@@ -291,7 +743,14 @@ mod tests {
 
         let db = Database::temp().unwrap();
         let results = DatabaseDB::new(&db);
-        let config = Config::default();
+        let mut config = Config::default();
+        config.classifiers.insert(
+            ClassifierRegex::new("hello w.rld").unwrap(),
+            ClassifierConfig {
+                label: "greeting".into(),
+                spurious: false,
+            },
+        );
         let ctx = ActionsCtx::new(&db, &config);
 
         crate::crates::lists::setup_test_lists(&db, &config).unwrap();
@@ -336,8 +795,21 @@ mod tests {
         assert!(String::from_utf8_lossy(match result_var {
             EncodedLog::Plain(ref data) => data,
             EncodedLog::Gzip(_) => panic!("The encoded log should not be Gzipped."),
+            EncodedLog::Zstd(_) => panic!("The encoded log should not be Zstd-compressed."),
         })
         .contains("hello world"));
+        assert_eq!(
+            results
+                .load_classifier(&ex, &MAIN_TOOLCHAIN, &krate)
+                .unwrap(),
+            Some("greeting".into())
+        );
+        let (res, timing) = results
+            .load_test_result_with_timing(&ex, &MAIN_TOOLCHAIN, &krate)
+            .unwrap()
+            .unwrap();
+        assert_eq!(res, TestResult::TestPass);
+        assert!(timing.unwrap().wall_time() >= chrono::Duration::zero());
 
         // Ensure no data is returned for missing results
         assert!(results
@@ -373,6 +845,10 @@ mod tests {
             results.get_result(&ex, &TEST_TOOLCHAIN, &krate).unwrap(),
             Some(TestResult::TestFail(FailureReason::Unknown))
         );
+        assert!(results
+            .load_classifier(&ex, &TEST_TOOLCHAIN, &krate)
+            .unwrap()
+            .is_none());
 
         // Test deleting the newly-added result
         results.delete_result(&ex, &TEST_TOOLCHAIN, &krate).unwrap();
@@ -415,6 +891,20 @@ mod tests {
             version: "1.2".into(),
         });
 
+        let diagnostic = Diagnostic {
+            level: DiagnosticLevel::Warning,
+            code: None,
+            lint: Some("unused_variables".into()),
+            message: "unused variable: `foo`".into(),
+            file: Some("src/lib.rs".into()),
+            line: Some(42),
+        };
+
+        let timing = TaskTiming {
+            started_at: Utc::now(),
+            finished_at: Utc::now() + chrono::Duration::seconds(42),
+        };
+
         // Store a result and versions
         results
             .store(
@@ -425,6 +915,9 @@ mod tests {
                         toolchain: MAIN_TOOLCHAIN.clone(),
                         result: TestResult::TestPass,
                         log: base64::encode("foo"),
+                        diagnostics: vec![diagnostic.clone()],
+                        classifier: Some("flaky-server".into()),
+                        timing: Some(timing.clone()),
                     }],
                     version: Some((krate.clone(), updated.clone())),
                 },
@@ -442,6 +935,24 @@ mod tests {
                 .unwrap(),
             Some(TestResult::TestPass)
         );
+        assert_eq!(
+            results
+                .load_diagnostics(&ex, &MAIN_TOOLCHAIN, &updated)
+                .unwrap(),
+            vec![diagnostic]
+        );
+        assert_eq!(
+            results
+                .load_classifier(&ex, &MAIN_TOOLCHAIN, &updated)
+                .unwrap(),
+            Some("flaky-server".into())
+        );
+        assert_eq!(
+            results
+                .load_test_result_with_timing(&ex, &MAIN_TOOLCHAIN, &updated)
+                .unwrap(),
+            Some((TestResult::TestPass, Some(timing)))
+        );
 
         assert_eq!(
             results.load_log(&ex, &MAIN_TOOLCHAIN, &krate).unwrap(),
@@ -453,5 +964,328 @@ mod tests {
                 .unwrap(),
             None
         );
+        assert!(results
+            .load_diagnostics(&ex, &MAIN_TOOLCHAIN, &krate)
+            .unwrap()
+            .is_empty());
+
+        // Deleting the result also deletes its diagnostics
+        results
+            .delete_result(&ex, &MAIN_TOOLCHAIN, &updated)
+            .unwrap();
+        assert!(results
+            .load_diagnostics(&ex, &MAIN_TOOLCHAIN, &updated)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_log_deduplication() {
+        let db = Database::temp().unwrap();
+        let results = DatabaseDB::new(&db);
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        // Create a dummy experiment to attach the results to
+        CreateExperiment::dummy("dummy").apply(&ctx).unwrap();
+        let ex = Experiment::get(&db, "dummy").unwrap().unwrap();
+
+        let krate = Crate::Registry(RegistryCrate {
+            name: "lazy_static".into(),
+            version: "1".into(),
+        });
+        let logs_count = || -> i64 {
+            db.get_row(
+                "SELECT COUNT(*) AS count FROM logs;",
+                &[] as &[u32],
+                |row| row.get("count"),
+            )
+            .unwrap()
+            .unwrap()
+        };
+        let store = |toolchain: &Toolchain, log: &str, encoding: EncodingType| {
+            results
+                .store(
+                    &ex,
+                    &ProgressData {
+                        results: vec![TaskResult {
+                            krate: krate.clone(),
+                            toolchain: toolchain.clone(),
+                            result: TestResult::TestPass,
+                            log: base64::encode(log),
+                            diagnostics: Vec::new(),
+                            classifier: None,
+                            timing: None,
+                        }],
+                        version: None,
+                    },
+                    encoding,
+                )
+                .unwrap();
+        };
+
+        // Identical logs are stored only once, with the encoding of the first one
+        store(&MAIN_TOOLCHAIN, "same log", EncodingType::Zstd);
+        store(&TEST_TOOLCHAIN, "same log", EncodingType::Plain);
+        assert_eq!(logs_count(), 1);
+        for tc in &[&*MAIN_TOOLCHAIN, &*TEST_TOOLCHAIN] {
+            let log = results.load_log(&ex, tc, &krate).unwrap().unwrap();
+            assert_eq!(log.get_encoding_type(), EncodingType::Zstd);
+            assert_eq!(log.to_plain().unwrap(), b"same log".to_vec());
+        }
+
+        // Replacing a result keeps the log if it's still used by another result...
+        store(&TEST_TOOLCHAIN, "another log", EncodingType::Plain);
+        assert_eq!(logs_count(), 2);
+
+        // ...and removes it when it's not used anymore
+        store(&TEST_TOOLCHAIN, "yet another log", EncodingType::Plain);
+        assert_eq!(logs_count(), 2);
+        assert_eq!(
+            results
+                .load_log(&ex, &TEST_TOOLCHAIN, &krate)
+                .unwrap()
+                .unwrap(),
+            EncodedLog::Plain(b"yet another log".to_vec())
+        );
+
+        // Deleting the results removes the unused logs as well
+        results.delete_result(&ex, &MAIN_TOOLCHAIN, &krate).unwrap();
+        assert_eq!(logs_count(), 1);
+        results.delete_all_results(&ex).unwrap();
+        assert_eq!(logs_count(), 0);
+    }
+
+    #[test]
+    fn test_external_log_store() {
+        let db = Database::temp().unwrap();
+        let dir = tempdir().unwrap();
+        let logs = Arc::new(FileLogStore::new(dir.path().into()));
+        let results = DatabaseDB::with_log_store(&db, logs.clone());
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        // Create a dummy experiment to attach the results to
+        CreateExperiment::dummy("dummy").apply(&ctx).unwrap();
+        let ex = Experiment::get(&db, "dummy").unwrap().unwrap();
+
+        let krate = Crate::Registry(RegistryCrate {
+            name: "lazy_static".into(),
+            version: "1".into(),
+        });
+        let hash = EncodedLog::hash_plain(b"external log");
+
+        results
+            .store(
+                &ex,
+                &ProgressData {
+                    results: vec![TaskResult {
+                        krate: krate.clone(),
+                        toolchain: MAIN_TOOLCHAIN.clone(),
+                        result: TestResult::TestPass,
+                        log: base64::encode("external log"),
+                        diagnostics: Vec::new(),
+                        classifier: None,
+                        timing: None,
+                    }],
+                    version: None,
+                },
+                EncodingType::Zstd,
+            )
+            .unwrap();
+
+        // The log is stored in the log store, and only its hash is kept in the database
+        assert!(logs.exists(&hash).unwrap());
+        assert!(!db
+            .exists("SELECT 1 FROM logs WHERE hash = ?1;", &[&hash])
+            .unwrap());
+        let log = results
+            .load_log(&ex, &MAIN_TOOLCHAIN, &krate)
+            .unwrap()
+            .unwrap();
+        assert_eq!(log.get_encoding_type(), EncodingType::Zstd);
+        assert_eq!(log.to_plain().unwrap(), b"external log".to_vec());
+
+        // Deleting the result removes the log from the log store
+        results.delete_result(&ex, &MAIN_TOOLCHAIN, &krate).unwrap();
+        assert!(!logs.exists(&hash).unwrap());
+    }
+
+    #[test]
+    fn test_triage() {
+        let db = Database::temp().unwrap();
+        let results = DatabaseDB::new(&db);
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        let issue = GitHubIssue {
+            api_url: "https://api.github.com/repos/rust-lang/rust/issues/1".into(),
+            html_url: "https://github.com/rust-lang/rust/issues/1".into(),
+            number: 1,
+        };
+        for (name, github_issue) in &[
+            ("first", Some(issue.clone())),
+            ("rerun", Some(issue)),
+            ("unrelated", None),
+        ] {
+            let mut create = CreateExperiment::dummy(name);
+            create.github_issue = github_issue.clone();
+            create.apply(&ctx).unwrap();
+        }
+        let first = Experiment::get(&db, "first").unwrap().unwrap();
+        let rerun = Experiment::get(&db, "rerun").unwrap().unwrap();
+        let unrelated = Experiment::get(&db, "unrelated").unwrap().unwrap();
+
+        let build_fail = Crate::Local("build-fail".into());
+        let test_fail = Crate::Local("test-fail".into());
+        let triage = |status, note: &str| Triage {
+            status,
+            note: note.into(),
+            link: None,
+            updated_by: "octocat".into(),
+            updated_at: Utc::now(),
+            carried_over_from: None,
+        };
+
+        let expected = triage(TriageStatus::Expected, "intended breakage");
+        results
+            .store_triage(&first, &build_fail, &expected)
+            .unwrap();
+        let spurious = triage(TriageStatus::Spurious, "");
+        results.store_triage(&first, &test_fail, &spurious).unwrap();
+        let filed = Triage {
+            link: Some("https://github.com/rust-lang/rust/issues/2".into()),
+            ..triage(TriageStatus::IssueFiled, "")
+        };
+        results.store_triage(&rerun, &test_fail, &filed).unwrap();
+
+        let loaded = results.load_triage(&first).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[&build_fail], expected);
+
+        // The triage of the previous experiment is carried over, unless it was overridden
+        let loaded = results.load_triage(&rerun).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(
+            loaded[&build_fail],
+            Triage {
+                carried_over_from: Some("first".into()),
+                ..expected
+            }
+        );
+        assert_eq!(loaded[&test_fail], filed);
+
+        // Experiments created for other issues don't share the triage
+        assert!(results.load_triage(&unrelated).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_environments() {
+        let db = Database::temp().unwrap();
+        let results = DatabaseDB::new(&db);
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+        CreateExperiment::dummy("dummy").apply(&ctx).unwrap();
+        let ex = Experiment::get(&db, "dummy").unwrap().unwrap();
+
+        let env = |rustc: &str| Environment {
+            git_revision: Some("0123456".into()),
+            docker_image: None,
+            toolchains: vec![
+                ToolchainVersions {
+                    toolchain: MAIN_TOOLCHAIN.to_string(),
+                    rustc: "rustc 1.40.0".into(),
+                    cargo: "cargo 1.40.0".into(),
+                },
+                ToolchainVersions {
+                    toolchain: TEST_TOOLCHAIN.to_string(),
+                    rustc: rustc.into(),
+                    cargo: "cargo 1.41.0-beta.1".into(),
+                },
+            ],
+        };
+        assert!(results.load_environments(&ex).unwrap().is_empty());
+
+        results
+            .record_environment(&ex, &env("rustc 1.41.0-beta.1"))
+            .unwrap();
+        results
+            .store_environment(&ex, "agent:foo", &env("rustc 1.41.0-beta.1"))
+            .unwrap();
+        // Recording the environment again replaces the previous one
+        results
+            .store_environment(&ex, "agent:foo", &env("rustc 1.41.0-beta.2"))
+            .unwrap();
+
+        let loaded = results.load_environments(&ex).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded["cli"], env("rustc 1.41.0-beta.1"));
+        assert_eq!(loaded["agent:foo"], env("rustc 1.41.0-beta.2"));
+    }
+
+    #[test]
+    fn test_load_crate_history() {
+        let db = Database::temp().unwrap();
+        let results = DatabaseDB::new(&db);
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+        CreateExperiment::dummy("first").apply(&ctx).unwrap();
+        CreateExperiment::dummy("second").apply(&ctx).unwrap();
+        let first = Experiment::get(&db, "first").unwrap().unwrap();
+        let second = Experiment::get(&db, "second").unwrap().unwrap();
+
+        let krate = |name: &str, version: &str| {
+            Crate::Registry(RegistryCrate {
+                name: name.into(),
+                version: version.into(),
+            })
+        };
+        let store = |ex: &Experiment, krate: &Crate, result: &TestResult| {
+            results
+                .store_result(
+                    ex,
+                    krate,
+                    &MAIN_TOOLCHAIN,
+                    result,
+                    b"log",
+                    None,
+                    None,
+                    EncodingType::Plain,
+                )
+                .unwrap();
+        };
+
+        let failed = TestResult::BuildFail(FailureReason::Unknown);
+        store(&second, &krate("lazy_static", "1.1"), &failed);
+        store(&first, &krate("lazy_static", "1.0"), &TestResult::TestPass);
+        // Neither other crates nor crates sharing a prefix are included
+        store(&first, &krate("lazy_static_plus", "1.0"), &failed);
+        store(&first, &krate("syn", "1.0"), &failed);
+
+        let history = results.load_crate_history("lazy_static").unwrap();
+        assert_eq!(
+            history
+                .iter()
+                .map(|entry| (entry.experiment.as_str(), &entry.krate, &entry.result))
+                .collect::<Vec<_>>(),
+            vec![
+                ("first", &krate("lazy_static", "1.0"), &TestResult::TestPass),
+                ("second", &krate("lazy_static", "1.1"), &failed),
+            ]
+        );
+        assert!(history
+            .iter()
+            .all(|entry| entry.toolchain == *MAIN_TOOLCHAIN));
+        assert!(results.load_crate_history("missing").unwrap().is_empty());
     }
 }
@@ -1,14 +1,21 @@
 use crate::crates::Crate;
 use crate::experiments::Experiment;
 use crate::prelude::*;
-use crate::results::{EncodedLog, ReadResults, TestResult};
+use crate::results::{
+    Diagnostic, EncodedLog, Environment, ReadResults, TaskTiming, TestResult, Triage,
+};
 use crate::toolchain::Toolchain;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Default)]
 struct DummyData {
     logs: HashMap<(Crate, Toolchain), EncodedLog>,
     results: HashMap<(Crate, Toolchain), TestResult>,
+    diagnostics: HashMap<(Crate, Toolchain), Vec<Diagnostic>>,
+    classifiers: HashMap<(Crate, Toolchain), String>,
+    timings: HashMap<(Crate, Toolchain), TaskTiming>,
+    triage: HashMap<Crate, Triage>,
+    environments: BTreeMap<String, Environment>,
 }
 
 #[derive(Default)]
@@ -45,6 +52,64 @@ impl DummyDB {
             .results
             .insert((krate, tc), res);
     }
+
+    pub fn add_dummy_diagnostics(
+        &mut self,
+        ex: &Experiment,
+        krate: Crate,
+        tc: Toolchain,
+        diagnostics: Vec<Diagnostic>,
+    ) {
+        self.experiments
+            .entry(ex.name.to_string())
+            .or_insert_with(DummyData::default)
+            .diagnostics
+            .insert((krate, tc), diagnostics);
+    }
+
+    pub fn add_dummy_classifier(
+        &mut self,
+        ex: &Experiment,
+        krate: Crate,
+        tc: Toolchain,
+        label: &str,
+    ) {
+        self.experiments
+            .entry(ex.name.to_string())
+            .or_insert_with(DummyData::default)
+            .classifiers
+            .insert((krate, tc), label.to_string());
+    }
+
+    pub fn add_dummy_timing(
+        &mut self,
+        ex: &Experiment,
+        krate: Crate,
+        tc: Toolchain,
+        timing: TaskTiming,
+    ) {
+        self.experiments
+            .entry(ex.name.to_string())
+            .or_insert_with(DummyData::default)
+            .timings
+            .insert((krate, tc), timing);
+    }
+
+    pub fn add_dummy_environment(&mut self, ex: &Experiment, agent: &str, env: Environment) {
+        self.experiments
+            .entry(ex.name.to_string())
+            .or_insert_with(DummyData::default)
+            .environments
+            .insert(agent.to_string(), env);
+    }
+
+    pub fn add_dummy_triage(&mut self, ex: &Experiment, krate: Crate, triage: Triage) {
+        self.experiments
+            .entry(ex.name.to_string())
+            .or_insert_with(DummyData::default)
+            .triage
+            .insert(krate, triage);
+    }
 }
 
 impl ReadResults for DummyDB {
@@ -73,4 +138,53 @@ impl ReadResults for DummyDB {
             .get(&(krate.clone(), toolchain.clone()))
             .cloned())
     }
+
+    fn load_diagnostics(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<Vec<Diagnostic>> {
+        Ok(self
+            .get_data(ex)?
+            .diagnostics
+            .get(&(krate.clone(), toolchain.clone()))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn load_classifier(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<Option<String>> {
+        Ok(self
+            .get_data(ex)?
+            .classifiers
+            .get(&(krate.clone(), toolchain.clone()))
+            .cloned())
+    }
+
+    fn load_test_result_with_timing(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<Option<(TestResult, Option<TaskTiming>)>> {
+        let data = self.get_data(ex)?;
+        let key = (krate.clone(), toolchain.clone());
+        Ok(data
+            .results
+            .get(&key)
+            .map(|res| (res.clone(), data.timings.get(&key).cloned())))
+    }
+
+    fn load_triage(&self, ex: &Experiment) -> Fallible<HashMap<Crate, Triage>> {
+        Ok(self.get_data(ex)?.triage.clone())
+    }
+
+    fn load_environments(&self, ex: &Experiment) -> Fallible<BTreeMap<String, Environment>> {
+        Ok(self.get_data(ex)?.environments.clone())
+    }
 }
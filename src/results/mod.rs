@@ -1,20 +1,24 @@
 mod db;
 #[cfg(test)]
 mod dummy;
+mod storage;
 use crate::config::Config;
 use crate::crates::Crate;
 use crate::experiments::Experiment;
 use crate::prelude::*;
 
-pub use crate::results::db::{DatabaseDB, ProgressData};
+pub use crate::results::db::{CrateHistoryEntry, DatabaseDB, ProgressData};
 #[cfg(test)]
 pub use crate::results::dummy::DummyDB;
+pub use crate::results::storage::{open_log_store, DatabaseLogStore, FileLogStore, LogStore};
 use crate::toolchain::Toolchain;
+use chrono::{DateTime, Utc};
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
 use rustwide::logging::LogStorage;
-use std::collections::BTreeSet;
+use sha1::{Digest, Sha1};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::{fmt, io::Read, io::Write, str::FromStr};
 
 pub trait ReadResults {
@@ -30,6 +34,31 @@ pub trait ReadResults {
         toolchain: &Toolchain,
         krate: &Crate,
     ) -> Fallible<Option<TestResult>>;
+    fn load_diagnostics(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<Vec<Diagnostic>>;
+    fn load_classifier(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<Option<String>>;
+    /// Load the result along with its timing, which results recorded before the timings were
+    /// collected don't have.
+    fn load_test_result_with_timing(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<Option<(TestResult, Option<TaskTiming>)>>;
+    /// Load the triage of the crates of the experiment, including the one carried over from
+    /// previous runs.
+    fn load_triage(&self, ex: &Experiment) -> Fallible<HashMap<Crate, Triage>>;
+    /// Load the environments the agents ran the experiment in, keyed by the name of the agent.
+    fn load_environments(&self, ex: &Experiment) -> Fallible<BTreeMap<String, Environment>>;
 }
 
 pub trait WriteResults {
@@ -40,6 +69,15 @@ pub trait WriteResults {
         krate: &Crate,
     ) -> Fallible<Option<TestResult>>;
     fn update_crate_version(&self, ex: &Experiment, old: &Crate, new: &Crate) -> Fallible<()>;
+    fn record_environment(&self, ex: &Experiment, env: &Environment) -> Fallible<()>;
+    fn record_diagnostics(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        diagnostics: Vec<Diagnostic>,
+    ) -> Fallible<()>;
+    /// Run the task producing the result, and record it along with its log and timing.
     fn record_result<F>(
         &self,
         ex: &Experiment,
@@ -62,12 +100,14 @@ pub trait DeleteResults {
 string_enum!(pub enum EncodingType {
     Plain => "plain",
     Gzip => "gzip",
+    Zstd => "zstd",
 });
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum EncodedLog {
     Plain(Vec<u8>),
     Gzip(Vec<u8>),
+    Zstd(Vec<u8>),
 }
 
 impl EncodedLog {
@@ -80,13 +120,28 @@ impl EncodedLog {
                 decoded_log.read_to_end(&mut new_log)?;
                 Ok(new_log)
             }
+            EncodedLog::Zstd(data) => Ok(zstd::decode_all(data.as_slice())?),
         }
     }
 
+    pub fn from_encoded(data: Vec<u8>, encoding: EncodingType) -> EncodedLog {
+        match encoding {
+            EncodingType::Plain => EncodedLog::Plain(data),
+            EncodingType::Gzip => EncodedLog::Gzip(data),
+            EncodingType::Zstd => EncodedLog::Zstd(data),
+        }
+    }
+
+    /// Returns the content address of a log, which only depends on its decoded content.
+    pub fn hash_plain(data: &[u8]) -> String {
+        format!("{:x}", Sha1::digest(data))
+    }
+
     pub fn get_encoding_type(&self) -> EncodingType {
         match self {
             EncodedLog::Plain(_) => EncodingType::Plain,
             EncodedLog::Gzip(_) => EncodingType::Gzip,
+            EncodedLog::Zstd(_) => EncodingType::Zstd,
         }
     }
 
@@ -94,6 +149,7 @@ impl EncodedLog {
         match self {
             EncodedLog::Plain(data) => data,
             EncodedLog::Gzip(data) => data,
+            EncodedLog::Zstd(data) => data,
         }
     }
 
@@ -105,6 +161,7 @@ impl EncodedLog {
                 let encoded_log = encoded_log.finish()?;
                 Ok(EncodedLog::Gzip(encoded_log))
             }
+            EncodingType::Zstd => Ok(EncodedLog::Zstd(zstd::encode_all(data, 0)?)),
             EncodingType::Plain => Ok(EncodedLog::Plain(data.to_vec())),
         }
     }
@@ -191,12 +248,65 @@ impl ::std::str::FromStr for DiagnosticCode {
     }
 }
 
+string_enum!(pub enum DiagnosticLevel {
+    Ice => "ice",
+    Error => "error",
+    Warning => "warning",
+});
+
+/// A compiler diagnostic emitted while building one of the local packages of a crate.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub level: DiagnosticLevel,
+    /// The error code, for example `E0308`.
+    pub code: Option<String>,
+    /// The name of the lint that emitted the diagnostic, if any.
+    pub lint: Option<String>,
+    pub message: String,
+    /// The file and line of the primary span.
+    pub file: Option<String>,
+    pub line: Option<u32>,
+}
+
+impl Diagnostic {
+    /// Whether two diagnostics refer to the same problem, ignoring the wording of the message
+    /// (which might change between compiler versions).
+    pub fn same_as(&self, other: &Diagnostic) -> bool {
+        self.level == other.level
+            && self.code == other.code
+            && self.lint == other.lint
+            && self.file == other.file
+            && self.line == other.line
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.level)?;
+        if let Some(code) = self.code.as_ref().or_else(|| self.lint.as_ref()) {
+            write!(f, " {}", code)?;
+        }
+        if let Some(file) = &self.file {
+            write!(f, " at {}", file)?;
+            if let Some(line) = self.line {
+                write!(f, ":{}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
 pub enum FailureReason {
     Unknown,
     OOM,
     Timeout,
     ICE,
+    Linker,
+    BuildScript,
+    ProcMacro,
+    Network,
+    DiskFull,
     CompilerError(BTreeSet<DiagnosticCode>),
     DependsOn(BTreeSet<Crate>),
 }
@@ -210,6 +320,11 @@ impl ::std::fmt::Display for FailureReason {
             FailureReason::OOM => write!(f, "oom"),
             FailureReason::Timeout => write!(f, "timeout"),
             FailureReason::ICE => write!(f, "ice"),
+            FailureReason::Linker => write!(f, "linker"),
+            FailureReason::BuildScript => write!(f, "build-script"),
+            FailureReason::ProcMacro => write!(f, "proc-macro"),
+            FailureReason::Network => write!(f, "network"),
+            FailureReason::DiskFull => write!(f, "disk-full"),
             FailureReason::CompilerError(codes) => write!(
                 f,
                 "compiler-error({})",
@@ -261,6 +376,11 @@ impl ::std::str::FromStr for FailureReason {
                 "oom" => Ok(FailureReason::OOM),
                 "timeout" => Ok(FailureReason::Timeout),
                 "ice" => Ok(FailureReason::ICE),
+                "linker" => Ok(FailureReason::Linker),
+                "build-script" => Ok(FailureReason::BuildScript),
+                "proc-macro" => Ok(FailureReason::ProcMacro),
+                "network" => Ok(FailureReason::Network),
+                "disk-full" => Ok(FailureReason::DiskFull),
                 _ => bail!("unexpected value"),
             }
         }
@@ -270,11 +390,17 @@ impl ::std::str::FromStr for FailureReason {
 impl FailureReason {
     pub(crate) fn is_spurious(&self) -> bool {
         match *self {
-            FailureReason::OOM | FailureReason::Timeout => true,
+            FailureReason::OOM
+            | FailureReason::Timeout
+            | FailureReason::Network
+            | FailureReason::DiskFull => true,
             FailureReason::CompilerError(_)
             | FailureReason::DependsOn(_)
             | FailureReason::Unknown
-            | FailureReason::ICE => false,
+            | FailureReason::ICE
+            | FailureReason::Linker
+            | FailureReason::BuildScript
+            | FailureReason::ProcMacro => false,
         }
     }
 }
@@ -303,6 +429,118 @@ test_result_enum!(pub enum TestResult {
 
 impl_serde_from_parse!(TestResult, expecting = "a test result");
 
+string_enum!(pub enum TriageStatus {
+    Unreviewed => "unreviewed",
+    Expected => "expected",
+    Spurious => "spurious",
+    NeedsIssue => "needs-issue",
+    IssueFiled => "issue-filed",
+});
+
+/// The decision of a reviewer about the result of a crate in an experiment.
+#[cfg_attr(test, derive(Debug))]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+pub struct Triage {
+    pub status: TriageStatus,
+    pub note: String,
+    /// A link to the issue or PR tracking the result, if any.
+    pub link: Option<String>,
+    pub updated_by: String,
+    pub updated_at: DateTime<Utc>,
+    /// The experiment the triage was recorded in, if it was carried over from a previous run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub carried_over_from: Option<String>,
+}
+
+/// When a task producing a result ran.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TaskTiming {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+impl TaskTiming {
+    pub fn wall_time(&self) -> chrono::Duration {
+        self.finished_at.signed_duration_since(self.started_at)
+    }
+}
+
+/// Runs a task, capturing its output in the storage and measuring how long it took.
+pub(crate) fn capture_timed<F>(storage: &LogStorage, f: F) -> Fallible<(TestResult, TaskTiming)>
+where
+    F: FnOnce() -> Fallible<TestResult>,
+{
+    let started_at = Utc::now();
+    let result = rustwide::logging::capture(storage, f)?;
+    let timing = TaskTiming {
+        started_at,
+        finished_at: Utc::now(),
+    };
+    Ok((result, timing))
+}
+
+/// The versions of a toolchain installed on an agent.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ToolchainVersions {
+    /// The name of the toolchain, as displayed in the reports.
+    pub toolchain: String,
+    /// The output of `rustc -Vv`.
+    pub rustc: String,
+    /// The output of `cargo -V`.
+    pub cargo: String,
+}
+
+/// The environment an agent ran an experiment in.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Environment {
+    /// The git revision of Crater running on the agent.
+    pub git_revision: Option<String>,
+    /// The digest of the docker image the crates were built in.
+    pub docker_image: Option<String>,
+    pub toolchains: Vec<ToolchainVersions>,
+}
+
+impl Environment {
+    fn toolchain(&self, toolchain: &str) -> Option<&ToolchainVersions> {
+        self.toolchains.iter().find(|tc| tc.toolchain == toolchain)
+    }
+}
+
+/// Describes the parts of the environments that differ across the agents, which might make their
+/// results inconsistent. An empty list is returned if all the agents ran in the same environment.
+pub fn environment_differences(envs: &BTreeMap<String, Environment>) -> Vec<String> {
+    fn differs<T: PartialEq>(mut values: impl Iterator<Item = T>) -> bool {
+        match values.next() {
+            Some(first) => values.any(|value| value != first),
+            None => false,
+        }
+    }
+
+    let mut differences = Vec::new();
+    if differs(envs.values().map(|env| &env.git_revision)) {
+        differences.push("the revision of Crater".to_string());
+    }
+    if differs(envs.values().map(|env| &env.docker_image)) {
+        differences.push("the docker image".to_string());
+    }
+
+    let toolchains = envs
+        .values()
+        .flat_map(|env| env.toolchains.iter().map(|tc| tc.toolchain.as_str()))
+        .collect::<BTreeSet<_>>();
+    for toolchain in toolchains {
+        let versions = || envs.values().map(|env| env.toolchain(toolchain));
+        if differs(versions().map(|tc| tc.map(|tc| &tc.rustc))) {
+            differences.push(format!("the version of rustc for {}", toolchain));
+        }
+        if differs(versions().map(|tc| tc.map(|tc| &tc.cargo))) {
+            differences.push(format!("the version of cargo for {}", toolchain));
+        }
+    }
+
+    differences
+}
+
 #[cfg(test)]
 mod tests {
     use crate::crates::*;
@@ -344,6 +582,11 @@ mod tests {
             "build-fail:compiler-error(001)" => BuildFail(CompilerError(btreeset!["001".parse().unwrap()])),
             "build-fail:oom" => BuildFail(OOM),
             "build-fail:ice" => BuildFail(ICE),
+            "build-fail:linker" => BuildFail(Linker),
+            "build-fail:build-script" => BuildFail(BuildScript),
+            "build-fail:proc-macro" => BuildFail(ProcMacro),
+            "test-fail:network" => TestFail(Network),
+            "build-fail:disk-full" => BuildFail(DiskFull),
             "test-fail:timeout" => TestFail(Timeout),
             "test-pass" => TestPass,
             "error" => Error,
@@ -358,4 +601,73 @@ mod tests {
         assert!(TestResult::from_str("error:oom").is_err());
         assert!(TestResult::from_str("build-fail:pleasedonotaddthis").is_err());
     }
+
+    #[test]
+    fn test_encoded_log() {
+        use super::{EncodedLog, EncodingType};
+
+        let log = b"Compiling lazy_static v1.0.0\nFinished dev [unoptimized] target(s)\n";
+        for &encoding in &[EncodingType::Plain, EncodingType::Gzip, EncodingType::Zstd] {
+            let encoded = EncodedLog::from_plain_slice(log, encoding).unwrap();
+            assert_eq!(encoded.get_encoding_type(), encoding);
+            assert_eq!(encoded.to_plain().unwrap(), log.to_vec());
+
+            let reloaded = EncodedLog::from_encoded(encoded.as_slice().to_vec(), encoding);
+            assert_eq!(reloaded, encoded);
+        }
+
+        // The hash only depends on the content of the log
+        assert_eq!(
+            EncodedLog::hash_plain(b"hello world"),
+            "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed"
+        );
+    }
+
+    #[test]
+    fn test_environment_differences() {
+        use super::{environment_differences, Environment, ToolchainVersions};
+        use std::collections::BTreeMap;
+
+        let env = |rustc: &str, docker: &str| Environment {
+            git_revision: Some("0123456".into()),
+            docker_image: Some(docker.into()),
+            toolchains: vec![
+                ToolchainVersions {
+                    toolchain: "stable".into(),
+                    rustc: "rustc 1.40.0".into(),
+                    cargo: "cargo 1.40.0".into(),
+                },
+                ToolchainVersions {
+                    toolchain: "beta".into(),
+                    rustc: rustc.into(),
+                    cargo: "cargo 1.41.0-beta.1".into(),
+                },
+            ],
+        };
+
+        let mut envs = BTreeMap::new();
+        assert!(environment_differences(&envs).is_empty());
+
+        envs.insert(
+            "agent-1".to_string(),
+            env("rustc 1.41.0-beta.1", "image@sha256:1"),
+        );
+        envs.insert(
+            "agent-2".to_string(),
+            env("rustc 1.41.0-beta.1", "image@sha256:1"),
+        );
+        assert!(environment_differences(&envs).is_empty());
+
+        envs.insert(
+            "agent-3".to_string(),
+            env("rustc 1.41.0-beta.2", "image@sha256:2"),
+        );
+        assert_eq!(
+            environment_differences(&envs),
+            vec![
+                "the docker image".to_string(),
+                "the version of rustc for beta".to_string(),
+            ]
+        );
+    }
 }
@@ -0,0 +1,428 @@
+use crate::config::{Config, LogStorageConfig};
+use crate::db::{Database, QueryUtils};
+use crate::prelude::*;
+use crate::report::{self, S3Prefix};
+use crate::results::{EncodedLog, EncodingType};
+use rusoto_s3::{
+    DeleteObjectRequest, GetObjectError, GetObjectRequest, HeadObjectError, HeadObjectRequest,
+    PutObjectRequest, S3Client, S3,
+};
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Persistence of the build logs.
+///
+/// Logs are content-addressed: each one is identified by the hash of its plain content, which is
+/// the only thing the `results` table stores about it.
+pub trait LogStore: Send + Sync {
+    fn load(&self, hash: &str) -> Fallible<Option<EncodedLog>>;
+    fn exists(&self, hash: &str) -> Fallible<bool>;
+    fn store(&self, hash: &str, log: &EncodedLog) -> Fallible<()>;
+    fn delete(&self, hash: &str) -> Fallible<()>;
+}
+
+pub fn open_log_store(config: &Config, db: &Database) -> Fallible<Arc<dyn LogStore>> {
+    Ok(match config.log_storage {
+        LogStorageConfig::Database => Arc::new(DatabaseLogStore::new(db.clone())),
+        LogStorageConfig::Filesystem { ref path } => Arc::new(FallbackLogStore::new(
+            FileLogStore::new(path.clone()),
+            db.clone(),
+        )),
+        LogStorageConfig::S3 { ref prefix } => Arc::new(FallbackLogStore::new(
+            S3LogStore::new(prefix.parse()?)?,
+            db.clone(),
+        )),
+    })
+}
+
+/// Reads the logs missing from a store from the database instead, where the logs stored before
+/// switching to that store still are.
+pub struct FallbackLogStore<S: LogStore> {
+    store: S,
+    database: DatabaseLogStore,
+}
+
+impl<S: LogStore> FallbackLogStore<S> {
+    pub fn new(store: S, db: Database) -> Self {
+        FallbackLogStore {
+            store,
+            database: DatabaseLogStore::new(db),
+        }
+    }
+}
+
+impl<S: LogStore> LogStore for FallbackLogStore<S> {
+    fn load(&self, hash: &str) -> Fallible<Option<EncodedLog>> {
+        if let Some(log) = self.store.load(hash)? {
+            Ok(Some(log))
+        } else {
+            self.database.load(hash)
+        }
+    }
+
+    fn exists(&self, hash: &str) -> Fallible<bool> {
+        Ok(self.store.exists(hash)? || self.database.exists(hash)?)
+    }
+
+    fn store(&self, hash: &str, log: &EncodedLog) -> Fallible<()> {
+        self.store.store(hash, log)
+    }
+
+    fn delete(&self, hash: &str) -> Fallible<()> {
+        self.store.delete(hash)?;
+        self.database.delete(hash)
+    }
+}
+
+/// Stores the logs in the `logs` table of the Crater database.
+pub struct DatabaseLogStore {
+    db: Database,
+}
+
+impl DatabaseLogStore {
+    pub fn new(db: Database) -> Self {
+        DatabaseLogStore { db }
+    }
+}
+
+impl LogStore for DatabaseLogStore {
+    fn load(&self, hash: &str) -> Fallible<Option<EncodedLog>> {
+        let row = self.db.get_row(
+            "SELECT data, encoding FROM logs WHERE hash = ?1;",
+            &[hash],
+            |row| {
+                let data: Vec<u8> = row.get("data");
+                let encoding: String = row.get("encoding");
+                (data, encoding)
+            },
+        )?;
+
+        if let Some((data, encoding)) = row {
+            Ok(Some(EncodedLog::from_encoded(data, encoding.parse()?)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn exists(&self, hash: &str) -> Fallible<bool> {
+        self.db
+            .exists("SELECT 1 FROM logs WHERE hash = ?1;", &[&hash])
+    }
+
+    fn store(&self, hash: &str, log: &EncodedLog) -> Fallible<()> {
+        self.db.execute(
+            "INSERT OR IGNORE INTO logs (hash, encoding, data) VALUES (?1, ?2, ?3);",
+            &[&hash, &log.get_encoding_type().to_str(), &log.as_slice()],
+        )?;
+        Ok(())
+    }
+
+    fn delete(&self, hash: &str) -> Fallible<()> {
+        self.db
+            .execute("DELETE FROM logs WHERE hash = ?1;", &[&hash])?;
+        Ok(())
+    }
+}
+
+/// Stores each log in its own file, with the encoding as the file extension.
+pub struct FileLogStore {
+    root: PathBuf,
+}
+
+impl FileLogStore {
+    pub fn new(root: PathBuf) -> Self {
+        FileLogStore { root }
+    }
+
+    fn path(&self, hash: &str, encoding: EncodingType) -> PathBuf {
+        // Logs are spread in subdirectories to avoid having millions of files in the same one
+        self.root
+            .join(&hash[..2])
+            .join(format!("{}.{}", hash, encoding.to_str()))
+    }
+
+    fn find(&self, hash: &str) -> Option<(PathBuf, EncodingType)> {
+        [EncodingType::Zstd, EncodingType::Gzip, EncodingType::Plain]
+            .iter()
+            .map(|&encoding| (self.path(hash, encoding), encoding))
+            .find(|(path, _)| path.exists())
+    }
+}
+
+impl LogStore for FileLogStore {
+    fn load(&self, hash: &str) -> Fallible<Option<EncodedLog>> {
+        if let Some((path, encoding)) = self.find(hash) {
+            Ok(Some(EncodedLog::from_encoded(fs::read(&path)?, encoding)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn exists(&self, hash: &str) -> Fallible<bool> {
+        Ok(self.find(hash).is_some())
+    }
+
+    fn store(&self, hash: &str, log: &EncodedLog) -> Fallible<()> {
+        let path = self.path(hash, log.get_encoding_type());
+        fs::create_dir_all(path.parent().unwrap())?;
+
+        // Write to a temporary file first, so a log is never visible while it's half written
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, log.as_slice())?;
+        fs::rename(&tmp, &path)?;
+
+        Ok(())
+    }
+
+    fn delete(&self, hash: &str) -> Fallible<()> {
+        while let Some((path, _)) = self.find(hash) {
+            fs::remove_file(&path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Stores each log in its own object, with the encoding as the object's `Content-Encoding`.
+pub struct S3LogStore {
+    prefix: S3Prefix,
+    client: S3Client,
+}
+
+impl S3LogStore {
+    pub fn new(prefix: S3Prefix) -> Fallible<Self> {
+        let client = report::get_s3_client_for_bucket(&prefix.bucket)?;
+        Ok(Self::with_client(prefix, client))
+    }
+
+    pub fn with_client(prefix: S3Prefix, client: S3Client) -> Self {
+        S3LogStore { prefix, client }
+    }
+
+    fn key(&self, hash: &str) -> String {
+        self.prefix
+            .prefix
+            .join(&hash[..2])
+            .join(hash)
+            .to_string_lossy()
+            .into()
+    }
+}
+
+impl LogStore for S3LogStore {
+    fn load(&self, hash: &str) -> Fallible<Option<EncodedLog>> {
+        let req = GetObjectRequest {
+            bucket: self.prefix.bucket.clone(),
+            key: self.key(hash),
+            ..Default::default()
+        };
+        let object = match self.client.get_object(req).sync() {
+            Err(GetObjectError::NoSuchKey(_)) => return Ok(None),
+            other => other.with_context(|_| format!("failed to download log {}", hash))?,
+        };
+
+        let encoding = match object.content_encoding.as_ref().map(|e| e.as_str()) {
+            None => EncodingType::Plain,
+            Some(encoding) => encoding.parse()?,
+        };
+        let mut data = Vec::new();
+        if let Some(body) = object.body {
+            body.into_blocking_read().read_to_end(&mut data)?;
+        }
+
+        Ok(Some(EncodedLog::from_encoded(data, encoding)))
+    }
+
+    fn exists(&self, hash: &str) -> Fallible<bool> {
+        let req = HeadObjectRequest {
+            bucket: self.prefix.bucket.clone(),
+            key: self.key(hash),
+            ..Default::default()
+        };
+        match self.client.head_object(req).sync() {
+            // HEAD responses don't have a body, so missing objects are often reported as a
+            // plain 404 rather than as NoSuchKey
+            Err(HeadObjectError::NoSuchKey(_)) => Ok(false),
+            Err(HeadObjectError::Unknown(ref resp)) if resp.status.as_u16() == 404 => Ok(false),
+            other => {
+                other.with_context(|_| format!("failed to check log {}", hash))?;
+                Ok(true)
+            }
+        }
+    }
+
+    fn store(&self, hash: &str, log: &EncodedLog) -> Fallible<()> {
+        let req = PutObjectRequest {
+            body: Some(log.as_slice().to_vec().into()),
+            bucket: self.prefix.bucket.clone(),
+            key: self.key(hash),
+            content_type: Some(mime::TEXT_PLAIN_UTF_8.to_string()),
+            content_encoding: match log.get_encoding_type() {
+                EncodingType::Plain => None,
+                encoding => Some(encoding.to_str().into()),
+            },
+            ..Default::default()
+        };
+        self.client
+            .put_object(req)
+            .sync()
+            .with_context(|_| format!("failed to upload log {}", hash))?;
+        Ok(())
+    }
+
+    fn delete(&self, hash: &str) -> Fallible<()> {
+        let req = DeleteObjectRequest {
+            bucket: self.prefix.bucket.clone(),
+            key: self.key(hash),
+            ..Default::default()
+        };
+        self.client
+            .delete_object(req)
+            .sync()
+            .with_context(|_| format!("failed to delete log {}", hash))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DatabaseLogStore, FallbackLogStore, FileLogStore, LogStore, S3LogStore};
+    use crate::db::Database;
+    use crate::results::{EncodedLog, EncodingType};
+    use futures::{Future, Stream};
+    use hyper::service::service_fn;
+    use hyper::{Body, Method, Request, Response, Server, StatusCode};
+    use rusoto_core::request::HttpClient;
+    use rusoto_core::Region;
+    use rusoto_credential::StaticProvider;
+    use rusoto_s3::S3Client;
+    use std::collections::HashMap;
+    use std::net::SocketAddr;
+    use std::sync::{Arc, Mutex};
+    use tempfile::tempdir;
+
+    const NO_SUCH_KEY: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+        <Error><Code>NoSuchKey</Code><Message>The specified key does not exist.</Message></Error>";
+
+    /// Start an in-memory server implementing the parts of the S3 API used by the log store.
+    fn fake_s3() -> SocketAddr {
+        let objects = Arc::new(Mutex::new(
+            HashMap::<String, (Vec<u8>, Option<String>)>::new(),
+        ));
+
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(move || {
+            let objects = objects.clone();
+            service_fn(move |req: Request<Body>| {
+                let objects = objects.clone();
+                let method = req.method().clone();
+                let key = req.uri().path().to_string();
+                let encoding = req
+                    .headers()
+                    .get("content-encoding")
+                    .map(|e| e.to_str().unwrap().to_string());
+
+                req.into_body().concat2().map(move |body| {
+                    let mut objects = objects.lock().unwrap();
+                    let mut resp = Response::builder();
+                    match (method, objects.get(&key).cloned()) {
+                        (Method::PUT, _) => {
+                            objects.insert(key, (body.to_vec(), encoding));
+                            resp.body(Body::empty())
+                        }
+                        (Method::DELETE, _) => {
+                            objects.remove(&key);
+                            resp.status(StatusCode::NO_CONTENT).body(Body::empty())
+                        }
+                        (method, Some((data, encoding))) => {
+                            if let Some(encoding) = encoding {
+                                resp.header("content-encoding", encoding.as_str());
+                            }
+                            if method == Method::GET {
+                                resp.body(data.into())
+                            } else {
+                                resp.body(Body::empty())
+                            }
+                        }
+                        (_, None) => resp.status(StatusCode::NOT_FOUND).body(NO_SUCH_KEY.into()),
+                    }
+                    .unwrap()
+                })
+            })
+        });
+
+        let addr = server.local_addr();
+        std::thread::spawn(move || {
+            hyper::rt::run(server.map_err(|e| panic!("fake S3 server failed: {}", e)))
+        });
+        addr
+    }
+
+    fn check_store(store: &dyn LogStore) {
+        let plain = EncodedLog::Plain(b"plain log".to_vec());
+        let zstd = EncodedLog::from_plain_slice(b"zstd log", EncodingType::Zstd).unwrap();
+        let plain_hash = EncodedLog::hash_plain(b"plain log");
+        let zstd_hash = EncodedLog::hash_plain(b"zstd log");
+
+        assert!(!store.exists(&plain_hash).unwrap());
+        assert!(store.load(&plain_hash).unwrap().is_none());
+
+        store.store(&plain_hash, &plain).unwrap();
+        store.store(&zstd_hash, &zstd).unwrap();
+        assert!(store.exists(&plain_hash).unwrap());
+        assert_eq!(store.load(&plain_hash).unwrap(), Some(plain));
+        assert_eq!(store.load(&zstd_hash).unwrap(), Some(zstd));
+
+        store.delete(&plain_hash).unwrap();
+        assert!(!store.exists(&plain_hash).unwrap());
+        assert!(store.load(&plain_hash).unwrap().is_none());
+        assert!(store.exists(&zstd_hash).unwrap());
+
+        // Deleting a missing log is not an error
+        store.delete(&plain_hash).unwrap();
+    }
+
+    #[test]
+    fn test_database_log_store() {
+        let db = Database::temp().unwrap();
+        check_store(&DatabaseLogStore::new(db));
+    }
+
+    #[test]
+    fn test_file_log_store() {
+        let dir = tempdir().unwrap();
+        check_store(&FileLogStore::new(dir.path().into()));
+    }
+
+    #[test]
+    fn test_s3_log_store() {
+        let client = S3Client::new_with(
+            HttpClient::new().unwrap(),
+            StaticProvider::new_minimal("access-key".into(), "secret-key".into()),
+            Region::Custom {
+                name: "us-east-1".into(),
+                endpoint: format!("http://{}", fake_s3()),
+            },
+        );
+        let prefix = "s3://crater-logs/logs".parse().unwrap();
+        check_store(&S3LogStore::with_client(prefix, client));
+    }
+
+    #[test]
+    fn test_fallback_log_store() {
+        let db = Database::temp().unwrap();
+        let dir = tempdir().unwrap();
+        let store = FallbackLogStore::new(FileLogStore::new(dir.path().into()), db.clone());
+        check_store(&store);
+
+        // Logs stored in the database before switching to another store are still found
+        let log = EncodedLog::Plain(b"old log".to_vec());
+        let hash = EncodedLog::hash_plain(b"old log");
+        DatabaseLogStore::new(db).store(&hash, &log).unwrap();
+        assert!(store.exists(&hash).unwrap());
+        assert_eq!(store.load(&hash).unwrap(), Some(log));
+
+        store.delete(&hash).unwrap();
+        assert!(!store.exists(&hash).unwrap());
+    }
+}
@@ -0,0 +1,75 @@
+use crate::experiments::Experiment;
+use crate::prelude::*;
+use crate::results::{Environment, ToolchainVersions};
+use crate::utils;
+use rustwide::cmd::{Command, Runnable};
+use rustwide::Workspace;
+
+fn capture_output<R: Runnable>(
+    workspace: &Workspace,
+    binary: R,
+    args: &[&str],
+) -> Fallible<String> {
+    Ok(Command::new(workspace, binary)
+        .args(args)
+        .log_output(false)
+        .run_capture()?
+        .stdout_lines()
+        .join("\n"))
+}
+
+fn docker_image_digest(workspace: &Workspace, image: &str) -> Fallible<String> {
+    // Images built locally don't have a repository digest, so their ID is used instead
+    let digest = capture_output(
+        workspace,
+        "docker",
+        &[
+            "image",
+            "inspect",
+            "--format",
+            "{{if .RepoDigests}}{{index .RepoDigests 0}}{{else}}{{.Id}}{{end}}",
+            image,
+        ],
+    )?;
+    if digest.is_empty() {
+        bail!("docker didn't return the digest of {}", image);
+    }
+    Ok(digest)
+}
+
+/// Captures the environment the experiment is run in. The toolchains of the experiment must
+/// already be installed.
+pub(super) fn capture_environment(
+    ex: &Experiment,
+    workspace: &Workspace,
+    docker_image: &str,
+) -> Fallible<Environment> {
+    let toolchains = ex
+        .toolchains
+        .iter()
+        .map(|tc| {
+            Ok(ToolchainVersions {
+                toolchain: tc.to_string(),
+                rustc: capture_output(workspace, tc.source.rustc(), &["-Vv"])
+                    .with_context(|_| format!("failed to get the version of rustc {}", tc))?,
+                cargo: capture_output(workspace, tc.source.cargo(), &["-V"])
+                    .with_context(|_| format!("failed to get the version of cargo {}", tc))?,
+            })
+        })
+        .collect::<Fallible<Vec<_>>>()?;
+
+    // A missing digest only makes the environment less precise, so it doesn't stop the experiment
+    let docker_image = match docker_image_digest(workspace, docker_image) {
+        Ok(digest) => Some(digest),
+        Err(err) => {
+            utils::report_failure(&err);
+            None
+        }
+    };
+
+    Ok(Environment {
+        git_revision: crate::GIT_REVISION.map(String::from),
+        docker_image,
+        toolchains,
+    })
+}
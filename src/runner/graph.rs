@@ -263,7 +263,7 @@ pub(super) fn build_graph(ex: &Experiment, crates: &[Crate], config: &Config) ->
     let mut graph = TasksGraph::new();
 
     for krate in crates {
-        if !ex.ignore_blacklist && config.should_skip(krate) {
+        if !ex.ignore_blacklist && config.should_skip_in_mode(krate, ex.mode) {
             for tc in &ex.toolchains {
                 let id = graph.add_task(
                     Task {
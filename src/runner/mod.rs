@@ -1,3 +1,4 @@
+mod environment;
 mod graph;
 mod tasks;
 mod test;
@@ -55,6 +56,7 @@ pub fn run_ex<DB: WriteResults + Sync>(
     db: &DB,
     threads_count: usize,
     config: &Config,
+    docker_image: &str,
 ) -> Fallible<()> {
     if !rustwide::cmd::docker_running(workspace) {
         return Err(err_msg("docker is not running"));
@@ -83,6 +85,17 @@ pub fn run_ex<DB: WriteResults + Sync>(
         }
     }
 
+    info!("recording the environment...");
+    // The environment is only used to investigate the results, so failing to capture it doesn't
+    // stop the experiment
+    match environment::capture_environment(ex, workspace, docker_image) {
+        Ok(env) => db.record_environment(ex, &env)?,
+        Err(err) => {
+            warn!("failed to capture the environment of the experiment");
+            crate::utils::report_failure(&err);
+        }
+    }
+
     info!("running tasks in {} threads...", threads_count);
 
     let state = RunnerState::new();
@@ -2,7 +2,7 @@ use crate::config::Config;
 use crate::crates::{Crate, GitHubRepo};
 use crate::experiments::Experiment;
 use crate::prelude::*;
-use crate::results::{EncodingType, TestResult, WriteResults};
+use crate::results::{Diagnostic, EncodingType, TestResult, WriteResults};
 use crate::runner::test::detect_broken;
 use crate::runner::{test, RunnerState};
 use crate::toolchain::Toolchain;
@@ -22,6 +22,7 @@ pub(super) struct TaskCtx<'ctx, DB: WriteResults + 'ctx> {
     pub(super) krate: &'ctx Crate,
     pub(super) state: &'ctx RunnerState,
     pub(super) quiet: bool,
+    pub(super) diagnostics: Mutex<Vec<Diagnostic>>,
 }
 
 impl<'ctx, DB: WriteResults + 'ctx> TaskCtx<'ctx, DB> {
@@ -44,6 +45,7 @@ impl<'ctx, DB: WriteResults + 'ctx> TaskCtx<'ctx, DB> {
             krate,
             state,
             quiet,
+            diagnostics: Mutex::new(Vec::new()),
         }
     }
 }
@@ -1,6 +1,6 @@
 use crate::crates::Crate;
 use crate::prelude::*;
-use crate::results::DiagnosticCode;
+use crate::results::{self, Diagnostic, DiagnosticCode};
 use crate::results::{BrokenReason, EncodingType, FailureReason, TestResult, WriteResults};
 use crate::runner::tasks::TaskCtx;
 use crate::runner::OverrideResult;
@@ -13,16 +13,67 @@ use rustwide::{Build, PrepareError};
 use std::collections::{BTreeSet, HashSet};
 use std::convert::TryFrom;
 
+const NETWORK_ERRORS: &[&str] = &[
+    "failed to lookup address information",
+    "Temporary failure in name resolution",
+    "Network is unreachable",
+    "Could not resolve host",
+];
+
+/// Failure reasons detected from the output of a command, ordered by how much they explain the
+/// failure.
+const OUTPUT_FAILURE_REASONS: &[FailureReason] = &[FailureReason::DiskFull, FailureReason::Network];
+
+/// Network errors are only looked for in the output of build commands, as tests can legitimately
+/// print them.
+fn detect_failure_from_output(line: &str, detect_network: bool) -> Option<FailureReason> {
+    if line.contains("No space left on device") {
+        Some(FailureReason::DiskFull)
+    } else if detect_network && NETWORK_ERRORS.iter().any(|err| line.contains(err)) {
+        Some(FailureReason::Network)
+    } else {
+        None
+    }
+}
+
+fn detect_failure_from_message(message: &str) -> Option<FailureReason> {
+    if message.starts_with("linking with `") || message.starts_with("linker `") {
+        Some(FailureReason::Linker)
+    } else if message.contains("panicked")
+        && (message.starts_with("proc-macro derive")
+            || message.starts_with("proc macro")
+            || message.starts_with("custom attribute"))
+    {
+        Some(FailureReason::ProcMacro)
+    } else {
+        None
+    }
+}
+
+/// Returns why the sandbox killed the command, if it did.
+fn sandbox_failure(err: &Error) -> Option<FailureReason> {
+    err.iter_chain()
+        .find_map(|cause| match cause.downcast_ctx() {
+            Some(&CommandError::SandboxOOM) => Some(FailureReason::OOM),
+            Some(&CommandError::NoOutputFor(_)) | Some(&CommandError::Timeout(_)) => {
+                Some(FailureReason::Timeout)
+            }
+            _ => None,
+        })
+}
+
 fn failure_reason(err: &Error) -> FailureReason {
+    // The sandbox killing the command explains the failure better than what was detected from its
+    // output, which only covers the part of the build that ran
+    if let Some(reason) = sandbox_failure(err) {
+        return reason;
+    }
+
     for cause in err.iter_chain() {
-        if let Some(&CommandError::SandboxOOM) = cause.downcast_ctx() {
-            return FailureReason::OOM;
-        } else if let Some(&CommandError::NoOutputFor(_)) = cause.downcast_ctx() {
-            return FailureReason::Timeout;
-        } else if let Some(&CommandError::Timeout(_)) = cause.downcast_ctx() {
-            return FailureReason::Timeout;
-        } else if let Some(reason) = cause.downcast_ctx::<FailureReason>() {
+        if let Some(reason) = cause.downcast_ctx::<FailureReason>() {
             return reason.clone();
+        } else if let Some(reason) = detect_failure_from_output(&cause.to_string(), false) {
+            return reason;
         }
     }
 
@@ -64,6 +115,55 @@ pub(super) fn detect_broken<T>(res: Result<T, Error>) -> Result<T, Error> {
     }
 }
 
+/// A build script that was compiled but never executed failed to run: it's blamed on the crate
+/// being tested if the script is its own, otherwise on the dependency it belongs to.
+fn failed_build_script(
+    compiled: &HashSet<PackageId>,
+    executed: &HashSet<PackageId>,
+    local_packages_id: &HashSet<PackageId>,
+) -> Option<FailureReason> {
+    let pkgid = compiled.iter().find(|pkgid| !executed.contains(pkgid))?;
+    if local_packages_id.contains(pkgid) {
+        Some(FailureReason::BuildScript)
+    } else if let Ok(krate) = Crate::try_from(pkgid) {
+        let mut deps = BTreeSet::new();
+        deps.insert(krate);
+        Some(FailureReason::DependsOn(deps))
+    } else {
+        None
+    }
+}
+
+fn is_error_code(code: &str) -> bool {
+    code.len() == 5 && code.starts_with('E') && code[1..].chars().all(|c| c.is_ascii_digit())
+}
+
+fn to_diagnostic(message: &cargo_metadata::diagnostic::Diagnostic) -> Option<Diagnostic> {
+    let level = match message.level {
+        DiagnosticLevel::Ice => results::DiagnosticLevel::Ice,
+        DiagnosticLevel::Error => results::DiagnosticLevel::Error,
+        DiagnosticLevel::Warning => results::DiagnosticLevel::Warning,
+        _ => return None,
+    };
+
+    // Lints put their name in place of the error code
+    let (code, lint) = match message.code {
+        Some(ref code) if is_error_code(&code.code) => (Some(code.code.clone()), None),
+        Some(ref code) => (None, Some(code.code.clone())),
+        None => (None, None),
+    };
+
+    let span = message.spans.iter().find(|span| span.is_primary);
+    Some(Diagnostic {
+        level,
+        code,
+        lint,
+        message: message.message.clone(),
+        file: span.map(|span| span.file_name.clone()),
+        line: span.map(|span| span.line_start as u32),
+    })
+}
+
 fn get_local_packages(build_env: &Build) -> Fallible<HashSet<PackageId>> {
     Ok(build_env
         .cargo()
@@ -99,10 +199,16 @@ fn run_cargo<DB: WriteResults>(
     let mut did_ice = false;
     let mut error_codes = BTreeSet::new();
     let mut deps = BTreeSet::new();
+    let mut diagnostics = Vec::new();
+    let mut message_reason = None;
+    let mut output_reasons = HashSet::new();
+    let mut compiled_build_scripts = HashSet::new();
+    let mut executed_build_scripts = HashSet::new();
 
     let mut detect_error = |line: &str, actions: &mut ProcessLinesActions| {
         // Avoid trying to deserialize non JSON output
-        if !line.starts_with('{') {
+        if !check_errors || !line.starts_with('{') {
+            output_reasons.extend(detect_failure_from_output(line, check_errors));
             return;
         }
 
@@ -114,11 +220,21 @@ fn run_cargo<DB: WriteResults>(
         match message {
             Message::CompilerMessage(compiler_message) => {
                 let inner_message = compiler_message.message;
+                if local_packages_id.contains(&compiler_message.package_id) {
+                    diagnostics.extend(to_diagnostic(&inner_message));
+                }
+                // The compiler reports failing to write its outputs as an error message
+                output_reasons.extend(detect_failure_from_output(&inner_message.message, false));
+
                 match (inner_message.level, &compiler_message.package_id) {
                     // the only local crate in a well defined job is the crate currently being tested
                     (DiagnosticLevel::Error, pkgid) if local_packages_id.contains(pkgid) => {
                         if let Some(code) = inner_message.code {
                             error_codes.insert(DiagnosticCode::from(code.code));
+                        } else if let Some(reason) =
+                            detect_failure_from_message(&inner_message.message)
+                        {
+                            message_reason = Some(reason);
                         }
                     }
                     (DiagnosticLevel::Ice, pkgid) if local_packages_id.contains(pkgid) => {
@@ -141,6 +257,21 @@ fn run_cargo<DB: WriteResults>(
 
                 actions.replace_with_lines(inner_message.rendered.unwrap_or_default().split('\n'));
             }
+            Message::CompilerArtifact(artifact) => {
+                if artifact
+                    .target
+                    .kind
+                    .iter()
+                    .any(|kind| kind == "custom-build")
+                {
+                    compiled_build_scripts.insert(artifact.package_id);
+                }
+                actions.remove_line();
+            }
+            Message::BuildScriptExecuted(script) => {
+                executed_build_scripts.insert(script.package_id);
+                actions.remove_line();
+            }
             _ => actions.remove_line(),
         }
     };
@@ -152,23 +283,55 @@ fn run_cargo<DB: WriteResults>(
         .env("RUST_BACKTRACE", "full")
         .env(rustflags_env, rustflags);
 
-    if check_errors {
-        command = command.process_lines(&mut detect_error);
-    }
+    command = command.process_lines(&mut detect_error);
 
+    command = command.timeout(Some(ctx.config.total_timeout(ctx.krate)));
     if ctx.quiet {
         command = command.no_output_timeout(None);
+    } else {
+        command = command.no_output_timeout(Some(ctx.config.no_output_timeout(ctx.krate)));
+    }
+
+    let res = command.run();
+    {
+        // `cargo test --no-run` replays the diagnostics already emitted by `cargo build`
+        let mut recorded = ctx.diagnostics.lock().unwrap();
+        let seen = recorded.iter().cloned().collect::<HashSet<_>>();
+        recorded.extend(diagnostics.into_iter().filter(|diag| !seen.contains(diag)));
     }
 
-    match command.run() {
+    match res {
         Ok(()) => Ok(()),
         Err(e) => {
+            let output_reason = OUTPUT_FAILURE_REASONS
+                .iter()
+                .find(|reason| output_reasons.contains(*reason));
+
+            // Build scripts are only checked when no other error was found, as those could stop
+            // the build before they run. Builds killed by the sandbox also stop before running
+            // them, and the reason is found by `failure_reason` instead.
+            let build_script_reason = if sandbox_failure(&e).is_some() {
+                None
+            } else {
+                failed_build_script(
+                    &compiled_build_scripts,
+                    &executed_build_scripts,
+                    local_packages_id,
+                )
+            };
+
             if did_ice {
                 Err(e.context(FailureReason::ICE).into())
             } else if !deps.is_empty() {
                 Err(e.context(FailureReason::DependsOn(deps)).into())
+            } else if let Some(reason) = message_reason {
+                Err(e.context(reason).into())
+            } else if let Some(reason) = output_reason {
+                Err(e.context(reason.clone()).into())
             } else if !error_codes.is_empty() {
                 Err(e.context(FailureReason::CompilerError(error_codes)).into())
+            } else if let Some(reason) = build_script_reason {
+                Err(e.context(reason).into())
             } else {
                 Err(e.into())
             }
@@ -209,7 +372,7 @@ pub(super) fn run_test<DB: WriteResults>(
                     ctx.experiment.name
                 );
                 let sandbox = SandboxBuilder::new()
-                    .memory_limit(Some(ctx.config.sandbox.memory_limit.to_bytes()))
+                    .memory_limit(Some(ctx.config.memory_limit(ctx.krate).to_bytes()))
                     .enable_networking(false);
 
                 let krate = &ctx.krate.to_rustwide();
@@ -220,10 +383,18 @@ pub(super) fn run_test<DB: WriteResults>(
                     build = build.patch_with_git(&patch.name, &patch.repo, &patch.branch);
                 }
 
-                detect_broken(build.run(|build| {
+                let res = detect_broken(build.run(|build| {
                     let local_packages_id = get_local_packages(build)?;
                     test_fn(ctx, build, &local_packages_id)
-                }))
+                }))?;
+
+                ctx.db.record_diagnostics(
+                    ctx.experiment,
+                    ctx.toolchain,
+                    ctx.krate,
+                    std::mem::take(&mut *ctx.diagnostics.lock().unwrap()),
+                )?;
+                Ok(res)
             },
         )?;
     }
@@ -371,3 +542,111 @@ pub(super) fn test_rustdoc<DB: WriteResults>(
         Ok(TestResult::TestPass)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        detect_failure_from_message, detect_failure_from_output, failed_build_script,
+        failure_reason,
+    };
+    use crate::crates::{Crate, RegistryCrate};
+    use crate::results::FailureReason;
+    use cargo_metadata::PackageId;
+    use failure::Error;
+    use rustwide::cmd::CommandError;
+    use std::collections::{BTreeSet, HashSet};
+
+    #[test]
+    fn test_detect_failure_from_output() {
+        assert_eq!(
+            detect_failure_from_output("error: No space left on device (os error 28)", false),
+            Some(FailureReason::DiskFull)
+        );
+        let network = "error: Could not resolve host: github.com";
+        assert_eq!(
+            detect_failure_from_output(network, true),
+            Some(FailureReason::Network)
+        );
+        // Tests can print network errors on purpose
+        assert_eq!(detect_failure_from_output(network, false), None);
+        assert_eq!(
+            detect_failure_from_output("Compiling foo v0.1.0", true),
+            None
+        );
+    }
+
+    #[test]
+    fn test_detect_failure_from_message() {
+        assert_eq!(
+            detect_failure_from_message("linking with `cc` failed: exit code: 1"),
+            Some(FailureReason::Linker)
+        );
+        assert_eq!(
+            detect_failure_from_message("linker `cc` not found"),
+            Some(FailureReason::Linker)
+        );
+        assert_eq!(
+            detect_failure_from_message("proc-macro derive panicked"),
+            Some(FailureReason::ProcMacro)
+        );
+        assert_eq!(
+            detect_failure_from_message("custom attribute panicked"),
+            Some(FailureReason::ProcMacro)
+        );
+        assert_eq!(detect_failure_from_message("proc macro not expanded"), None);
+        assert_eq!(detect_failure_from_message("mismatched types"), None);
+    }
+
+    #[test]
+    fn test_failed_build_script() {
+        let local = PackageId {
+            repr: "local 0.1.0 (path+file:///opt/rustwide/workdir)".into(),
+        };
+        let dep = PackageId {
+            repr: "dep 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)".into(),
+        };
+        let set = |ids: &[&PackageId]| ids.iter().cloned().cloned().collect::<HashSet<_>>();
+        let locals = set(&[&local]);
+
+        assert_eq!(
+            failed_build_script(&set(&[&local, &dep]), &set(&[&dep]), &locals),
+            Some(FailureReason::BuildScript)
+        );
+        assert_eq!(
+            failed_build_script(&set(&[&local, &dep]), &set(&[&local]), &locals),
+            Some(FailureReason::DependsOn(btreeset![Crate::Registry(
+                RegistryCrate {
+                    name: "dep".into(),
+                    version: "1.0.0".into(),
+                }
+            )]))
+        );
+        assert_eq!(
+            failed_build_script(&set(&[&local, &dep]), &set(&[&local, &dep]), &locals),
+            None
+        );
+    }
+
+    #[test]
+    fn test_failure_reason() {
+        let err: Error = Error::from(CommandError::Timeout(60))
+            .context(FailureReason::BuildScript)
+            .into();
+        assert_eq!(failure_reason(&err), FailureReason::Timeout);
+
+        let err: Error = Error::from(CommandError::SandboxOOM)
+            .context(FailureReason::DependsOn(btreeset![Crate::Registry(
+                RegistryCrate {
+                    name: "dep".into(),
+                    version: "1.0.0".into(),
+                }
+            )]))
+            .into();
+        assert_eq!(failure_reason(&err), FailureReason::OOM);
+
+        let err: Error = failure::err_msg("build failed")
+            .context(FailureReason::Linker)
+            .into();
+        assert_eq!(failure_reason(&err), FailureReason::Linker);
+    }
+}
@@ -1,5 +1,10 @@
 use crate::config::Config;
+use crate::crates::Crate;
 use crate::prelude::*;
+use crate::report;
+use crate::results::{CrateHistoryEntry, TestResult};
+use crate::toolchain::Toolchain;
+use chrono::{DateTime, Utc};
 use http::header::{HeaderValue, CONTENT_TYPE};
 use http::Response;
 use http::StatusCode;
@@ -16,6 +21,39 @@ pub struct AgentConfig {
     pub crater_config: Config,
 }
 
+/// The result of a crate with one of the toolchains of a past experiment.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CrateHistoryResult {
+    pub experiment: String,
+    pub date: DateTime<Utc>,
+    #[serde(rename = "crate")]
+    pub krate: Crate,
+    pub toolchain: Toolchain,
+    pub result: TestResult,
+    /// The log in the report of the experiment, if it was generated.
+    pub log_url: Option<String>,
+}
+
+impl From<CrateHistoryEntry> for CrateHistoryResult {
+    fn from(entry: CrateHistoryEntry) -> Self {
+        CrateHistoryResult {
+            log_url: entry.report_url.as_ref().map(|url| {
+                format!(
+                    "{}{}/log.txt",
+                    report::report_base_url(url),
+                    report::crate_log_path(&entry.toolchain, &entry.krate)
+                )
+            }),
+            experiment: entry.experiment,
+            date: entry.date,
+            krate: entry.krate,
+            toolchain: entry.toolchain,
+            result: entry.result,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(tag = "status", rename_all = "kebab-case")]
 pub enum ApiResponse<T> {
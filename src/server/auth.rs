@@ -7,8 +7,12 @@ use regex::Regex;
 use rust_team_data::v1 as team_data;
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 use warp::{self, Filter, Rejection};
 
+/// How long the GitHub token of a web UI user is trusted before it's checked again.
+const VALIDATED_TOKEN_TTL: Duration = Duration::from_secs(5 * 60);
+
 lazy_static! {
     static ref GIT_REVISION_RE: Regex =
         Regex::new(r"^crater(-agent)?/(?P<sha>[a-f0-9]{7,40})( \(.*\))?$").unwrap();
@@ -39,6 +43,22 @@ fn parse_token(authorization: &str) -> Option<&str> {
     None
 }
 
+/// Parse the credentials of HTTP basic authentication, returning the username and password.
+fn parse_basic_auth(authorization: &str) -> Option<(String, String)> {
+    let mut segments = authorization.splitn(2, ' ');
+    if segments.next()? != "Basic" {
+        return None;
+    }
+
+    let decoded = base64::decode(segments.next()?.trim()).ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    let middle = decoded.find(':')?;
+    Some((
+        decoded[..middle].to_string(),
+        decoded[middle + 1..].to_string(),
+    ))
+}
+
 fn git_revision(user_agent: &str) -> Option<String> {
     GIT_REVISION_RE
         .captures(user_agent)
@@ -89,6 +109,68 @@ pub fn auth_filter(
     })
 }
 
+/// Authenticate a user of the web UI, who provides their GitHub username and a personal access
+/// token through HTTP basic authentication. Returns the username if they're allowed to use the
+/// bot, and `None` if the credentials are missing or wrong.
+pub fn check_acl_user(data: &Data, headers: &HeaderMap) -> Fallible<Option<String>> {
+    let (username, token) = match headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_basic_auth)
+    {
+        Some(credentials) => credentials,
+        None => return Ok(None),
+    };
+
+    if let Some(login) = data.validated_tokens.get(&username, &token) {
+        return Ok(Some(login));
+    }
+
+    let user = match GitHubApi::with_token(&token).current_user() {
+        Ok(user) => user,
+        Err(err) => {
+            warn!("failed to authenticate {} on GitHub: {}", username, err);
+            return Ok(None);
+        }
+    };
+    if !user.login.eq_ignore_ascii_case(&username) {
+        return Ok(None);
+    }
+
+    if data.acl.allowed(&user.login, user.id)? {
+        data.validated_tokens
+            .insert(username, token, user.login.clone());
+        Ok(Some(user.login))
+    } else {
+        Ok(None)
+    }
+}
+
+/// The credentials of the web UI users allowed recently, to avoid asking GitHub about the token
+/// on every request.
+#[derive(Clone, Default)]
+pub struct ValidatedTokens {
+    cached: Arc<RwLock<HashMap<(String, String), (String, Instant)>>>,
+}
+
+impl ValidatedTokens {
+    fn get(&self, username: &str, token: &str) -> Option<String> {
+        let cached = self.cached.read().unwrap();
+        match cached.get(&(username.to_string(), token.to_string())) {
+            Some((login, validated_at)) if validated_at.elapsed() < VALIDATED_TOKEN_TTL => {
+                Some(login.clone())
+            }
+            _ => None,
+        }
+    }
+
+    fn insert(&self, username: String, token: String, login: String) {
+        let mut cached = self.cached.write().unwrap();
+        cached.retain(|_, (_, validated_at)| validated_at.elapsed() < VALIDATED_TOKEN_TTL);
+        cached.insert((username, token), (login, Instant::now()));
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ACL {
     cached_usernames: Arc<RwLock<HashSet<String>>>,
@@ -189,7 +271,7 @@ impl ACL {
 
 #[cfg(test)]
 mod tests {
-    use super::{git_revision, parse_token};
+    use super::{git_revision, parse_basic_auth, parse_token, ValidatedTokens};
 
     #[test]
     fn test_parse_token() {
@@ -200,6 +282,35 @@ mod tests {
         assert_eq!(parse_token("CraterToken foo bar"), None);
     }
 
+    #[test]
+    fn test_validated_tokens() {
+        let tokens = ValidatedTokens::default();
+        assert_eq!(tokens.get("octocat", "token"), None);
+
+        tokens.insert("octocat".into(), "token".into(), "Octocat".into());
+        assert_eq!(tokens.get("octocat", "token"), Some("Octocat".into()));
+        assert_eq!(tokens.get("octocat", "other-token"), None);
+        assert_eq!(tokens.get("someone-else", "token"), None);
+    }
+
+    #[test]
+    fn test_parse_basic_auth() {
+        let encoded = |credentials: &str| format!("Basic {}", base64::encode(credentials));
+
+        assert_eq!(
+            parse_basic_auth(&encoded("foo:bar")),
+            Some(("foo".into(), "bar".into()))
+        );
+        assert_eq!(
+            parse_basic_auth(&encoded("foo:bar:baz")),
+            Some(("foo".into(), "bar:baz".into()))
+        );
+        assert_eq!(parse_basic_auth(&encoded("foo")), None);
+        assert_eq!(parse_basic_auth("Basic"), None);
+        assert_eq!(parse_basic_auth("Basic not-base64!"), None);
+        assert_eq!(parse_basic_auth("CraterToken foo"), None);
+    }
+
     #[test]
     fn test_git_revision() {
         for sha in &["0000000", "0000000000000000000000000000000000000000"] {
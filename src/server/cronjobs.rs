@@ -6,6 +6,7 @@ use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
+const HOUR: Duration = Duration::from_secs(60 * 60);
 const DAY: Duration = Duration::from_secs(60 * 60 * 24);
 struct JobDescription {
     name: &'static str,
@@ -13,11 +14,18 @@ struct JobDescription {
     exec: fn(Arc<Data>) -> Fallible<()>,
 }
 
-static JOBS: &[JobDescription] = &[JobDescription {
-    name: "crates lists update",
-    interval: DAY,
-    exec: update_crates as fn(Arc<Data>) -> Fallible<()>,
-}];
+static JOBS: &[JobDescription] = &[
+    JobDescription {
+        name: "crates lists update",
+        interval: DAY,
+        exec: update_crates as fn(Arc<Data>) -> Fallible<()>,
+    },
+    JobDescription {
+        name: "crate durations update",
+        interval: HOUR,
+        exec: update_crate_durations as fn(Arc<Data>) -> Fallible<()>,
+    },
+];
 
 pub fn spawn(data: Data) {
     let data = Arc::new(data);
@@ -51,3 +59,7 @@ fn update_crates(data: Arc<Data>) -> Fallible<()> {
     }
     .apply(&ctx)
 }
+
+fn update_crate_durations(data: Arc<Data>) -> Fallible<()> {
+    data.crate_durations.refresh(&data.db)
+}
@@ -0,0 +1,267 @@
+use crate::db::{Database, QueryUtils};
+use crate::experiments::{Experiment, Status};
+use crate::prelude::*;
+use chrono::{Duration, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// How far back the completed jobs are looked at to measure the throughput of the agents.
+const THROUGHPUT_WINDOW_MINUTES: i64 = 60;
+
+/// The remaining work of an unfinished experiment, in seconds of job wall time.
+struct QueueEntry {
+    name: String,
+    running: bool,
+    remaining_work: f64,
+    /// How many seconds of job wall time are completed every second for this experiment, which
+    /// is the number of jobs the assigned agents are running in parallel.
+    throughput: f64,
+}
+
+struct Durations {
+    per_crate: HashMap<String, f64>,
+    average: f64,
+}
+
+/// The historical wall time of the jobs. Computing it goes through all the results, so it's
+/// cached and refreshed periodically by a cron job instead of on every estimate.
+#[derive(Clone, Default)]
+pub struct CrateDurations {
+    cached: Arc<RwLock<Option<Arc<Durations>>>>,
+}
+
+impl CrateDurations {
+    pub fn refresh(&self, db: &Database) -> Fallible<()> {
+        let durations = match average_duration(db)? {
+            Some(average) => Some(Arc::new(Durations {
+                per_crate: crate_durations(db)?,
+                average,
+            })),
+            None => None,
+        };
+        *self.cached.write().unwrap() = durations;
+        Ok(())
+    }
+
+    fn get(&self) -> Option<Arc<Durations>> {
+        self.cached.read().unwrap().clone()
+    }
+}
+
+/// Estimates how long each running or queued experiment will take to complete.
+///
+/// The remaining work of each experiment is estimated from the historical wall time of its
+/// crates, and is divided by the current throughput of the agents working on it. Queued
+/// experiments (and running ones that didn't complete any job recently) also wait for the
+/// experiments before them in the queue, processed at the throughput of all the agents.
+pub fn estimate_completion(
+    db: &Database,
+    durations: &CrateDurations,
+) -> Fallible<HashMap<String, Duration>> {
+    let durations = match durations.get() {
+        Some(durations) => durations,
+        // Nothing was timed yet, so there is no way to estimate anything
+        None => return Ok(HashMap::new()),
+    };
+    let throughput = recent_throughput(db)?;
+
+    let mut queue = Vec::new();
+    for ex in Experiment::unfinished(db)? {
+        let running = match ex.status {
+            Status::Running => true,
+            Status::Queued => false,
+            _ => continue,
+        };
+
+        let mut remaining_work = 0.0;
+        for (krate, remaining_jobs) in remaining_jobs(db, &ex)? {
+            remaining_work += durations
+                .per_crate
+                .get(&krate)
+                .cloned()
+                .unwrap_or(durations.average)
+                * remaining_jobs;
+        }
+
+        queue.push(QueueEntry {
+            throughput: throughput.get(&ex.name).cloned().unwrap_or(0.0),
+            name: ex.name,
+            running,
+            remaining_work,
+        });
+    }
+
+    // Running experiments are processed before the queued ones
+    queue.sort_by_key(|entry| !entry.running);
+    Ok(estimate_queue(&queue))
+}
+
+fn estimate_queue(queue: &[QueueEntry]) -> HashMap<String, Duration> {
+    let total_throughput: f64 = queue.iter().map(|entry| entry.throughput).sum();
+    let to_duration = |seconds: f64| Duration::seconds(seconds.ceil() as i64);
+
+    let mut estimates = HashMap::new();
+    let mut work_ahead = 0.0;
+    for entry in queue {
+        work_ahead += entry.remaining_work;
+
+        if entry.running && entry.throughput > 0.0 {
+            estimates.insert(
+                entry.name.clone(),
+                to_duration(entry.remaining_work / entry.throughput),
+            );
+        } else if total_throughput > 0.0 {
+            estimates.insert(
+                entry.name.clone(),
+                to_duration(work_ahead / total_throughput),
+            );
+        }
+    }
+    estimates
+}
+
+/// The average wall time of a single job of each crate, across all the experiments.
+fn crate_durations(db: &Database) -> Fallible<HashMap<String, f64>> {
+    Ok(db
+        .query(
+            "SELECT crate, AVG((julianday(finished_at) - julianday(started_at)) * 86400.0) \
+             FROM results WHERE started_at IS NOT NULL AND finished_at IS NOT NULL \
+             GROUP BY crate;",
+            &[],
+            |r| (r.get::<_, String>(0), r.get::<_, f64>(1)),
+        )?
+        .into_iter()
+        .collect())
+}
+
+/// The average wall time of a single job, used for the crates that were never timed.
+fn average_duration(db: &Database) -> Fallible<Option<f64>> {
+    Ok(db
+        .get_row(
+            "SELECT AVG((julianday(finished_at) - julianday(started_at)) * 86400.0) \
+             FROM results WHERE started_at IS NOT NULL AND finished_at IS NOT NULL;",
+            &[] as &[u32],
+            |r| r.get::<_, Option<f64>>(0),
+        )?
+        .and_then(|average| average))
+}
+
+/// The number of jobs every crate of the experiment still has to run.
+fn remaining_jobs(db: &Database, ex: &Experiment) -> Fallible<Vec<(String, f64)>> {
+    let toolchains = ex.toolchains.len() as f64;
+    Ok(db
+        .query(
+            "SELECT experiment_crates.crate, COUNT(results.crate) FROM experiment_crates \
+             LEFT JOIN results ON results.experiment = experiment_crates.experiment \
+             AND results.crate = experiment_crates.crate \
+             WHERE experiment_crates.experiment = ?1 AND experiment_crates.skipped = 0 \
+             GROUP BY experiment_crates.crate;",
+            &[&ex.name],
+            |r| (r.get::<_, String>(0), r.get::<_, i64>(1)),
+        )?
+        .into_iter()
+        .map(|(krate, completed)| (krate, (toolchains - completed as f64).max(0.0)))
+        .filter(|&(_, remaining)| remaining > 0.0)
+        .collect())
+}
+
+/// The throughput of the agents working on each experiment, measured on the recently
+/// completed jobs.
+fn recent_throughput(db: &Database) -> Fallible<HashMap<String, f64>> {
+    let since = Utc::now() - Duration::minutes(THROUGHPUT_WINDOW_MINUTES);
+    let window = (THROUGHPUT_WINDOW_MINUTES * 60) as f64;
+    Ok(db
+        .query(
+            "SELECT experiment, SUM((julianday(finished_at) - julianday(started_at)) * 86400.0) \
+             FROM results WHERE started_at IS NOT NULL AND finished_at >= ?1 \
+             GROUP BY experiment;",
+            &[&since],
+            |r| (r.get::<_, String>(0), r.get::<_, f64>(1)),
+        )?
+        .into_iter()
+        .map(|(ex, busy)| (ex, busy / window))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{estimate_completion, estimate_queue, CrateDurations, QueueEntry};
+    use crate::actions::{Action, ActionsCtx, CreateExperiment};
+    use crate::config::Config;
+    use crate::db::{Database, QueryUtils};
+    use crate::experiments::Experiment;
+    use chrono::{Duration, Utc};
+
+    fn entry(name: &str, running: bool, remaining_work: f64, throughput: f64) -> QueueEntry {
+        QueueEntry {
+            name: name.into(),
+            running,
+            remaining_work,
+            throughput,
+        }
+    }
+
+    #[test]
+    fn test_estimate_queue() {
+        let estimates = estimate_queue(&[
+            entry("running", true, 1000.0, 10.0),
+            entry("stalled", true, 500.0, 0.0),
+            entry("queued", false, 2000.0, 0.0),
+        ]);
+
+        // Running experiments only depend on the agents working on them
+        assert_eq!(estimates["running"], Duration::seconds(100));
+        // The other ones wait for the work ahead of them in the queue
+        assert_eq!(estimates["stalled"], Duration::seconds(150));
+        assert_eq!(estimates["queued"], Duration::seconds(350));
+
+        // Without any throughput there is nothing to base the estimates on
+        let estimates = estimate_queue(&[entry("queued", false, 2000.0, 0.0)]);
+        assert!(estimates.is_empty());
+    }
+
+    #[test]
+    fn test_estimate_completion() {
+        let db = Database::temp().unwrap();
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+        CreateExperiment::dummy("dummy").apply(&ctx).unwrap();
+        let ex = Experiment::get(&db, "dummy").unwrap().unwrap();
+
+        // Nothing was timed yet
+        let durations = CrateDurations::default();
+        durations.refresh(&db).unwrap();
+        assert!(durations.get().is_none());
+        assert!(estimate_completion(&db, &durations).unwrap().is_empty());
+
+        let krate = ex.get_crates(&db).unwrap()[0].id();
+        let started_at = Utc::now() - Duration::minutes(5);
+        for (toolchain, seconds) in ex.toolchains.iter().zip(&[10, 30]) {
+            db.execute(
+                "INSERT INTO results (experiment, crate, toolchain, result, log_hash, \
+                 started_at, finished_at) VALUES (?1, ?2, ?3, 'test-pass', 'hash', ?4, ?5);",
+                &[
+                    &ex.name,
+                    &krate,
+                    &toolchain.to_string(),
+                    &started_at,
+                    &(started_at + Duration::seconds(*seconds)),
+                ],
+            )
+            .unwrap();
+        }
+
+        // The cache is only updated when it's refreshed
+        assert!(durations.get().is_none());
+        durations.refresh(&db).unwrap();
+        let cached = durations.get().unwrap();
+        assert!((cached.per_crate[&krate] - 20.0).abs() < 0.1);
+        assert!((cached.average - 20.0).abs() < 0.1);
+
+        // The agents completed jobs of the experiment recently, so its completion is estimated
+        let estimates = estimate_completion(&db, &durations).unwrap();
+        assert!(estimates["dummy"] > Duration::zero());
+    }
+}
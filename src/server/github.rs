@@ -38,6 +38,24 @@ impl GitHubApi {
         }
     }
 
+    /// Create a client authenticated as the owner of the token, instead of as the bot.
+    pub fn with_token(token: &str) -> Self {
+        GitHubApi {
+            token: token.to_string(),
+        }
+    }
+
+    pub fn current_user(&self) -> Fallible<User> {
+        let mut response = self.build_request(Method::GET, "user").send()?;
+
+        if response.status() == StatusCode::OK {
+            Ok(response.json()?)
+        } else {
+            let error: Error = response.json()?;
+            Err(GitHubError::RequestFailed(response.status(), error.message).into())
+        }
+    }
+
     fn build_request(&self, method: Method, url: &str) -> RequestBuilder {
         let url = if !url.starts_with("https://") {
             format!("https://api.github.com/{}", url)
@@ -52,8 +70,7 @@ impl GitHubApi {
 
 impl GitHub for GitHubApi {
     fn username(&self) -> Fallible<String> {
-        let response: User = self.build_request(Method::GET, "user").send()?.json()?;
-        Ok(response.login)
+        Ok(self.current_user()?.login)
     }
 
     fn post_comment(&self, issue_url: &str, body: &str) -> Fallible<()> {
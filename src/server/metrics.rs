@@ -2,6 +2,7 @@ use crate::db::{Database, QueryUtils};
 use crate::experiments::{Assignee, Experiment};
 use crate::prelude::*;
 use crate::server::agents::Agent;
+use crate::server::eta::{estimate_completion, CrateDurations};
 use chrono::{DateTime, Utc};
 use prometheus::proto::{Metric, MetricFamily};
 use prometheus::{
@@ -12,12 +13,14 @@ use prometheus::{
 const JOBS_METRIC: &str = "crater_completed_jobs_total";
 const AGENT_WORK_METRIC: &str = "crater_agent_supposed_to_work";
 const LAST_CRATES_UPDATE_METRIC: &str = "crater_last_crates_update";
+const EXPERIMENT_ETA_METRIC: &str = "crater_experiment_eta_seconds";
 
 #[derive(Clone)]
 pub struct Metrics {
     crater_completed_jobs_total: IntCounterVec,
     crater_work_status: IntGaugeVec,
     crater_last_crates_update: IntGauge,
+    crater_experiment_eta_seconds: IntGaugeVec,
 }
 
 impl Metrics {
@@ -30,11 +33,18 @@ impl Metrics {
         let crates_update_opts =
             prometheus::opts!(LAST_CRATES_UPDATE_METRIC, "last update of crates lists");
         let crater_last_crates_update = prometheus::register_int_gauge!(crates_update_opts)?;
+        let eta_opts = prometheus::opts!(
+            EXPERIMENT_ETA_METRIC,
+            "estimated seconds until the experiment completes"
+        );
+        let crater_experiment_eta_seconds =
+            prometheus::register_int_gauge_vec!(eta_opts, &["experiment"])?;
 
         Ok(Metrics {
             crater_completed_jobs_total,
             crater_work_status,
             crater_last_crates_update,
+            crater_experiment_eta_seconds,
         })
     }
 
@@ -105,6 +115,22 @@ impl Metrics {
         }
     }
 
+    pub fn update_experiment_etas(
+        &self,
+        db: &Database,
+        durations: &CrateDurations,
+    ) -> Fallible<()> {
+        self.crater_experiment_eta_seconds.reset();
+
+        for (experiment, eta) in estimate_completion(db, durations)? {
+            self.crater_experiment_eta_seconds
+                .with_label_values(&[&experiment])
+                .set(eta.num_seconds());
+        }
+
+        Ok(())
+    }
+
     pub fn on_complete_experiment(&self, experiment: &str) -> Fallible<()> {
         self.remove_experiment_jobs(experiment)
     }
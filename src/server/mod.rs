@@ -2,6 +2,7 @@ pub mod agents;
 pub mod api_types;
 mod auth;
 mod cronjobs;
+mod eta;
 mod github;
 mod messages;
 mod metrics;
@@ -13,8 +14,10 @@ mod try_builds;
 use crate::config::Config;
 use crate::db::Database;
 use crate::prelude::*;
+use crate::results::{open_log_store, LogStore};
 use crate::server::agents::Agents;
-use crate::server::auth::ACL;
+use crate::server::auth::{ValidatedTokens, ACL};
+use crate::server::eta::CrateDurations;
 use crate::server::github::{GitHub, GitHubApi};
 use crate::server::tokens::Tokens;
 use http::{self, header::HeaderValue, Response};
@@ -45,8 +48,11 @@ pub struct Data {
     pub tokens: Tokens,
     pub agents: Agents,
     pub db: Database,
+    pub logs: Arc<dyn LogStore>,
     pub reports_worker: reports::ReportsWorker,
     pub acl: ACL,
+    pub validated_tokens: ValidatedTokens,
+    pub crate_durations: CrateDurations,
     pub metrics: Metrics,
 }
 
@@ -58,6 +64,7 @@ pub fn run(config: Config, bind: SocketAddr) -> Fallible<()> {
     let bot_username = github.username()?;
     let acl = ACL::new(&config, &github)?;
     let metrics = Metrics::new()?;
+    let logs = open_log_store(&config, &db)?;
 
     info!("bot username: {}", bot_username);
 
@@ -68,8 +75,11 @@ pub fn run(config: Config, bind: SocketAddr) -> Fallible<()> {
         tokens,
         agents,
         db,
+        logs,
         reports_worker: reports::ReportsWorker::new(),
         acl,
+        validated_tokens: ValidatedTokens::default(),
+        crate_durations: CrateDurations::default(),
         metrics,
     };
 
@@ -90,6 +100,8 @@ pub fn run(config: Config, bind: SocketAddr) -> Fallible<()> {
                 .unify()
                 .or(warp::path("metrics").and(routes::metrics::routes(data.clone())))
                 .unify()
+                .or(warp::path("api").and(routes::api::routes(data.clone())))
+                .unify()
                 .or(routes::ui::routes(data))
                 .unify(),
         )
@@ -1,30 +1,95 @@
+use crate::config::ReportStorageConfig;
+use crate::crates::Crate;
 use crate::experiments::{Experiment, Status};
 use crate::prelude::*;
-use crate::report::{self, Comparison, TestResults};
+use crate::report::{self, Comparison, ReportWriter, TestResults};
 use crate::results::DatabaseDB;
 use crate::server::messages::{Label, Message};
+use crate::server::tokens::ReportsBucket;
 use crate::server::Data;
 use crate::utils;
+use chrono::{Duration as ChronoDuration, Utc};
 use rusoto_core::request::HttpClient;
 use rusoto_s3::S3Client;
-use std::sync::{Arc, Mutex};
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::thread::{self, Thread};
 use std::time::Duration;
 
 // Automatically wake up the reports generator thread every 10 minutes to check for new jobs
 const AUTOMATIC_THREAD_WAKEUP: u64 = 600;
+// Generate a partial report of each running experiment every 6 hours
+const PARTIAL_REPORTS_INTERVAL: i64 = 6 * 3600;
+// Partial reports are published next to the final ones, in their own directory
+const PARTIAL_REPORTS_PREFIX: &str = "partial";
 
-fn generate_report(data: &Data, ex: &Experiment, results: &DatabaseDB) -> Fallible<TestResults> {
-    let client = S3Client::new_with(
-        HttpClient::new()?,
-        data.tokens.reports_bucket.to_aws_credentials(),
-        data.tokens.reports_bucket.region.to_region()?,
-    );
-    let dest = format!("s3://{}/{}", data.tokens.reports_bucket.bucket, &ex.name);
-    let writer = report::S3Writer::create(Box::new(client), dest.parse()?)?;
+fn reports_bucket(data: &Data) -> Fallible<&ReportsBucket> {
+    data.tokens.reports_bucket.as_ref().ok_or_else(|| {
+        err_msg("the reports are stored on S3, but `reports-bucket` is missing from tokens.toml")
+    })
+}
+
+fn gen_report<W: ReportWriter + Display>(
+    data: &Data,
+    ex: &Experiment,
+    crates: &[Crate],
+    writer: &W,
+    results: &DatabaseDB,
+    partial: bool,
+) -> Fallible<TestResults> {
+    if partial {
+        report::gen_partial(results, ex, crates, writer, &data.config)
+    } else {
+        let formats = report::ReportFormat::defaults();
+        report::gen(results, ex, crates, writer, &data.config, &formats, false)
+    }
+}
+
+/// Write a report in the storage configured in the `[report-storage]` section, under `dest`.
+fn write_report(
+    data: &Data,
+    ex: &Experiment,
+    crates: &[Crate],
+    dest: &str,
+    results: &DatabaseDB,
+    partial: bool,
+) -> Fallible<TestResults> {
+    match data.config.report_storage {
+        ReportStorageConfig::S3 => {
+            let bucket = reports_bucket(data)?;
+            let client = S3Client::new_with(
+                HttpClient::new()?,
+                bucket.to_aws_credentials(),
+                bucket.region.to_region()?,
+            );
+            let dest = format!("s3://{}/{}", bucket.bucket, dest);
+            let writer = report::S3Writer::create(Box::new(client), dest.parse()?)?;
+            gen_report(data, ex, crates, &writer, results, partial)
+        }
+        ReportStorageConfig::Filesystem { ref path, .. } => {
+            let writer = report::FileWriter::create_with_encoded_names(path.join(dest))?;
+            gen_report(data, ex, crates, &writer, results, partial)
+        }
+    }
+}
+
+fn report_url(data: &Data, dest: &str) -> Fallible<String> {
+    let base_url = match data.config.report_storage {
+        ReportStorageConfig::S3 => {
+            let bucket = reports_bucket(data)?;
+            bucket.public_url.replace("{bucket}", &bucket.bucket)
+        }
+        ReportStorageConfig::Filesystem { ref public_url, .. } => {
+            public_url.trim_end_matches('/').to_string()
+        }
+    };
+    Ok(format!("{}/{}/index.html", base_url, dest))
+}
 
+fn generate_report(data: &Data, ex: &Experiment, results: &DatabaseDB) -> Fallible<TestResults> {
     let crates = ex.get_crates(&data.db)?;
-    let res = report::gen(results, &ex, &crates, &writer, &data.config, false)?;
+    let res = write_report(data, ex, &crates, &ex.name, results, false)?;
 
     //remove metrics about completed experiments
     data.metrics.on_complete_experiment(&ex.name)?;
@@ -32,9 +97,129 @@ fn generate_report(data: &Data, ex: &Experiment, results: &DatabaseDB) -> Fallib
     Ok(res)
 }
 
+/// Generate a report of a running experiment, only including the crates already tested with both
+/// toolchains. The report templates mark it as incomplete as the experiment is still running.
+fn generate_partial_report(
+    data: &Data,
+    ex: &Experiment,
+    results: &DatabaseDB,
+) -> Fallible<(usize, TestResults)> {
+    let crates = results
+        .load_all_results(ex)?
+        .into_iter()
+        .filter(|(_, runs)| runs.iter().all(Option::is_some))
+        .map(|(krate, _)| krate)
+        .collect::<Vec<_>>();
+    let res = write_report(data, ex, &crates, &partial_report_dest(ex), results, true)?;
+
+    Ok((crates.len(), res))
+}
+
+fn partial_report_dest(ex: &Experiment) -> String {
+    format!("{}/{}", PARTIAL_REPORTS_PREFIX, ex.name)
+}
+
+fn maybe_generate_partial_report(
+    data: &Data,
+    ex: &Experiment,
+    results: &DatabaseDB,
+) -> Fallible<()> {
+    let now = Utc::now();
+    let requested = data.reports_worker.take_partial_request(&ex.name);
+    let last = ex
+        .last_partial_report(&data.db)?
+        .map(|(_, generated_at)| generated_at)
+        .or(ex.started_at)
+        .unwrap_or(now);
+    if !requested
+        && now.signed_duration_since(last) < ChronoDuration::seconds(PARTIAL_REPORTS_INTERVAL)
+    {
+        return Ok(());
+    }
+
+    info!("generating a partial report for experiment {}...", ex.name);
+    match generate_partial_report(data, ex, results) {
+        Err(err) => {
+            error!("failed to generate the partial report of {}", ex.name);
+            utils::report_failure(&err);
+
+            // Periodic reports are retried silently at the next interval
+            if let Some(github_issue) = ex.github_issue.as_ref().filter(|_| requested) {
+                Message::new()
+                    .line(
+                        "rotating_light",
+                        format!(
+                            "Generation of the partial report of **`{}`** failed: {}",
+                            ex.name, err
+                        ),
+                    )
+                    .send(&github_issue.api_url, data)?;
+            }
+        }
+        Ok((tested, res)) => {
+            let url = report_url(data, &partial_report_dest(ex))?;
+            ex.set_partial_report(&data.db, &url, Utc::now())?;
+            info!("partial report for the experiment {} generated!", ex.name);
+
+            if let Some(github_issue) = ex.github_issue.as_ref().filter(|_| requested) {
+                Message::new()
+                    .line(
+                        "bar_chart",
+                        format!(
+                            "Partial report of **`{}`**: {} regressed and {} fixed so far \
+                             ({} crates tested with both toolchains)",
+                            ex.name,
+                            res.info.get(&Comparison::Regressed).unwrap_or(&0),
+                            res.info.get(&Comparison::Fixed).unwrap_or(&0),
+                            tested,
+                        ),
+                    )
+                    .line("newspaper", format!("[Open the partial report]({}).", url))
+                    .note(
+                        "warning",
+                        "The experiment is still running: the report is incomplete.",
+                    )
+                    .send(&github_issue.api_url, data)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn generate_partial_reports(data: &Data, results: &DatabaseDB) -> Fallible<()> {
+    for ex in Experiment::unfinished(&data.db)? {
+        if ex.status != Status::Running {
+            continue;
+        }
+
+        // A failure with one experiment shouldn't prevent the partial reports of the others
+        if let Err(err) = maybe_generate_partial_report(data, &ex, results) {
+            utils::report_failure(&err);
+        }
+    }
+
+    Ok(())
+}
+
+/// Partial reports are generated in their own thread, so that they never delay the final reports.
+fn partial_reports_thread(data: &Data) -> Fallible<()> {
+    let timeout = Duration::from_secs(AUTOMATIC_THREAD_WAKEUP);
+    let results = DatabaseDB::with_log_store(&data.db, data.logs.clone());
+
+    loop {
+        if let Err(err) = generate_partial_reports(data, &results) {
+            utils::report_failure(&err);
+        }
+
+        // This will sleep AUTOMATIC_THREAD_WAKEUP seconds *or* until a wake is received
+        std::thread::park_timeout(timeout);
+    }
+}
+
 fn reports_thread(data: &Data) -> Fallible<()> {
     let timeout = Duration::from_secs(AUTOMATIC_THREAD_WAKEUP);
-    let results = DatabaseDB::new(&data.db);
+    let results = DatabaseDB::with_log_store(&data.db, data.logs.clone());
 
     loop {
         let mut ex = match Experiment::first_by_status(&data.db, Status::NeedsReport)? {
@@ -77,15 +262,12 @@ fn reports_thread(data: &Data) -> Fallible<()> {
                 continue;
             }
             Ok(res) => {
-                let base_url = data
-                    .tokens
-                    .reports_bucket
-                    .public_url
-                    .replace("{bucket}", &data.tokens.reports_bucket.bucket);
-                let report_url = format!("{}/{}/index.html", base_url, name);
+                let report_url = report_url(data, &name)?;
 
                 ex.set_status(&data.db, Status::Completed)?;
                 ex.set_report_url(&data.db, &report_url)?;
+                ex.clear_partial_report(&data.db)?;
+                data.reports_worker.forget_partial_request(&name);
                 info!("report for the experiment {} generated successfully!", name);
 
                 let (regressed, fixed) = (
@@ -125,31 +307,73 @@ fn reports_thread(data: &Data) -> Fallible<()> {
     }
 }
 
+fn spawn_worker<F>(slot: &Mutex<Option<Thread>>, name: &'static str, worker: F)
+where
+    F: Fn() -> Fallible<()> + Send + 'static,
+{
+    let joiner = thread::spawn(move || loop {
+        let result = worker().with_context(|_| format!("the {} thread crashed", name));
+        if let Err(e) = result {
+            utils::report_failure(&e);
+        }
+    });
+    *slot.lock().unwrap_or_else(|e| e.into_inner()) = Some(joiner.thread().clone());
+}
+
+fn wake_worker(slot: &Mutex<Option<Thread>>, name: &str) {
+    let guard = slot.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(thread) = &*guard {
+        thread.unpark();
+    } else {
+        warn!("no {} to wake up!", name);
+    }
+}
+
 #[derive(Clone, Default)]
-pub struct ReportsWorker(Arc<Mutex<Option<Thread>>>);
+pub struct ReportsWorker {
+    thread: Arc<Mutex<Option<Thread>>>,
+    partial_thread: Arc<Mutex<Option<Thread>>>,
+    partial_requests: Arc<Mutex<HashSet<String>>>,
+}
 
 impl ReportsWorker {
     pub fn new() -> Self {
-        ReportsWorker(Arc::new(Mutex::new(None)))
+        ReportsWorker::default()
     }
 
     pub fn spawn(&self, data: Data) {
-        let joiner = thread::spawn(move || loop {
-            let result = reports_thread(&data.clone())
-                .with_context(|_| "the reports generator thread crashed");
-            if let Err(e) = result {
-                utils::report_failure(&e);
-            }
+        let partial_data = data.clone();
+        spawn_worker(&self.thread, "reports generator", move || {
+            reports_thread(&data)
         });
-        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some(joiner.thread().clone());
+        spawn_worker(
+            &self.partial_thread,
+            "partial reports generator",
+            move || partial_reports_thread(&partial_data),
+        );
     }
 
     pub fn wake(&self) {
-        let guard = self.0.lock().unwrap_or_else(|e| e.into_inner());
-        if let Some(thread) = &*guard {
-            thread.unpark();
-        } else {
-            warn!("no report generator to wake up!");
-        }
+        wake_worker(&self.thread, "report generator");
+    }
+
+    /// Queue a partial report of a running experiment, without waiting for the next periodic one.
+    pub fn request_partial_report(&self, ex: &str) {
+        self.partial_requests().insert(ex.to_string());
+        wake_worker(&self.partial_thread, "partial report generator");
+    }
+
+    fn take_partial_request(&self, ex: &str) -> bool {
+        self.partial_requests().remove(ex)
+    }
+
+    fn forget_partial_request(&self, ex: &str) {
+        self.partial_requests().remove(ex);
+    }
+
+    fn partial_requests(&self) -> MutexGuard<HashSet<String>> {
+        self.partial_requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
     }
 }
@@ -1,11 +1,13 @@
 use crate::agent::Capabilities;
 use crate::experiments::{Assignee, Experiment, Status};
 use crate::prelude::*;
-use crate::results::{DatabaseDB, EncodingType, ProgressData};
+use crate::results::{DatabaseDB, EncodingType, Environment, ProgressData};
 use crate::server::api_types::{AgentConfig, ApiResponse};
 use crate::server::auth::{auth_filter, AuthDetails, TokenType};
+use crate::server::eta::estimate_completion;
 use crate::server::messages::Message;
 use crate::server::{Data, HttpError};
+use chrono_humanize::{Accuracy, HumanTime, Tense};
 use failure::Compat;
 use http::{Response, StatusCode};
 use hyper::Body;
@@ -21,6 +23,11 @@ pub struct ExperimentData<T> {
     data: T,
 }
 
+#[derive(Deserialize)]
+struct EnvironmentData {
+    environment: Environment,
+}
+
 pub fn routes(
     data: Arc<Data>,
     mutex: Arc<Mutex<Data>>,
@@ -60,6 +67,14 @@ pub fn routes(
         .and(auth_filter(data.clone(), TokenType::Agent))
         .map(endpoint_record_progress);
 
+    let record_environment = warp::post2()
+        .and(warp::path("record-environment"))
+        .and(warp::path::end())
+        .and(warp::body::json())
+        .and(mutex_filter.clone())
+        .and(auth_filter(data.clone(), TokenType::Agent))
+        .map(endpoint_record_environment);
+
     let heartbeat = warp::post2()
         .and(warp::path("heartbeat"))
         .and(warp::path::end())
@@ -84,6 +99,8 @@ pub fn routes(
                 .unify()
                 .or(record_progress)
                 .unify()
+                .or(record_environment)
+                .unify()
                 .or(heartbeat)
                 .unify()
                 .or(error)
@@ -120,10 +137,18 @@ fn endpoint_next_experiment(
     let result = if let Some((new, ex)) = next {
         if new {
             if let Some(ref github_issue) = ex.github_issue {
+                let etas = estimate_completion(&data.db, &data.crate_durations)?;
+                let eta = match etas.get(&ex.name) {
+                    Some(eta) => format!(
+                        ", and should be completed {}",
+                        HumanTime::from(*eta).to_text_en(Accuracy::Rough, Tense::Future)
+                    ),
+                    None => String::new(),
+                };
                 Message::new()
                     .line(
                         "construction",
-                        format!("Experiment **`{}`** is now **running**", ex.name,),
+                        format!("Experiment **`{}`** is now **running**{}", ex.name, eta),
                     )
                     .send(&github_issue.api_url, &data)?;
             }
@@ -165,8 +190,8 @@ fn endpoint_record_progress(
     data.metrics
         .record_completed_jobs(&auth.name, &ex.name, result.data.results.len() as i64);
 
-    let db = DatabaseDB::new(&data.db);
-    db.store(&ex, &result.data, EncodingType::Gzip)?;
+    let db = DatabaseDB::with_log_store(&data.db, data.logs.clone());
+    db.store(&ex, &result.data, EncodingType::Zstd)?;
 
     let (completed, all) = ex.raw_progress(&data.db)?;
     if completed == all {
@@ -178,6 +203,30 @@ fn endpoint_record_progress(
     Ok(ApiResponse::Success { result: true }.into_response()?)
 }
 
+fn endpoint_record_environment(
+    env: ExperimentData<EnvironmentData>,
+    mutex: Arc<Mutex<Data>>,
+    auth: AuthDetails,
+) -> Fallible<Response<Body>> {
+    let data = mutex.lock().unwrap();
+    let ex = Experiment::get(&data.db, &env.experiment_name)?
+        .ok_or_else(|| err_msg("no experiment run by this agent"))?;
+
+    info!(
+        "received the environment of agent {} for experiment {}",
+        auth.name, ex.name,
+    );
+
+    let db = DatabaseDB::with_log_store(&data.db, data.logs.clone());
+    db.store_environment(
+        &ex,
+        &Assignee::Agent(auth.name).to_string(),
+        &env.data.environment,
+    )?;
+
+    Ok(ApiResponse::Success { result: true }.into_response()?)
+}
+
 fn endpoint_heartbeat(data: Arc<Data>, auth: AuthDetails) -> Fallible<Response<Body>> {
     if let Some(rev) = auth.git_revision {
         data.agents.set_git_revision(&auth.name, &rev)?;
@@ -0,0 +1,55 @@
+use crate::prelude::*;
+use crate::results::DatabaseDB;
+use crate::server::api_types::{ApiResponse, CrateHistoryResult};
+use crate::server::Data;
+use http::{Response, StatusCode};
+use hyper::Body;
+use std::sync::Arc;
+use warp::path::Tail;
+use warp::{self, Filter, Rejection};
+
+pub fn routes(
+    data: Arc<Data>,
+) -> impl Filter<Extract = (Response<Body>,), Error = Rejection> + Clone {
+    let data_filter = warp::any().map(move || data.clone());
+
+    let crate_history = warp::get2()
+        .and(warp::path("crate"))
+        .and(warp::path::tail())
+        .and(data_filter)
+        .map(endpoint_crate_history);
+
+    warp::any()
+        .and(crate_history)
+        .map(handle_results)
+        .recover(handle_errors)
+        .unify()
+}
+
+fn endpoint_crate_history(name: Tail, data: Arc<Data>) -> Fallible<Response<Body>> {
+    let result = DatabaseDB::with_log_store(&data.db, data.logs.clone())
+        .load_crate_history(name.as_str())?
+        .into_iter()
+        .map(CrateHistoryResult::from)
+        .collect::<Vec<_>>();
+
+    Ok(ApiResponse::Success { result }.into_response()?)
+}
+
+fn handle_results(resp: Fallible<Response<Body>>) -> Response<Body> {
+    match resp {
+        Ok(resp) => resp,
+        Err(err) => ApiResponse::internal_error(err.to_string())
+            .into_response()
+            .unwrap(),
+    }
+}
+
+fn handle_errors(err: Rejection) -> Result<Response<Body>, Rejection> {
+    match err.status() {
+        StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED => {
+            Ok(ApiResponse::not_found().into_response().unwrap())
+        }
+        _ => Err(err),
+    }
+}
@@ -35,6 +35,8 @@ fn endpoint_metrics(data: Arc<Data>) -> Fallible<Response<Body>> {
     )?;
 
     data.metrics.update_crates_lists(&data.db)?;
+    data.metrics
+        .update_experiment_etas(&data.db, &data.crate_durations)?;
 
     let mut buffer = Vec::new();
     let families = prometheus::gather();
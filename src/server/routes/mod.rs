@@ -1,4 +1,5 @@
 pub mod agent;
+pub mod api;
 pub mod metrics;
 pub mod ui;
 pub mod webhooks;
@@ -0,0 +1,71 @@
+use crate::prelude::*;
+use crate::report::{self, ResultName};
+use crate::results::{DatabaseDB, TestResult};
+use crate::server::api_types::CrateHistoryResult;
+use crate::server::routes::ui::{render_template, LayoutContext};
+use crate::server::Data;
+use chrono::SecondsFormat;
+use http::Response;
+use hyper::Body;
+use std::sync::Arc;
+use warp::path::Tail;
+
+#[derive(Serialize)]
+struct HistoryRow {
+    experiment: String,
+    date: String,
+    name: String,
+    url: String,
+    toolchain: String,
+    res: String,
+    res_class: &'static str,
+    log_url: Option<String>,
+}
+
+impl HistoryRow {
+    fn new(result: CrateHistoryResult) -> Self {
+        let res_class = match result.result {
+            TestResult::TestPass => "green",
+            TestResult::BrokenCrate(_)
+            | TestResult::BuildFail(_)
+            | TestResult::TestFail(_)
+            | TestResult::Error => "red",
+            TestResult::TestSkipped | TestResult::Skipped => "",
+        };
+
+        HistoryRow {
+            date: result.date.to_rfc3339_opts(SecondsFormat::Secs, true),
+            name: report::crate_to_name(&result.krate),
+            url: report::crate_to_url(&result.krate),
+            toolchain: result.toolchain.to_string(),
+            res: result.result.long_name(),
+            res_class,
+            log_url: result.log_url,
+            experiment: result.experiment,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct CrateHistoryContext {
+    layout: LayoutContext,
+    name: String,
+    results: Vec<HistoryRow>,
+}
+
+pub fn endpoint_crate_history(name: Tail, data: Arc<Data>) -> Fallible<Response<Body>> {
+    let results = DatabaseDB::with_log_store(&data.db, data.logs.clone())
+        .load_crate_history(name.as_str())?
+        .into_iter()
+        .map(|entry| HistoryRow::new(entry.into()))
+        .collect();
+
+    render_template(
+        "ui/crate-history.html",
+        &CrateHistoryContext {
+            layout: LayoutContext::new(),
+            name: name.as_str().to_string(),
+            results,
+        },
+    )
+}
@@ -1,13 +1,15 @@
 use crate::experiments::{Experiment, Mode, Status};
 use crate::prelude::*;
-use crate::report::ResultName;
+use crate::report::{report_base_url, ResultName};
+use crate::results::{environment_differences, DatabaseDB, Environment, ReadResults};
+use crate::server::eta::estimate_completion;
 use crate::server::routes::ui::{render_template, LayoutContext};
 use crate::server::{Data, HttpError};
 use chrono::{Duration, SecondsFormat, Utc};
 use chrono_humanize::{Accuracy, HumanTime, Tense};
 use http::Response;
 use hyper::Body;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
 #[derive(Serialize)]
@@ -20,10 +22,15 @@ struct ExperimentData {
     requirement: Option<String>,
     progress: u8,
     priority: i32,
+    eta: Option<String>,
 }
 
 impl ExperimentData {
-    fn new(data: &Data, experiment: &Experiment) -> Fallible<Self> {
+    fn new(
+        data: &Data,
+        experiment: &Experiment,
+        etas: &HashMap<String, Duration>,
+    ) -> Fallible<Self> {
         let (status_class, status_pretty, show_progress) = match experiment.status {
             Status::Queued => ("", "Queued", true),
             Status::Running => ("orange", "Running", true),
@@ -54,6 +61,9 @@ impl ExperimentData {
             } else {
                 100
             },
+            eta: etas
+                .get(&experiment.name)
+                .map(|eta| HumanTime::from(*eta).to_text_en(Accuracy::Rough, Tense::Present)),
         })
     }
 }
@@ -72,13 +82,14 @@ pub fn endpoint_queue(data: Arc<Data>) -> Fallible<Response<Body>> {
     let mut generating_report = Vec::new();
     let mut report_failed = Vec::new();
 
+    let etas = estimate_completion(&data.db, &data.crate_durations)?;
     for experiment in &Experiment::unfinished(&data.db)? {
         // Don't include completed experiments in the queue
         if experiment.status == Status::Completed {
             continue;
         }
 
-        let ex = ExperimentData::new(&data, &experiment)?;
+        let ex = ExperimentData::new(&data, &experiment, &etas)?;
 
         match experiment.status {
             Status::Queued => queued.push(ex),
@@ -115,6 +126,8 @@ struct ExperimentExt {
 
     github_url: Option<String>,
     report_url: Option<String>,
+    partial_report_url: Option<String>,
+    bug_reports_url: Option<String>,
 
     created_at: String,
     started_at: Option<String>,
@@ -126,6 +139,9 @@ struct ExperimentExt {
     duration: Option<String>,
     estimated_end: Option<String>,
     average_job_duration: Option<String>,
+
+    environments: BTreeMap<String, Environment>,
+    environment_differences: Vec<String>,
 }
 
 #[derive(Serialize)]
@@ -184,11 +200,27 @@ pub fn endpoint_experiment(name: String, data: Arc<Data>) -> Fallible<Response<B
             (None, None, None)
         };
 
+        // Prefer the queue-aware estimate, which accounts for the history of the crates
+        let etas = estimate_completion(&data.db, &data.crate_durations)?;
+        let estimated_end = etas
+            .get(&ex.name)
+            .map(|eta| HumanTime::from(*eta).to_text_en(Accuracy::Rough, Tense::Present))
+            .or(estimated_end);
+
+        let environments =
+            DatabaseDB::with_log_store(&data.db, data.logs.clone()).load_environments(&ex)?;
+        let partial_report_url = ex.last_partial_report(&data.db)?.map(|(url, _)| url);
+
         let experiment = ExperimentExt {
-            common: ExperimentData::new(&data, &ex)?,
+            common: ExperimentData::new(&data, &ex, &etas)?,
 
             github_url: ex.github_issue.map(|i| i.html_url),
             report_url: ex.report_url.clone(),
+            partial_report_url,
+            bug_reports_url: ex
+                .report_url
+                .as_ref()
+                .map(|url| format!("{}bug-reports/index.md", report_base_url(url))),
 
             created_at: ex.created_at.to_rfc3339_opts(SecondsFormat::Secs, true),
             started_at: ex
@@ -204,6 +236,9 @@ pub fn endpoint_experiment(name: String, data: Arc<Data>) -> Fallible<Response<B
             duration,
             estimated_end,
             average_job_duration,
+
+            environment_differences: environment_differences(&environments),
+            environments,
         };
 
         render_template(
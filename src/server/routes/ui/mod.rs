@@ -9,7 +9,12 @@ use std::sync::Arc;
 use warp::{self, Filter, Rejection};
 
 mod agents;
+mod crates;
 mod experiments;
+mod reports;
+mod triage;
+
+const TRIAGE_FORM_SIZE_LIMIT: u64 = 64 * 1024;
 
 #[derive(Serialize)]
 struct LayoutContext {
@@ -41,6 +46,40 @@ pub fn routes(
         .and(data_filter.clone())
         .map(experiments::endpoint_experiment);
 
+    let triage = warp::get2()
+        .and(warp::path("ex"))
+        .and(warp::path::param())
+        .and(warp::path("triage"))
+        .and(warp::path::end())
+        .and(warp::header::headers_cloned())
+        .and(data_filter.clone())
+        .map(triage::endpoint_triage);
+
+    let triage_update = warp::post2()
+        .and(warp::path("ex"))
+        .and(warp::path::param())
+        .and(warp::path("triage"))
+        .and(warp::path::end())
+        .and(warp::header::headers_cloned())
+        .and(warp::body::content_length_limit(TRIAGE_FORM_SIZE_LIMIT))
+        .and(warp::body::form())
+        .and(data_filter.clone())
+        .map(triage::endpoint_triage_update);
+
+    let reports = warp::get2()
+        .and(warp::path("reports"))
+        .and(warp::path::param())
+        .and(warp::path::tail())
+        .and(warp::path::full())
+        .and(data_filter.clone())
+        .map(reports::endpoint_report_file);
+
+    let crate_history = warp::get2()
+        .and(warp::path("crate"))
+        .and(warp::path::tail())
+        .and(data_filter.clone())
+        .map(crates::endpoint_crate_history);
+
     let agents = warp::get2()
         .and(warp::path("agents"))
         .and(warp::path::end())
@@ -58,6 +97,14 @@ pub fn routes(
             queue
                 .or(experiment)
                 .unify()
+                .or(triage)
+                .unify()
+                .or(triage_update)
+                .unify()
+                .or(reports)
+                .unify()
+                .or(crate_history)
+                .unify()
                 .or(agents)
                 .unify()
                 .or(assets)
@@ -0,0 +1,130 @@
+use crate::config::ReportStorageConfig;
+use crate::prelude::*;
+use crate::report::encoded_path;
+use crate::results::EncodingType;
+use crate::server::{Data, HttpError};
+use http::header::{HeaderValue, CONTENT_ENCODING, CONTENT_TYPE, LOCATION};
+use http::{Response, StatusCode};
+use hyper::Body;
+use mime::{self, Mime};
+use percent_encoding::percent_decode_str;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use warp::path::{FullPath, Tail};
+
+fn file_mime(path: &Path) -> Mime {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("html") => mime::TEXT_HTML_UTF_8,
+        Some("css") => mime::TEXT_CSS_UTF_8,
+        Some("js") => mime::APPLICATION_JAVASCRIPT_UTF_8,
+        Some("json") => mime::APPLICATION_JSON,
+        Some("csv") => mime::TEXT_CSV_UTF_8,
+        Some("xml") => mime::TEXT_XML,
+        Some("gz") => "application/gzip".parse().unwrap(),
+        _ => mime::TEXT_PLAIN_UTF_8,
+    }
+}
+
+/// Find the file of the report matching the requested path, along with its encoding. Paths
+/// escaping the reports directory are rejected.
+fn find_report_file(root: &Path, path: &str) -> Option<(PathBuf, EncodingType)> {
+    let mut relative = PathBuf::from(percent_decode_str(path).decode_utf8().ok()?.as_ref());
+    if path.is_empty() || path.ends_with('/') {
+        relative.push("index.html");
+    }
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+
+    // Browsers can't decode zstd, so FileWriter never writes it
+    [EncodingType::Plain, EncodingType::Gzip]
+        .iter()
+        .map(|&encoding| (root.join(encoded_path(&relative, encoding)), encoding))
+        .find(|(path, _)| path.is_file())
+}
+
+pub fn endpoint_report_file(
+    ex: String,
+    tail: Tail,
+    full_path: FullPath,
+    data: Arc<Data>,
+) -> Fallible<Response<Body>> {
+    let root = match data.config.report_storage {
+        ReportStorageConfig::Filesystem { ref path, .. } => path,
+        ReportStorageConfig::S3 => return Err(HttpError::NotFound.into()),
+    };
+
+    // Relative links in the reports only work with a trailing slash
+    if tail.as_str().is_empty() && !full_path.as_str().ends_with('/') {
+        let mut resp = Response::new(Body::empty());
+        *resp.status_mut() = StatusCode::MOVED_PERMANENTLY;
+        resp.headers_mut().insert(
+            LOCATION,
+            HeaderValue::from_str(&format!("{}/", full_path.as_str()))?,
+        );
+        return Ok(resp);
+    }
+
+    let (path, encoding) =
+        find_report_file(root, &format!("{}/{}", ex, tail.as_str())).ok_or(HttpError::NotFound)?;
+
+    let mut resp = Response::new(fs::read(&path)?.into());
+    // Encoded files are typed after the extension they had before being encoded
+    let mime = match encoding {
+        EncodingType::Plain => file_mime(&path),
+        _ => file_mime(&path.with_extension("")),
+    };
+    resp.headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_str(mime.as_ref())?);
+    if encoding != EncodingType::Plain {
+        resp.headers_mut().insert(
+            CONTENT_ENCODING,
+            HeaderValue::from_static(encoding.to_str()),
+        );
+    }
+    Ok(resp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_report_file;
+    use crate::results::EncodingType;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_find_report_file() {
+        let root = tempdir().unwrap();
+        fs::write(root.path().join("index.html"), "report").unwrap();
+        fs::create_dir_all(root.path().join("beta/reg/foo-1.0.0")).unwrap();
+        fs::write(root.path().join("beta/reg/foo-1.0.0/log.txt.gz"), "log").unwrap();
+        fs::create_dir_all(root.path().join("try#1234/reg/foo-1.0.0")).unwrap();
+        fs::write(root.path().join("try#1234/reg/foo-1.0.0/log.txt"), "log").unwrap();
+
+        assert_eq!(
+            find_report_file(root.path(), ""),
+            Some((root.path().join("index.html"), EncodingType::Plain))
+        );
+        assert_eq!(
+            find_report_file(root.path(), "beta/reg/foo-1.0.0/log.txt"),
+            Some((
+                root.path().join("beta/reg/foo-1.0.0/log.txt.gz"),
+                EncodingType::Gzip
+            ))
+        );
+        assert_eq!(
+            find_report_file(root.path(), "try%231234/reg/foo-1.0.0/log.txt"),
+            Some((
+                root.path().join("try#1234/reg/foo-1.0.0/log.txt"),
+                EncodingType::Plain
+            ))
+        );
+        assert_eq!(find_report_file(root.path(), "missing.html"), None);
+        assert_eq!(find_report_file(root.path(), "../index.html"), None);
+        assert_eq!(find_report_file(root.path(), "%2E%2E/index.html"), None);
+    }
+}
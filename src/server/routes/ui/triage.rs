@@ -0,0 +1,271 @@
+use crate::crates::Crate;
+use crate::db::QueryUtils;
+use crate::experiments::Experiment;
+use crate::prelude::*;
+use crate::report::{self, Comparison};
+use crate::results::{DatabaseDB, ReadResults, Triage, TriageStatus};
+use crate::server::auth::check_acl_user;
+use crate::server::routes::ui::{render_template, LayoutContext};
+use crate::server::{Data, HttpError};
+use chrono::{SecondsFormat, Utc};
+use http::header::{HeaderMap, HeaderValue, HOST, LOCATION, ORIGIN, REFERER, WWW_AUTHENTICATE};
+use http::{Response, StatusCode};
+use hyper::Body;
+use std::sync::Arc;
+use url::Url;
+
+#[derive(Serialize)]
+struct TriageRow {
+    krate: String,
+    name: String,
+    url: String,
+    res: Comparison,
+    status: &'static str,
+    note: String,
+    link: String,
+    updated_by: Option<String>,
+    updated_at: Option<String>,
+    carried_over_from: Option<String>,
+}
+
+#[derive(Serialize)]
+struct TriageContext {
+    layout: LayoutContext,
+    experiment: String,
+    statuses: &'static [&'static str],
+    crates: Vec<TriageRow>,
+}
+
+#[derive(Deserialize)]
+pub struct TriageForm {
+    #[serde(rename = "crate")]
+    krate: String,
+    status: TriageStatus,
+    note: String,
+    link: String,
+}
+
+fn error_response(status: StatusCode) -> Response<Body> {
+    let mut resp = Response::new(format!("{}\n", status).into());
+    *resp.status_mut() = status;
+    resp
+}
+
+fn unauthorized() -> Fallible<Response<Body>> {
+    let mut resp = error_response(StatusCode::UNAUTHORIZED);
+    resp.headers_mut().insert(
+        WWW_AUTHENTICATE,
+        HeaderValue::from_static("Basic realm=\"crater\""),
+    );
+    Ok(resp)
+}
+
+/// Browsers also send the credentials of basic authentication with requests coming from other
+/// websites, so forms submitted from another origin are rejected. Browsers not sending the
+/// `Origin` header send the `Referer` instead, and requests with neither are rejected too.
+fn same_origin(headers: &HeaderMap) -> bool {
+    let host = match headers.get(HOST).and_then(|host| host.to_str().ok()) {
+        Some(host) => host,
+        None => return false,
+    };
+    let source = match headers
+        .get(ORIGIN)
+        .or_else(|| headers.get(REFERER))
+        .and_then(|source| source.to_str().ok())
+        .and_then(|source| Url::parse(source).ok())
+    {
+        Some(source) => source,
+        None => return false,
+    };
+
+    match (source.host_str(), source.port()) {
+        (Some(source_host), Some(port)) => format!("{}:{}", source_host, port) == host,
+        (Some(source_host), None) => source_host == host,
+        (None, _) => false,
+    }
+}
+
+pub fn endpoint_triage(
+    name: String,
+    headers: HeaderMap,
+    data: Arc<Data>,
+) -> Fallible<Response<Body>> {
+    let ex = Experiment::get(&data.db, &name)?.ok_or(HttpError::NotFound)?;
+    if check_acl_user(&data, &headers)?.is_none() {
+        return unauthorized();
+    }
+
+    let db = DatabaseDB::with_log_store(&data.db, data.logs.clone());
+    let mut triage = db.load_triage(&ex)?;
+
+    let mut crates = Vec::new();
+    for (krate, runs) in db.load_all_results(&ex)? {
+        let res = report::compare(
+            &data.config,
+            ex.mode,
+            &krate,
+            runs[0].as_ref(),
+            runs[1].as_ref(),
+        );
+        let entry = triage.remove(&krate);
+        // Crates not regressed anymore are still shown if they were triaged
+        if res == Comparison::Regressed || entry.is_some() {
+            crates.push((krate, res, entry));
+        }
+    }
+    crates.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let crates = crates
+        .into_iter()
+        .map(|(krate, res, entry)| TriageRow {
+            krate: krate.id(),
+            name: report::crate_to_name(&krate),
+            url: report::crate_to_url(&krate),
+            res,
+            status: entry
+                .as_ref()
+                .map(|entry| entry.status)
+                .unwrap_or(TriageStatus::Unreviewed)
+                .to_str(),
+            note: entry
+                .as_ref()
+                .map(|entry| entry.note.clone())
+                .unwrap_or_default(),
+            link: entry
+                .as_ref()
+                .and_then(|entry| entry.link.clone())
+                .unwrap_or_default(),
+            updated_by: entry.as_ref().map(|entry| entry.updated_by.clone()),
+            updated_at: entry
+                .as_ref()
+                .map(|entry| entry.updated_at.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            carried_over_from: entry.and_then(|entry| entry.carried_over_from),
+        })
+        .collect();
+
+    render_template(
+        "ui/triage.html",
+        &TriageContext {
+            layout: LayoutContext::new(),
+            experiment: ex.name,
+            statuses: TriageStatus::possible_values(),
+            crates,
+        },
+    )
+}
+
+pub fn endpoint_triage_update(
+    name: String,
+    headers: HeaderMap,
+    form: TriageForm,
+    data: Arc<Data>,
+) -> Fallible<Response<Body>> {
+    let ex = Experiment::get(&data.db, &name)?.ok_or(HttpError::NotFound)?;
+    if !same_origin(&headers) {
+        return Ok(error_response(StatusCode::FORBIDDEN));
+    }
+    let username = match check_acl_user(&data, &headers)? {
+        Some(username) => username,
+        None => return unauthorized(),
+    };
+
+    let krate: Crate = form.krate.parse()?;
+    if !data.db.exists(
+        "SELECT 1 FROM experiment_crates WHERE experiment = ?1 AND crate = ?2;",
+        &[&ex.name, &krate.id()],
+    )? {
+        return Err(HttpError::NotFound.into());
+    }
+
+    let link = form.link.trim();
+    DatabaseDB::with_log_store(&data.db, data.logs.clone()).store_triage(
+        &ex,
+        &krate,
+        &Triage {
+            status: form.status,
+            note: form.note.trim().to_string(),
+            link: if link.is_empty() {
+                None
+            } else {
+                Some(link.to_string())
+            },
+            updated_by: username,
+            updated_at: Utc::now(),
+            carried_over_from: None,
+        },
+    )?;
+
+    let mut resp = Response::new(Body::empty());
+    *resp.status_mut() = StatusCode::SEE_OTHER;
+    resp.headers_mut().insert(
+        LOCATION,
+        HeaderValue::from_str(&format!("/ex/{}/triage", ex.name))?,
+    );
+    Ok(resp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::same_origin;
+    use http::header::{HeaderMap, HeaderValue, HOST, ORIGIN, REFERER};
+
+    #[test]
+    fn test_same_origin() {
+        let headers =
+            |host: &'static str, origin: Option<&'static str>, referer: Option<&'static str>| {
+                let mut headers = HeaderMap::new();
+                headers.insert(HOST, HeaderValue::from_static(host));
+                if let Some(origin) = origin {
+                    headers.insert(ORIGIN, HeaderValue::from_static(origin));
+                }
+                if let Some(referer) = referer {
+                    headers.insert(REFERER, HeaderValue::from_static(referer));
+                }
+                headers
+            };
+        let crater = "crater.rust-lang.org";
+
+        assert!(same_origin(&headers(
+            crater,
+            Some("https://crater.rust-lang.org"),
+            None
+        )));
+        assert!(!same_origin(&headers(
+            crater,
+            Some("https://example.com"),
+            None
+        )));
+        assert!(!same_origin(&headers(crater, Some("null"), None)));
+        assert!(same_origin(&headers(
+            "localhost:8000",
+            Some("http://localhost:8000"),
+            None
+        )));
+        assert!(!same_origin(&headers(
+            "localhost:8000",
+            Some("http://localhost:9000"),
+            None
+        )));
+
+        // The referer is used when the origin is missing
+        assert!(same_origin(&headers(
+            crater,
+            None,
+            Some("https://crater.rust-lang.org/ex/foo/triage")
+        )));
+        assert!(!same_origin(&headers(
+            crater,
+            None,
+            Some("https://example.com/crater.rust-lang.org")
+        )));
+        // The origin takes precedence over the referer
+        assert!(!same_origin(&headers(
+            crater,
+            Some("https://example.com"),
+            Some("https://crater.rust-lang.org/ex/foo/triage")
+        )));
+
+        // Requests with neither are rejected
+        assert!(!same_origin(&headers(crater, None, None)));
+    }
+}
@@ -138,6 +138,10 @@ generate_parser!(pub enum Command {
         name: Option<String> = "name",
     })
 
+    "partial-report" => PartialReport(PartialReportArgs {
+        name: Option<String> = "name",
+    })
+
     "retry" => Retry(RetryArgs {
         name: Option<String> = "name",
     })
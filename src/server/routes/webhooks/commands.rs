@@ -5,7 +5,7 @@ use crate::prelude::*;
 use crate::server::github::{GitHub, Issue, Repository};
 use crate::server::messages::{Label, Message};
 use crate::server::routes::webhooks::args::{
-    AbortArgs, CheckArgs, EditArgs, RetryArgs, RetryReportArgs, RunArgs,
+    AbortArgs, CheckArgs, EditArgs, PartialReportArgs, RetryArgs, RetryReportArgs, RunArgs,
 };
 use crate::server::Data;
 use crate::toolchain::Toolchain;
@@ -213,6 +213,29 @@ pub fn retry_report(data: &Data, issue: &Issue, args: RetryReportArgs) -> Fallib
     }
 }
 
+pub fn partial_report(data: &Data, issue: &Issue, args: PartialReportArgs) -> Fallible<()> {
+    let name = get_name(&data.db, issue, args.name)?;
+
+    if let Some(experiment) = Experiment::get(&data.db, &name)? {
+        if experiment.status != Status::Running {
+            bail!("experiment **`{}`** is not running!", name);
+        }
+
+        data.reports_worker.request_partial_report(&name);
+
+        Message::new()
+            .line(
+                "hourglass",
+                format!("Generation of a partial report for **`{}`** queued.", name),
+            )
+            .send(&issue.url, data)?;
+
+        Ok(())
+    } else {
+        bail!("an experiment named **`{}`** doesn't exist!", name);
+    }
+}
+
 pub fn retry(data: &Data, issue: &Issue, args: RetryArgs) -> Fallible<()> {
     let name = get_name(&data.db, issue, args.name)?;
 
@@ -131,6 +131,10 @@ fn process_command(
                 commands::retry_report(data, issue, args)?;
             }
 
+            Command::PartialReport(args) => {
+                commands::partial_report(data, issue, args)?;
+            }
+
             Command::Retry(args) => {
                 commands::retry(data, issue, args)?;
             }
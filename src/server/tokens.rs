@@ -53,7 +53,9 @@ impl ReportsBucket {
 #[serde(rename_all = "kebab-case")]
 pub struct Tokens {
     pub bot: BotTokens,
-    pub reports_bucket: ReportsBucket,
+    /// Only needed when the reports are published to S3, see `[report-storage]` in `config.toml`.
+    #[serde(default)]
+    pub reports_bucket: Option<ReportsBucket>,
     pub agents: HashMap<String, String>,
 }
 
@@ -65,7 +67,7 @@ impl Default for Tokens {
                 webhooks_secret: String::new(),
                 api_token: String::new(),
             },
-            reports_bucket: ReportsBucket {
+            reports_bucket: Some(ReportsBucket {
                 region: BucketRegion::S3 {
                     region: "us-west-1".to_string(),
                 },
@@ -73,7 +75,7 @@ impl Default for Tokens {
                 public_url: String::new(),
                 access_key: String::new(),
                 secret_key: String::new(),
-            },
+            }),
             agents: HashMap::new(),
         }
     }
@@ -0,0 +1,90 @@
+/// Whether the string is a pattern, where `*` matches any sequence of characters.
+pub(crate) fn is_pattern(input: &str) -> bool {
+    input.contains('*')
+}
+
+/// How specific the pattern is, used to pick the most specific one when multiple patterns match
+/// the same string.
+pub(crate) fn specificity(pattern: &str) -> usize {
+    pattern.chars().filter(|&c| c != '*').count()
+}
+
+pub(crate) fn matches(pattern: &str, input: &str) -> bool {
+    Pattern::new(pattern).matches(input)
+}
+
+/// A pattern split on its `*` once, to be matched against many strings.
+#[derive(Clone, Debug)]
+pub(crate) struct Pattern {
+    pattern: String,
+    /// The literal parts between the `*`, there's always at least one even for an empty pattern.
+    parts: Vec<String>,
+}
+
+impl Pattern {
+    pub(crate) fn new(pattern: &str) -> Self {
+        Pattern {
+            pattern: pattern.into(),
+            parts: pattern.split('*').map(String::from).collect(),
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    pub(crate) fn specificity(&self) -> usize {
+        specificity(&self.pattern)
+    }
+
+    pub(crate) fn matches(&self, input: &str) -> bool {
+        let (first, rest) = self.parts.split_first().unwrap();
+        if !input.starts_with(first.as_str()) {
+            return false;
+        }
+        let mut input = &input[first.len()..];
+
+        let (last, middle) = match rest.split_last() {
+            Some(split) => split,
+            // The pattern doesn't contain any `*`
+            None => return input.is_empty(),
+        };
+
+        for part in middle {
+            match input.find(part.as_str()) {
+                Some(idx) => input = &input[idx + part.len()..],
+                None => return false,
+            }
+        }
+        input.ends_with(last.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_pattern, matches, specificity};
+
+    #[test]
+    fn test_matches() {
+        assert!(!is_pattern("lazy_static"));
+        assert!(is_pattern("*-sys"));
+
+        assert!(matches("lazy_static", "lazy_static"));
+        assert!(!matches("lazy_static", "lazy_static2"));
+
+        assert!(matches("*-sys", "openssl-sys"));
+        assert!(!matches("*-sys", "openssl-sys2"));
+        assert!(matches("rust-lang/*", "rust-lang/rust"));
+        assert!(!matches("rust-lang/*", "rust-lang-nursery/rustfmt"));
+        assert!(matches("*", ""));
+        assert!(matches("a*b*c", "abc"));
+        assert!(matches("a*b*c", "aXbYbZc"));
+        assert!(!matches("a*b*c", "aXcYb"));
+        // Parts of the pattern can't overlap
+        assert!(!matches("ab*ba", "aba"));
+
+        assert_eq!(specificity("*"), 0);
+        assert_eq!(specificity("*-sys"), 4);
+        assert_eq!(specificity("rust-lang/*"), 10);
+    }
+}
@@ -10,6 +10,7 @@ pub(crate) mod http;
 #[macro_use]
 mod macros;
 pub(crate) mod disk_usage;
+pub(crate) mod glob;
 pub(crate) mod path;
 pub(crate) mod serialize;
 pub mod size;
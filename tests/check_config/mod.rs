@@ -55,3 +55,70 @@ fn test_bad_config_missing_repo() {
         .code(1)
         .stderr(contains("GitHub repo `ghost/missing-repo` is missing"));
 }
+
+#[test]
+fn test_bad_config_classifier_regex() {
+    Command::crater()
+        .args(&[
+            "check-config",
+            "tests/check_config/bad-classifier-regex.toml",
+        ])
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(contains(
+            "classifier regex `could not find system library (` is invalid",
+        ));
+}
+
+#[test]
+fn test_bad_config_log_storage_prefix() {
+    Command::crater()
+        .args(&[
+            "check-config",
+            "tests/check_config/bad-log-storage-prefix.toml",
+        ])
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(contains(
+            "log storage prefix `https://crater-logs/logs` is invalid",
+        ));
+}
+
+#[test]
+fn test_bad_config_git_repo_url() {
+    Command::crater()
+        .args(&["check-config", "tests/check_config/bad-git-repo-url.toml"])
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(contains("git repo `not a url` is not a valid URL"));
+}
+
+#[test]
+fn test_bad_config_path_crate() {
+    Command::crater()
+        .args(&["check-config", "tests/check_config/bad-path-crate.toml"])
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(contains(
+            "path crate `relative/path` is not an absolute path",
+        ));
+}
+
+#[test]
+fn test_bad_config_unmatched_pattern() {
+    Command::crater()
+        .args(&[
+            "check-config",
+            "tests/check_config/bad-unmatched-pattern.toml",
+        ])
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(contains(
+            "pattern `crater_missing_*` doesn't match any crate",
+        ));
+}